@@ -0,0 +1,265 @@
+use crate::commands::AppSettings;
+use crate::note_manager::{self, read_file_with_encoding};
+use crate::utils::validate_path_security;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Check an attachment against the size limit, extension allow-list and
+/// (when safe attachments are enabled) executable sniffing from the
+/// settings. Returns the detected content type.
+pub fn validate_attachment(
+    filename: &str,
+    data: &[u8],
+    settings: &AppSettings,
+) -> Result<&'static str, String> {
+    if let Some(max_mb) = settings.max_attachment_mb {
+        let max_bytes = max_mb.saturating_mul(1024 * 1024);
+        if data.len() as u64 > max_bytes {
+            return Err(format!(
+                "Attachment '{filename}' is {:.1} MB, which exceeds the {max_mb} MB limit",
+                data.len() as f64 / (1024.0 * 1024.0)
+            ));
+        }
+    }
+
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+
+    if let Some(allowed) = &settings.allowed_attachment_extensions {
+        let is_allowed = allowed
+            .iter()
+            .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(&extension));
+        if !is_allowed {
+            return Err(format!(
+                "Attachments of type '.{extension}' are not allowed"
+            ));
+        }
+    }
+
+    if settings.safe_attachments && is_executable(data) {
+        return Err(format!(
+            "Attachment '{filename}' looks like an executable and was rejected"
+        ));
+    }
+
+    Ok(detect_content_type(&extension, data))
+}
+
+/// Native executables and scripts with a shebang line
+fn is_executable(data: &[u8]) -> bool {
+    const SIGNATURES: [&[u8]; 7] = [
+        b"MZ",               // Windows PE
+        b"\x7fELF",          // Linux ELF
+        b"\xfe\xed\xfa\xce", // Mach-O 32-bit
+        b"\xfe\xed\xfa\xcf", // Mach-O 64-bit
+        b"\xce\xfa\xed\xfe", // Mach-O 32-bit, little endian
+        b"\xcf\xfa\xed\xfe", // Mach-O 64-bit, little endian
+        b"#!",               // Shell scripts
+    ];
+    SIGNATURES
+        .iter()
+        .any(|signature| data.starts_with(signature))
+}
+
+/// Content type from the file's magic bytes, falling back to its extension
+pub fn detect_content_type(extension: &str, data: &[u8]) -> &'static str {
+    const MAGIC: [(&[u8], &str); 7] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"MZ", "application/x-msdownload"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+    if let Some((_, content_type)) = MAGIC.iter().find(|(magic, _)| data.starts_with(magic)) {
+        // Office documents are zip archives underneath
+        if *content_type == "application/zip" {
+            if let Some(office_type) = content_type_for_extension(extension) {
+                return office_type;
+            }
+        }
+        return content_type;
+    }
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    content_type_for_extension(extension).unwrap_or(if std::str::from_utf8(data).is_ok() {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    })
+}
+
+fn content_type_for_extension(extension: &str) -> Option<&'static str> {
+    let content_type = match extension {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "txt" => "text/plain",
+        "json" => "application/json",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        _ => return None,
+    };
+    Some(content_type)
+}
+
+/// Folders holding images and attachments saved next to notes
+const ASSET_FOLDERS: [&str; 2] = ["images", "attachments"];
+
+/// Asset files under `notes_dir` that no note references. References are
+/// resolved relative to the folder of the note that contains them.
+pub fn find_unreferenced_assets(notes_dir: &str) -> Result<Vec<String>, String> {
+    let link_target = regex::Regex::new(
+        r#"\]\(\s*<?([^)>]+?)>?(?:\s+"[^"]*")?\s*\)|\[\[([^\]|#]+)|(?:src|href)\s*=\s*["']([^"']+)["']"#,
+    )
+    .unwrap();
+
+    let mut referenced = HashSet::new();
+    for note in note_manager::list_notes(notes_dir)? {
+        let content = match read_file_with_encoding(&note.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let note_dir = Path::new(&note.path).parent().unwrap_or(Path::new(""));
+
+        for caps in link_target.captures_iter(&content) {
+            let target = match caps.get(1).or(caps.get(2)).or(caps.get(3)) {
+                Some(target) => percent_decode(target.as_str().trim()),
+                None => continue,
+            };
+            if target.contains("://") || !is_in_asset_folder(Path::new(&target)) {
+                continue;
+            }
+            referenced.insert(normalize_path(&note_dir.join(&target)));
+        }
+    }
+
+    let notes_root = Path::new(notes_dir);
+    let mut unreferenced = Vec::new();
+    for entry in WalkDir::new(notes_root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = match entry.path().strip_prefix(notes_root) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let is_internal = relative.components().any(|component| {
+            component.as_os_str() == ".plainflux" || component.as_os_str() == ".git"
+        });
+        if is_internal || !is_in_asset_folder(relative) {
+            continue;
+        }
+        if !referenced.contains(&normalize_path(entry.path())) {
+            unreferenced.push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    unreferenced.sort();
+    Ok(unreferenced)
+}
+
+/// Delete the given asset files, refusing anything outside the notes
+/// directory or outside an asset folder. Returns the number deleted.
+pub fn delete_assets(paths: &[String], notes_dir: &str) -> Result<usize, String> {
+    let notes_root = normalize_path(Path::new(notes_dir));
+
+    // Check every path before deleting anything
+    for path in paths {
+        validate_path_security(path, notes_dir).map_err(|e| e.to_string())?;
+
+        let normalized = normalize_path(Path::new(path));
+        let relative = normalized
+            .strip_prefix(&notes_root)
+            .map_err(|_| format!("'{path}' is outside the notes directory"))?;
+        if !is_in_asset_folder(relative) || !normalized.is_file() {
+            return Err(format!("'{path}' is not an image or attachment"));
+        }
+    }
+
+    for path in paths {
+        fs::remove_file(path).map_err(|e| format!("Failed to delete '{path}': {e}"))?;
+    }
+
+    Ok(paths.len())
+}
+
+fn is_in_asset_folder(path: &Path) -> bool {
+    path.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            ASSET_FOLDERS
+                .iter()
+                .any(|folder| component.as_os_str() == *folder)
+        })
+    })
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Escape a relative path for use as a markdown link target, keeping `/`
+/// separators and non-ASCII text readable
+pub(crate) fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        if !c.is_ascii() || c.is_ascii_alphanumeric() || "-_.~/!$&'*+,;=:@".contains(c) {
+            encoded.push(c);
+        } else {
+            encoded.push_str(&format!("%{:02X}", c as u8));
+        }
+    }
+    encoded
+}
+
+/// Decode `%20`-style escapes used for spaces in markdown link targets
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
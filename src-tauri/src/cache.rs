@@ -1,6 +1,8 @@
 use regex::Regex;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
 use walkdir::WalkDir;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,18 +18,44 @@ pub struct Tag {
     pub note_path: String,
 }
 
+pub const BLOCK_TYPE_HEADING: &str = "heading";
+pub const BLOCK_TYPE_PARAGRAPH: &str = "paragraph";
+
+/// A referenceable block: a heading (id is its slug) or a paragraph marked
+/// with a trailing `^id` (id stored with the caret, as written in links)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Block {
+    pub block_id: String,
+    pub line_number: i32,
+    pub content: String,
+    pub block_type: String,
+    pub level: i32, // heading depth, 0 for paragraph blocks
+}
+
+/// A search hit with an excerpt highlighted by the FTS index
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FtsSnippet {
+    pub path: String,
+    pub title: String,
+    pub snippet: String,
+    pub score: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Todo {
     pub id: i32,
     pub note_path: String,
     pub line_number: i32,
     pub content: String,
+    pub clean_content: String, // content with due/scheduled/priority/recurrence markers removed
     pub is_completed: bool,
     pub due_date: Option<String>, // ISO 8601 date string (YYYY-MM-DD)
+    pub scheduled_date: Option<String>, // ISO 8601 date the task is planned to start
     pub priority: Option<String>, // "high", "medium", "low"
     pub indent_level: i32,        // Indentation level (0 = root, 1+ = nested)
     pub parent_line: Option<i32>, // Line number of parent todo (if nested)
     pub recurrence_pattern: Option<String>, // Recurrence pattern (e.g., "daily", "weekly", "every:monday")
+    pub links: Vec<String>, // Resolved paths of notes linked with [[...]] in the todo
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,6 +71,28 @@ pub struct Bookmark {
     pub path: Option<String>, // URL path for deeper grouping
     pub created_at: String,   // ISO 8601 timestamp
     pub tags: Option<String>, // Comma-separated tags
+    pub is_favorite: bool,
+    pub visit_count: i64, // times opened through open_url_external
+}
+
+const BOOKMARK_COLUMNS: &str = "id, url, title, description, note_path, line_number, domain, subdomain, path, created_at, tags, is_favorite, visit_count";
+
+fn bookmark_from_row(row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        note_path: row.get(4)?,
+        line_number: row.get(5)?,
+        domain: row.get(6)?,
+        subdomain: row.get(7)?,
+        path: row.get(8)?,
+        created_at: row.get(9)?,
+        tags: row.get(10)?,
+        is_favorite: row.get(11)?,
+        visit_count: row.get(12)?,
+    })
 }
 
 // Helper struct for extracted todo data (avoids type complexity)
@@ -55,8 +105,72 @@ type ExtractedTodo = (
     i32,
     Option<i32>,
     Option<String>,
+    Option<String>,
+    String,
+    Vec<String>,
 );
 
+// Longest window `get_todos_agenda` will build buckets for
+const MAX_AGENDA_DAYS: u32 = 366;
+
+// Column list matching `row_to_todo`
+const TODO_COLUMNS: &str = "id, note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern, scheduled_date, clean_content, links";
+
+fn row_to_todo(row: &rusqlite::Row) -> rusqlite::Result<Todo> {
+    Ok(Todo {
+        id: row.get(0)?,
+        note_path: row.get(1)?,
+        line_number: row.get(2)?,
+        content: row.get(3)?,
+        is_completed: row.get(4)?,
+        due_date: row.get(5)?,
+        priority: row.get(6)?,
+        indent_level: row.get(7)?,
+        parent_line: row.get(8)?,
+        recurrence_pattern: row.get(9)?,
+        scheduled_date: row.get(10)?,
+        clean_content: row.get(11)?,
+        links: serde_json::from_str(&row.get::<_, String>(12)?).unwrap_or_default(),
+    })
+}
+
+/// Tokenizers the full-text index can be built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTokenizer {
+    /// Words with English stemming, so "running" also finds "run"
+    #[default]
+    Porter,
+    /// Words in any language, matched as written
+    Unicode61,
+    /// Any run of three or more characters, for languages without spaces
+    /// between words and for substring search
+    Trigram,
+}
+
+impl SearchTokenizer {
+    /// The FTS5 `tokenize` option for this tokenizer
+    fn fts_option(self) -> &'static str {
+        match self {
+            SearchTokenizer::Porter => "porter unicode61",
+            SearchTokenizer::Unicode61 => "unicode61",
+            SearchTokenizer::Trigram => "trigram",
+        }
+    }
+}
+
+fn create_note_content_sql(table: &str, tokenizer: SearchTokenizer) -> String {
+    format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS {table} USING fts5(
+                note_path UNINDEXED,
+                title,
+                content,
+                tokenize = '{}'
+            )",
+        tokenizer.fts_option()
+    )
+}
+
 pub struct CacheDb {
     conn: Connection,
 }
@@ -66,11 +180,90 @@ impl CacheDb {
         let conn =
             Connection::open(db_path).map_err(|e| format!("Failed to open database: {e}"))?;
 
+        // WAL lets readers run alongside a writer; the busy timeout makes a
+        // second connection wait for a lock instead of failing straight away.
+        // In-memory databases stay in "memory" journal mode.
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|e| format!("Failed to enable WAL mode: {e}"))?;
+        conn.execute_batch("PRAGMA busy_timeout = 5000; PRAGMA cache_size = -16000;")
+            .map_err(|e| format!("Failed to configure database: {e}"))?;
+
         let db = CacheDb { conn };
         db.init_tables()?;
         Ok(db)
     }
 
+    /// Open the cache at `db_path`, starting over with an empty one if the
+    /// file is corrupted. Bookmarks and favorites live only in this database,
+    /// so the bad file is set aside as `<db>.corrupt-<timestamp>` rather than
+    /// deleted, and other failures (permissions, a lock) are returned as-is.
+    pub fn open_or_recreate(db_path: &str) -> Result<Self, String> {
+        if let Some(problem) = Self::find_corruption(db_path)? {
+            let quarantined = format!("{db_path}.corrupt-{}", chrono::Utc::now().timestamp());
+            eprintln!(
+                "Warning: Cache database at '{db_path}' is corrupted ({problem}); moving it to '{quarantined}'"
+            );
+            for suffix in ["", "-wal", "-shm"] {
+                let path = format!("{db_path}{suffix}");
+                match std::fs::rename(&path, format!("{quarantined}{suffix}")) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(format!("Failed to move corrupted cache '{path}': {e}")),
+                }
+            }
+        }
+        CacheDb::new(db_path)
+    }
+
+    /// What's wrong with the database at `db_path`, or `None` if it is
+    /// healthy or doesn't exist yet. Only SQLite's corruption errors and a
+    /// failed integrity check count; anything else is an error.
+    fn find_corruption(db_path: &str) -> Result<Option<String>, String> {
+        use rusqlite::ErrorCode;
+
+        let result = Connection::open(db_path).and_then(|conn| {
+            conn.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0))
+        });
+        match result {
+            Ok(result) if result == "ok" => Ok(None),
+            Ok(result) => Ok(Some(format!("integrity check failed: {result}"))),
+            Err(e)
+                if matches!(
+                    e.sqlite_error_code(),
+                    Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+                ) =>
+            {
+                Ok(Some(e.to_string()))
+            }
+            Err(e) => Err(format!("Failed to open cache database: {e}")),
+        }
+    }
+
+    /// Run `PRAGMA integrity_check`, failing with the first problem found
+    pub fn check_integrity(&self) -> Result<(), String> {
+        let result: String = self
+            .conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to check database integrity: {e}"))?;
+        if result == "ok" {
+            Ok(())
+        } else {
+            Err(format!("Database integrity check failed: {result}"))
+        }
+    }
+
+    /// File the cache database lives in
+    pub fn db_path(&self) -> Option<String> {
+        self.conn.path().map(str::to_string)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
     fn init_tables(&self) -> Result<(), String> {
         self.conn
             .execute(
@@ -127,6 +320,9 @@ impl CacheDb {
                 indent_level INTEGER NOT NULL DEFAULT 0,
                 parent_line INTEGER,
                 recurrence_pattern TEXT,
+                scheduled_date TEXT,
+                clean_content TEXT NOT NULL DEFAULT '',
+                links TEXT NOT NULL DEFAULT '[]',
                 UNIQUE(note_path, line_number)
             )",
                 [],
@@ -150,6 +346,25 @@ impl CacheDb {
         let _ = self
             .conn
             .execute("ALTER TABLE todos ADD COLUMN recurrence_pattern TEXT", []);
+        // Rows cached before these columns existed need their notes re-indexed
+        let mut needs_reindex = self
+            .conn
+            .execute("ALTER TABLE todos ADD COLUMN scheduled_date TEXT", [])
+            .is_ok();
+        needs_reindex |= self
+            .conn
+            .execute(
+                "ALTER TABLE todos ADD COLUMN clean_content TEXT NOT NULL DEFAULT ''",
+                [],
+            )
+            .is_ok();
+        needs_reindex |= self
+            .conn
+            .execute(
+                "ALTER TABLE todos ADD COLUMN links TEXT NOT NULL DEFAULT '[]'",
+                [],
+            )
+            .is_ok();
 
         self.conn
             .execute(
@@ -182,12 +397,7 @@ impl CacheDb {
         // Create FTS5 virtual table for full-text search
         self.conn
             .execute(
-                "CREATE VIRTUAL TABLE IF NOT EXISTS note_content USING fts5(
-                note_path UNINDEXED,
-                title,
-                content,
-                tokenize = 'porter unicode61'
-            )",
+                &create_note_content_sql("note_content", SearchTokenizer::default()),
                 [],
             )
             .map_err(|e| format!("Failed to create FTS5 table: {e}"))?;
@@ -207,6 +417,17 @@ impl CacheDb {
             )
             .map_err(|e| format!("Failed to create blocks table: {e}"))?;
 
+        // 'heading' blocks are slugged headings, 'paragraph' blocks carry an explicit ^id
+        let _ = self.conn.execute(
+            "ALTER TABLE blocks ADD COLUMN block_type TEXT NOT NULL DEFAULT 'heading'",
+            [],
+        );
+        // Heading depth (number of '#'); 0 for paragraph blocks
+        let _ = self.conn.execute(
+            "ALTER TABLE blocks ADD COLUMN level INTEGER NOT NULL DEFAULT 1",
+            [],
+        );
+
         self.conn
             .execute(
                 "CREATE INDEX IF NOT EXISTS idx_blocks_note ON blocks(note_path)",
@@ -242,6 +463,15 @@ impl CacheDb {
             )
             .map_err(|e| format!("Failed to create bookmarks table: {e}"))?;
 
+        let _ = self.conn.execute(
+            "ALTER TABLE bookmarks ADD COLUMN is_favorite BOOLEAN NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = self.conn.execute(
+            "ALTER TABLE bookmarks ADD COLUMN visit_count INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
         self.conn
             .execute(
                 "CREATE INDEX IF NOT EXISTS idx_bookmarks_url ON bookmarks(url)",
@@ -282,6 +512,53 @@ impl CacheDb {
             )
             .map_err(|e| format!("Failed to create note_metadata table: {e}"))?;
 
+        // Create aliases table for frontmatter `aliases:` lookups. Notes
+        // indexed before it existed need re-indexing to fill it.
+        needs_reindex |= !self
+            .conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'aliases')",
+                [],
+                |row| row.get::<_, bool>(0),
+            )
+            .map_err(|e| format!("Failed to check for aliases table: {e}"))?;
+        self.conn
+            .execute(
+                "CREATE TABLE IF NOT EXISTS aliases (
+                id INTEGER PRIMARY KEY,
+                alias TEXT NOT NULL,
+                note_path TEXT NOT NULL,
+                UNIQUE(alias, note_path)
+            )",
+                [],
+            )
+            .map_err(|e| format!("Failed to create aliases table: {e}"))?;
+
+        self.conn
+            .execute(
+                "CREATE INDEX IF NOT EXISTS idx_aliases_alias ON aliases(alias COLLATE NOCASE)",
+                [],
+            )
+            .map_err(|e| format!("Failed to create aliases index: {e}"))?;
+
+        if needs_reindex {
+            self.conn
+                .execute("DELETE FROM note_metadata", [])
+                .map_err(|e| format!("Failed to reset note metadata: {e}"))?;
+        }
+
+        // First time each note was seen. Saves replace the file, so its own
+        // creation time doesn't survive; this table isn't cleared on rebuild.
+        self.conn
+            .execute(
+                "CREATE TABLE IF NOT EXISTS note_created (
+                path TEXT PRIMARY KEY,
+                created INTEGER NOT NULL
+            )",
+                [],
+            )
+            .map_err(|e| format!("Failed to create note_created table: {e}"))?;
+
         Ok(())
     }
 
@@ -291,26 +568,53 @@ impl CacheDb {
         content: &str,
         notes_dir: &str,
     ) -> Result<(), String> {
+        // Bookmark rows are rebuilt below; keep what the user did with them
+        let bookmark_state = self.bookmark_state_for_note(note_path)?;
         self.clear_note_cache(note_path)?;
 
+        let aliases = extract_aliases(content);
+        for alias in aliases {
+            self.add_alias(&alias, note_path)?;
+        }
+
+        // A note often repeats a link or tag; resolve and insert each once
         let links = extract_links(content);
-        for link in links {
-            // Strip block reference if present (e.g., "Note#heading" -> "Note")
-            let note_name = link.split('#').next().unwrap_or(&link);
+        let mut seen_names = HashSet::new();
+        let mut seen_paths = HashSet::new();
+        for link in &links {
+            // Strip block reference and display text (e.g., "Note#heading|text" -> "Note")
+            let note_name = link.split(['#', '|']).next().unwrap_or(link);
+            if !seen_names.insert(note_name) {
+                continue;
+            }
 
             // Try to find the actual file path for this link
-            if let Ok(link_path) = resolve_note_link(note_name, notes_dir) {
-                self.add_link(note_path, &link_path)?;
+            if let Ok(link_path) = self.resolve_note_link(note_name, notes_dir, Some(note_path)) {
+                if seen_paths.insert(link_path.clone()) {
+                    self.add_link(note_path, &link_path)?;
+                }
             }
         }
 
-        let tags = extract_tags(content);
-        for tag in tags {
-            self.add_tag(&tag, note_path)?;
+        let mut seen_tags = HashSet::new();
+        for tag in extract_tags(content) {
+            if seen_tags.insert(tag.clone()) {
+                self.add_tag(&tag, note_path)?;
+            }
         }
 
         let todos = extract_todos(content);
         for todo in todos {
+            let mut todo_links: Vec<String> = Vec::new();
+            for link in &todo.10 {
+                let note_name = link.split(['#', '|']).next().unwrap_or(link);
+                if let Ok(link_path) = self.resolve_note_link(note_name, notes_dir, Some(note_path))
+                {
+                    if !todo_links.contains(&link_path) {
+                        todo_links.push(link_path);
+                    }
+                }
+            }
             self.add_todo(
                 note_path,
                 todo.0,            // line_number
@@ -321,11 +625,18 @@ impl CacheDb {
                 todo.5,            // indent_level
                 todo.6,            // parent_line
                 todo.7.as_deref(), // recurrence_pattern
+                todo.8.as_deref(), // scheduled_date
+                &todo.9,           // clean_content
+                &todo_links,
             )?;
         }
 
         let bookmarks = extract_bookmarks(content);
         for bookmark in bookmarks {
+            // A malformed URL shouldn't stop the rest of the note being indexed
+            if parse_url_components(&bookmark.0).is_err() {
+                continue;
+            }
             self.add_bookmark(
                 &bookmark.0,           // url
                 bookmark.1.as_deref(), // title
@@ -335,10 +646,43 @@ impl CacheDb {
                 bookmark.3.as_deref(), // tags
             )?;
         }
+        for (url, is_favorite, visit_count) in bookmark_state {
+            self.conn
+                .execute(
+                    "UPDATE bookmarks SET is_favorite = ?1, visit_count = ?2 WHERE url = ?3 AND note_path = ?4",
+                    params![is_favorite, visit_count, url, note_path],
+                )
+                .map_err(|e| format!("Failed to restore bookmark: {e}"))?;
+        }
 
         Ok(())
     }
 
+    /// (url, is_favorite, visit_count) of a note's bookmarks that have been
+    /// starred or visited
+    fn bookmark_state_for_note(&self, note_path: &str) -> Result<Vec<(String, bool, i64)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT url, is_favorite, visit_count FROM bookmarks
+                 WHERE note_path = ?1 AND (is_favorite OR visit_count > 0)",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let rows = stmt
+            .query_map(params![note_path], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| format!("Failed to query bookmarks: {e}"))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row.map_err(|e| format!("Failed to get bookmark: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
     pub fn update_note_cache_with_fts(
         &self,
         note_path: &str,
@@ -355,13 +699,105 @@ impl CacheDb {
         // Index blocks
         let blocks = extract_blocks(content);
         self.remove_blocks_for_note(note_path)?;
-        for (block_id, line_number, block_content) in blocks {
-            self.add_block(note_path, &block_id, line_number, &block_content)?;
+        for block in blocks {
+            self.add_block(note_path, &block)?;
         }
 
         Ok(())
     }
 
+    /// Index every note in `notes` (path, title, content) in a single
+    /// transaction and drop index entries for notes that aren't listed.
+    /// Much faster than indexing notes one by one for a full rebuild; on
+    /// error nothing is changed. Returns the number of notes indexed.
+    pub fn rebuild_all(
+        &self,
+        notes: &[(String, String, String)],
+        notes_dir: &str,
+    ) -> Result<usize, String> {
+        self.rebuild_all_with_progress(notes, notes_dir, |_, _| true)
+    }
+
+    /// `rebuild_all`, calling `on_progress(done, total)` before each note.
+    /// Returning false stops the rebuild there: the notes indexed so far are
+    /// kept and the rest keep their previous index entries.
+    pub fn rebuild_all_with_progress(
+        &self,
+        notes: &[(String, String, String)],
+        notes_dir: &str,
+        on_progress: impl FnMut(usize, usize) -> bool,
+    ) -> Result<usize, String> {
+        // Fsyncs are pointless mid-rebuild: a crash just means rebuilding again
+        let synchronous: i64 = self
+            .conn
+            .query_row("PRAGMA synchronous", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to read synchronous pragma: {e}"))?;
+        self.conn
+            .execute_batch("PRAGMA synchronous = OFF")
+            .map_err(|e| format!("Failed to set synchronous pragma: {e}"))?;
+
+        let result = self.rebuild_all_in_transaction(notes, notes_dir, on_progress);
+
+        self.conn
+            .execute_batch(&format!("PRAGMA synchronous = {synchronous}"))
+            .map_err(|e| format!("Failed to restore synchronous pragma: {e}"))?;
+        result
+    }
+
+    fn rebuild_all_in_transaction(
+        &self,
+        notes: &[(String, String, String)],
+        notes_dir: &str,
+        mut on_progress: impl FnMut(usize, usize) -> bool,
+    ) -> Result<usize, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+
+        let listed: HashSet<&str> = notes.iter().map(|(path, _, _)| path.as_str()).collect();
+        let stale: Vec<String> = self
+            .get_all_indexed_paths()?
+            .into_iter()
+            .filter(|path| !listed.contains(path.as_str()))
+            .collect();
+        for path in &stale {
+            self.clear_note_cache(path)?;
+        }
+
+        let mut indexed = 0;
+        for (path, title, content) in notes {
+            if !on_progress(indexed, notes.len()) {
+                break;
+            }
+            self.update_note_cache_with_fts(path, title, content, notes_dir)?;
+            indexed += 1;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit cache rebuild: {e}"))?;
+        Ok(indexed)
+    }
+
+    /// Paths with an entry in the full-text index
+    fn get_all_indexed_paths(&self) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT note_path FROM note_content")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let paths = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to query paths: {e}"))?;
+
+        let mut result = Vec::new();
+        for path in paths {
+            result.push(path.map_err(|e| format!("Failed to get path: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
     pub fn clear_note_cache(&self, note_path: &str) -> Result<(), String> {
         self.conn
             .execute("DELETE FROM links WHERE from_note = ?1", params![note_path])
@@ -382,6 +818,13 @@ impl CacheDb {
             )
             .map_err(|e| format!("Failed to clear bookmarks: {e}"))?;
 
+        self.conn
+            .execute(
+                "DELETE FROM aliases WHERE note_path = ?1",
+                params![note_path],
+            )
+            .map_err(|e| format!("Failed to clear aliases: {e}"))?;
+
         // Also remove from FTS index and blocks
         self.remove_note_content(note_path)?;
         self.remove_blocks_for_note(note_path)?;
@@ -409,6 +852,99 @@ impl CacheDb {
         Ok(())
     }
 
+    pub fn add_alias(&self, alias: &str, note_path: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO aliases (alias, note_path) VALUES (?1, ?2)",
+                params![alias, note_path],
+            )
+            .map_err(|e| format!("Failed to add alias: {e}"))?;
+        Ok(())
+    }
+
+    /// Find the note that declares `alias` in its frontmatter (case-insensitive)
+    pub fn find_note_by_alias(&self, alias: &str) -> Result<Option<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT note_path FROM aliases WHERE alias = ?1 COLLATE NOCASE
+                 ORDER BY note_path LIMIT 1",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let result = stmt
+            .query_row(params![alias], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Failed to query alias: {e}"))?;
+
+        Ok(result)
+    }
+
+    /// Aliases declared in a note's frontmatter
+    pub fn get_aliases_for_note(&self, note_path: &str) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT alias FROM aliases WHERE note_path = ?1 ORDER BY alias")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let aliases = stmt
+            .query_map(params![note_path], |row| row.get(0))
+            .map_err(|e| format!("Failed to query aliases: {e}"))?;
+
+        let mut result = Vec::new();
+        for alias in aliases {
+            result.push(alias.map_err(|e| format!("Failed to get alias: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Every (alias, note_path) pair, ordered by alias
+    pub fn get_all_aliases(&self) -> Result<Vec<(String, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT alias, note_path FROM aliases ORDER BY alias COLLATE NOCASE")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let aliases = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query aliases: {e}"))?;
+
+        let mut result = Vec::new();
+        for alias in aliases {
+            result.push(alias.map_err(|e| format!("Failed to get alias: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Resolve a `[[link]]` target to a note path, matching the filename stem
+    /// first and falling back to frontmatter aliases. With `local_to` (the
+    /// linking note), a same-named note in its folder wins over duplicates
+    /// elsewhere.
+    pub fn resolve_note_link(
+        &self,
+        link_name: &str,
+        notes_dir: &str,
+        local_to: Option<&str>,
+    ) -> Result<String, String> {
+        if let Some(folder) = local_to.and_then(|from| std::path::Path::new(from).parent()) {
+            let name_without_ext = link_name.trim_end_matches(".md");
+            let candidate = folder.join(format!("{name_without_ext}.md"));
+            if let Some(path) = crate::note_manager::find_case_insensitive_match(&candidate) {
+                return Ok(path.to_string_lossy().to_string());
+            }
+        }
+
+        if let Ok(path) = find_note_by_stem(link_name, notes_dir) {
+            return Ok(path);
+        }
+
+        let name_without_ext = link_name.trim_end_matches(".md");
+        self.find_note_by_alias(name_without_ext)?
+            .ok_or_else(|| format!("Note not found: {link_name}"))
+    }
+
     pub fn get_backlinks(&self, note_path: &str) -> Result<Vec<String>, String> {
         let mut stmt = self
             .conn
@@ -445,48 +981,155 @@ impl CacheDb {
         Ok(result)
     }
 
-    pub fn get_notes_by_tag(&self, tag: &str) -> Result<Vec<String>, String> {
+    pub fn get_tags_for_note(&self, note_path: &str) -> Result<Vec<String>, String> {
         let mut stmt = self
             .conn
-            .prepare("SELECT note_path FROM tags WHERE tag = ?1")
+            .prepare("SELECT tag FROM tags WHERE note_path = ?1 ORDER BY tag")
             .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
-        let notes = stmt
-            .query_map(params![tag], |row| row.get(0))
-            .map_err(|e| format!("Failed to query notes: {e}"))?;
+        let tags = stmt
+            .query_map(params![note_path], |row| row.get(0))
+            .map_err(|e| format!("Failed to query tags: {e}"))?;
 
         let mut result = Vec::new();
-        for note in notes {
-            result.push(note.map_err(|e| format!("Failed to get note: {e}"))?);
+        for tag in tags {
+            result.push(tag.map_err(|e| format!("Failed to get tag: {e}"))?);
         }
 
         Ok(result)
     }
 
-    pub fn get_all_links(&self) -> Result<Vec<Link>, String> {
+    /// Every tag with the number of notes using it, most used first
+    pub fn get_all_tags_with_counts(&self) -> Result<Vec<(String, usize)>, String> {
         let mut stmt = self
             .conn
-            .prepare("SELECT from_note, to_note FROM links")
+            .prepare(
+                "SELECT tag, COUNT(DISTINCT note_path) AS note_count FROM tags GROUP BY tag ORDER BY note_count DESC, tag",
+            )
             .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
-        let links = stmt
+        let tags = stmt
             .query_map([], |row| {
-                Ok(Link {
-                    from_note: row.get(0)?,
-                    to_note: row.get(1)?,
-                })
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
             })
-            .map_err(|e| format!("Failed to query links: {e}"))?;
+            .map_err(|e| format!("Failed to query tags: {e}"))?;
 
         let mut result = Vec::new();
-        for link in links {
-            result.push(link.map_err(|e| format!("Failed to get link: {e}"))?);
+        for tag in tags {
+            result.push(tag.map_err(|e| format!("Failed to get tag: {e}"))?);
         }
 
         Ok(result)
     }
 
-    pub fn get_links_for_note(&self, note_path: &str) -> Result<Vec<Link>, String> {
+    /// Tags starting with `prefix` (case-insensitive), most used first
+    pub fn get_tag_suggestions(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, usize)>, String> {
+        let escaped = prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT tag, COUNT(DISTINCT note_path) AS note_count FROM tags
+                 WHERE tag LIKE ?1 || '%' ESCAPE '\\'
+                 GROUP BY tag ORDER BY note_count DESC, tag LIMIT ?2",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let tags = stmt
+            .query_map(params![escaped, limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .map_err(|e| format!("Failed to query tags: {e}"))?;
+
+        let mut result = Vec::new();
+        for tag in tags {
+            result.push(tag.map_err(|e| format!("Failed to get tag: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    pub fn get_notes_by_tag(&self, tag: &str) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT note_path FROM tags WHERE tag = ?1")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let notes = stmt
+            .query_map(params![tag], |row| row.get(0))
+            .map_err(|e| format!("Failed to query notes: {e}"))?;
+
+        let mut result = Vec::new();
+        for note in notes {
+            result.push(note.map_err(|e| format!("Failed to get note: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    pub fn get_all_links(&self) -> Result<Vec<Link>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT from_note, to_note FROM links")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let links = stmt
+            .query_map([], |row| {
+                Ok(Link {
+                    from_note: row.get(0)?,
+                    to_note: row.get(1)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query links: {e}"))?;
+
+        let mut result = Vec::new();
+        for link in links {
+            result.push(link.map_err(|e| format!("Failed to get link: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Number of notes each note links to, ignoring self-links
+    pub fn get_outgoing_link_counts(&self) -> Result<HashMap<String, usize>, String> {
+        self.link_counts_by("from_note")
+    }
+
+    /// Number of notes linking to each note, ignoring self-links
+    pub fn get_incoming_link_counts(&self) -> Result<HashMap<String, usize>, String> {
+        self.link_counts_by("to_note")
+    }
+
+    fn link_counts_by(&self, column: &str) -> Result<HashMap<String, usize>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {column}, COUNT(*) FROM links WHERE from_note != to_note GROUP BY {column}"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .map_err(|e| format!("Failed to query link counts: {e}"))?;
+
+        let mut result = HashMap::new();
+        for count in counts {
+            let (path, count) = count.map_err(|e| format!("Failed to get link count: {e}"))?;
+            result.insert(path, count);
+        }
+
+        Ok(result)
+    }
+
+    pub fn get_links_for_note(&self, note_path: &str) -> Result<Vec<Link>, String> {
         let mut stmt = self
             .conn
             .prepare(
@@ -524,10 +1167,15 @@ impl CacheDb {
         indent_level: i32,
         parent_line: Option<i32>,
         recurrence_pattern: Option<&str>,
+        scheduled_date: Option<&str>,
+        clean_content: &str,
+        links: &[String],
     ) -> Result<(), String> {
+        let links = serde_json::to_string(links)
+            .map_err(|e| format!("Failed to serialize todo links: {e}"))?;
         self.conn.execute(
-            "INSERT OR REPLACE INTO todos (note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern],
+            "INSERT OR REPLACE INTO todos (note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern, scheduled_date, clean_content, links) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern, scheduled_date, clean_content, links],
         ).map_err(|e| format!("Failed to add todo: {e}"))?;
 
         Ok(())
@@ -535,24 +1183,11 @@ impl CacheDb {
 
     pub fn get_incomplete_todos(&self) -> Result<Vec<Todo>, String> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern FROM todos WHERE is_completed = 0 ORDER BY note_path, line_number"
+            &format!("SELECT {TODO_COLUMNS} FROM todos WHERE is_completed = 0 ORDER BY note_path, line_number")
         ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let todos = stmt
-            .query_map([], |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    note_path: row.get(1)?,
-                    line_number: row.get(2)?,
-                    content: row.get(3)?,
-                    is_completed: row.get(4)?,
-                    due_date: row.get(5)?,
-                    priority: row.get(6)?,
-                    indent_level: row.get(7)?,
-                    parent_line: row.get(8)?,
-                    recurrence_pattern: row.get(9)?,
-                })
-            })
+            .query_map([], row_to_todo)
             .map_err(|e| format!("Failed to query todos: {e}"))?;
 
         let mut result = Vec::new();
@@ -563,26 +1198,35 @@ impl CacheDb {
         Ok(result)
     }
 
+    /// Paths of notes with at least one incomplete todo
+    pub fn get_notes_with_incomplete_todos(&self) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT note_path FROM todos WHERE is_completed = 0")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let paths = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to query todos: {e}"))?;
+
+        let mut result = Vec::new();
+        for path in paths {
+            result.push(path.map_err(|e| format!("Failed to get note path: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
     pub fn get_all_todos(&self) -> Result<Vec<Todo>, String> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern FROM todos ORDER BY note_path, is_completed, line_number"
-        ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos ORDER BY note_path, is_completed, line_number"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let todos = stmt
-            .query_map([], |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    note_path: row.get(1)?,
-                    line_number: row.get(2)?,
-                    content: row.get(3)?,
-                    is_completed: row.get(4)?,
-                    due_date: row.get(5)?,
-                    priority: row.get(6)?,
-                    indent_level: row.get(7)?,
-                    parent_line: row.get(8)?,
-                    recurrence_pattern: row.get(9)?,
-                })
-            })
+            .query_map([], row_to_todo)
             .map_err(|e| format!("Failed to query todos: {e}"))?;
 
         let mut result = Vec::new();
@@ -593,6 +1237,159 @@ impl CacheDb {
         Ok(result)
     }
 
+    /// Incomplete todos with the given priority. Accepts `high`/`medium`/`low`
+    /// as well as the `p:1`/`p:2`/`p:3` shorthand.
+    pub fn get_todos_by_priority(&self, priority: &str) -> Result<Vec<Todo>, String> {
+        let priority =
+            normalize_priority(priority).ok_or_else(|| format!("Invalid priority: {priority}"))?;
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos WHERE is_completed = 0 AND priority = ?1 ORDER BY note_path, line_number"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let todos = stmt
+            .query_map(params![priority], row_to_todo)
+            .map_err(|e| format!("Failed to query todos: {e}"))?;
+
+        let mut result = Vec::new();
+        for todo in todos {
+            result.push(todo.map_err(|e| format!("Failed to get todo: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Incomplete todos ordered by priority (high first, unprioritized last),
+    /// then due date (undated last), then note path
+    pub fn get_incomplete_todos_sorted(&self) -> Result<Vec<Todo>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos WHERE is_completed = 0 \
+                 ORDER BY CASE priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 2 ELSE 3 END, \
+                 due_date IS NULL, due_date, note_path, line_number"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let todos = stmt
+            .query_map([], row_to_todo)
+            .map_err(|e| format!("Failed to query todos: {e}"))?;
+
+        let mut result = Vec::new();
+        for todo in todos {
+            result.push(todo.map_err(|e| format!("Failed to get todo: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Incomplete todos whose scheduled (start) date is today
+    pub fn get_todos_scheduled_today(&self) -> Result<Vec<Todo>, String> {
+        use chrono::Local;
+
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos WHERE is_completed = 0 AND scheduled_date = ?1 ORDER BY note_path, line_number"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let todos = stmt
+            .query_map(params![today], row_to_todo)
+            .map_err(|e| format!("Failed to query todos: {e}"))?;
+
+        let mut result = Vec::new();
+        for todo in todos {
+            result.push(todo.map_err(|e| format!("Failed to get todo: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    /// Incomplete todos with a due date before today, oldest first
+    pub fn get_overdue_todos(&self) -> Result<Vec<Todo>, String> {
+        use chrono::{Local, NaiveDate};
+
+        // ISO dates compare correctly as strings; the GLOB drops anything
+        // that isn't shaped like YYYY-MM-DD
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos
+                 WHERE is_completed = 0
+                   AND due_date IS NOT NULL
+                   AND due_date GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
+                   AND due_date < ?1
+                 ORDER BY due_date, note_path, line_number"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let todos = stmt
+            .query_map(params![today], row_to_todo)
+            .map_err(|e| format!("Failed to query todos: {e}"))?;
+
+        let mut result = Vec::new();
+        for todo in todos {
+            let todo = todo.map_err(|e| format!("Failed to get todo: {e}"))?;
+
+            // Reject well-shaped but impossible dates like 2025-13-40
+            let is_valid_date = todo
+                .due_date
+                .as_deref()
+                .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+            if is_valid_date {
+                result.push(todo);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Incomplete todos bucketed for an agenda view: one entry per date from
+    /// today through `days` days ahead (always present, possibly empty), plus
+    /// "overdue" and "no_date" buckets. Todos due beyond the window are left
+    /// out, and recurring todos only appear under their current due date.
+    /// `days` is capped at `MAX_AGENDA_DAYS`.
+    pub fn get_todos_agenda(&self, days: u32) -> Result<BTreeMap<String, Vec<Todo>>, String> {
+        use chrono::{Days, Local, NaiveDate};
+
+        let days = days.min(MAX_AGENDA_DAYS);
+        let today = Local::now().date_naive();
+        let last_day = today
+            .checked_add_days(Days::new(days as u64))
+            .ok_or_else(|| format!("Agenda range of {days} days is out of range"))?;
+
+        let mut agenda: BTreeMap<String, Vec<Todo>> = BTreeMap::new();
+        agenda.insert("overdue".to_string(), Vec::new());
+        agenda.insert("no_date".to_string(), Vec::new());
+        for date in today.iter_days().take_while(|date| *date <= last_day) {
+            agenda.insert(date.format("%Y-%m-%d").to_string(), Vec::new());
+        }
+
+        for todo in self.get_incomplete_todos()? {
+            let due_date = todo
+                .due_date
+                .as_deref()
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+            let bucket = match due_date {
+                None => "no_date".to_string(),
+                Some(date) if date < today => "overdue".to_string(),
+                Some(date) if date <= last_day => date.format("%Y-%m-%d").to_string(),
+                Some(_) => continue,
+            };
+
+            agenda.entry(bucket).or_default().push(todo);
+        }
+
+        Ok(agenda)
+    }
+
     pub fn toggle_todo(&self, note_path: &str, line_number: i32) -> Result<bool, String> {
         // Get current state
         let mut stmt = self
@@ -618,25 +1415,15 @@ impl CacheDb {
     }
 
     pub fn get_todo(&self, note_path: &str, line_number: i32) -> Result<Todo, String> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, note_path, line_number, content, is_completed, due_date, priority, indent_level, parent_line, recurrence_pattern FROM todos WHERE note_path = ?1 AND line_number = ?2"
-        ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos WHERE note_path = ?1 AND line_number = ?2"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let todo = stmt
-            .query_row(params![note_path, line_number], |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    note_path: row.get(1)?,
-                    line_number: row.get(2)?,
-                    content: row.get(3)?,
-                    is_completed: row.get(4)?,
-                    due_date: row.get(5)?,
-                    priority: row.get(6)?,
-                    indent_level: row.get(7)?,
-                    parent_line: row.get(8)?,
-                    recurrence_pattern: row.get(9)?,
-                })
-            })
+            .query_row(params![note_path, line_number], row_to_todo)
             .map_err(|e| format!("Failed to get todo: {e}"))?;
 
         Ok(todo)
@@ -669,20 +1456,166 @@ impl CacheDb {
         Ok(())
     }
 
+    /// The tokenizer the full-text index was built with
+    pub fn search_tokenizer(&self) -> Result<Option<SearchTokenizer>, String> {
+        let sql: String = self
+            .conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE name = 'note_content'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read FTS table definition: {e}"))?;
+
+        Ok([
+            SearchTokenizer::Porter,
+            SearchTokenizer::Unicode61,
+            SearchTokenizer::Trigram,
+        ]
+        .into_iter()
+        .find(|tokenizer| sql.contains(&format!("tokenize = '{}'", tokenizer.fts_option()))))
+    }
+
+    /// Rebuild the full-text index with `tokenizer` unless it already uses
+    /// it. FTS5 can't change the tokenizer of a table, so the indexed notes
+    /// are copied into a new table that then replaces the old one, all in one
+    /// transaction. Returns whether the index was rebuilt.
+    pub fn set_search_tokenizer(&self, tokenizer: SearchTokenizer) -> Result<bool, String> {
+        if self.search_tokenizer()? == Some(tokenizer) {
+            return Ok(false);
+        }
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+        tx.execute("DROP TABLE IF EXISTS note_content_rebuild", [])
+            .map_err(|e| format!("Failed to clear FTS rebuild table: {e}"))?;
+        tx.execute(
+            &create_note_content_sql("note_content_rebuild", tokenizer),
+            [],
+        )
+        .map_err(|e| format!("Failed to create FTS5 table: {e}"))?;
+        tx.execute(
+            "INSERT INTO note_content_rebuild (note_path, title, content)
+             SELECT note_path, title, content FROM note_content",
+            [],
+        )
+        .map_err(|e| format!("Failed to reindex notes: {e}"))?;
+        tx.execute("DROP TABLE note_content", [])
+            .map_err(|e| format!("Failed to drop FTS5 table: {e}"))?;
+        tx.execute(
+            "ALTER TABLE note_content_rebuild RENAME TO note_content",
+            [],
+        )
+        .map_err(|e| format!("Failed to replace FTS5 table: {e}"))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit FTS rebuild: {e}"))?;
+
+        Ok(true)
+    }
+
     pub fn search_notes_fts(&self, query: &str) -> Result<Vec<String>, String> {
-        // FTS5 search returning note paths that match
+        Ok(self
+            .search_notes_fts_ranked(query)?
+            .into_iter()
+            .map(|(path, _score)| path)
+            .collect())
+    }
+
+    /// FTS5 search returning matching note paths with their relevance score,
+    /// best match first. Higher scores are better.
+    pub fn search_notes_fts_ranked(&self, query: &str) -> Result<Vec<(String, f64)>, String> {
+        self.search_notes_fts_filtered(Some(query), &[])
+    }
+
+    /// Ranked FTS5 search restricted to notes carrying every tag in `tags`.
+    /// With no FTS expression, returns all notes matching the tags unranked.
+    pub fn search_notes_fts_filtered(
+        &self,
+        fts_expression: Option<&str>,
+        tags: &[String],
+    ) -> Result<Vec<(String, f64)>, String> {
+        self.query_fts_filtered("", fts_expression, tags, |row| {
+            Ok((row.get::<_, String>(0)?, -row.get::<_, f64>(1)?))
+        })
+    }
+
+    /// Like `search_notes_fts_filtered`, but with a highlighted excerpt
+    /// from FTS5's `snippet()` so results don't need the files read. The
+    /// snippet is HTML-escaped with matches wrapped in `<mark>`.
+    pub fn search_notes_fts_snippets(
+        &self,
+        fts_expression: Option<&str>,
+        tags: &[String],
+    ) -> Result<Vec<FtsSnippet>, String> {
+        // Private-use sentinels survive escaping and become <mark> after
+        let extra_columns = if fts_expression.is_some() {
+            ", title, snippet(note_content, 2, char(57344), char(57345), '…', 32)"
+        } else {
+            ", title, ''"
+        };
+        self.query_fts_filtered(extra_columns, fts_expression, tags, |row| {
+            let snippet = crate::export::escape_html(&row.get::<_, String>(3)?)
+                .replace('\u{E000}', "<mark>")
+                .replace('\u{E001}', "</mark>");
+            Ok(FtsSnippet {
+                path: row.get(0)?,
+                title: row.get(2)?,
+                snippet,
+                score: -row.get::<_, f64>(1)?,
+            })
+        })
+    }
+
+    /// Run an FTS query selecting the note path, the score and then
+    /// `extra_columns`, restricted to notes carrying all of `tags`
+    fn query_fts_filtered<T>(
+        &self,
+        extra_columns: &str,
+        fts_expression: Option<&str>,
+        tags: &[String],
+        map_row: impl FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+    ) -> Result<Vec<T>, String> {
+        let mut values: Vec<&str> = Vec::new();
+
+        // bm25() takes one weight per column (note_path, title, content) and
+        // returns lower-is-better values, so title hits count 10x and the
+        // score is negated for callers
+        let mut sql = match fts_expression {
+            Some(expression) => {
+                values.push(expression);
+                format!(
+                    "SELECT note_path, bm25(note_content, 0.0, 10.0, 1.0) AS score{extra_columns}
+                     FROM note_content WHERE note_content MATCH ?1"
+                )
+            }
+            None => {
+                format!("SELECT note_path, 0.0 AS score{extra_columns} FROM note_content WHERE 1")
+            }
+        };
+
+        for tag in tags {
+            values.push(tag);
+            sql.push_str(&format!(
+                " AND note_path IN (SELECT note_path FROM tags WHERE tag = ?{} COLLATE NOCASE)",
+                values.len()
+            ));
+        }
+        sql.push_str(" ORDER BY score, note_path");
+
         let mut stmt = self
             .conn
-            .prepare("SELECT note_path FROM note_content WHERE note_content MATCH ?1 ORDER BY rank")
+            .prepare(&sql)
             .map_err(|e| format!("Failed to prepare FTS search: {e}"))?;
 
-        let paths = stmt
-            .query_map(params![query], |row| row.get(0))
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values), map_row)
             .map_err(|e| format!("Failed to execute FTS search: {e}"))?;
 
         let mut result = Vec::new();
-        for path in paths {
-            result.push(path.map_err(|e| format!("Failed to get path: {e}"))?);
+        for row in rows {
+            result.push(row.map_err(|e| format!("Failed to get path: {e}"))?);
         }
 
         Ok(result)
@@ -690,17 +1623,18 @@ impl CacheDb {
 
     // Block Reference Methods
 
-    pub fn add_block(
-        &self,
-        note_path: &str,
-        block_id: &str,
-        line_number: i32,
-        content: &str,
-    ) -> Result<(), String> {
+    pub fn add_block(&self, note_path: &str, block: &Block) -> Result<(), String> {
         self.conn
             .execute(
-                "INSERT OR REPLACE INTO blocks (note_path, block_id, line_number, content) VALUES (?1, ?2, ?3, ?4)",
-                params![note_path, block_id, line_number, content],
+                "INSERT OR REPLACE INTO blocks (note_path, block_id, line_number, content, block_type, level) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    note_path,
+                    block.block_id,
+                    block.line_number,
+                    block.content,
+                    block.block_type,
+                    block.level
+                ],
             )
             .map_err(|e| format!("Failed to add block: {e}"))?;
         Ok(())
@@ -738,18 +1672,46 @@ impl CacheDb {
         Ok(result)
     }
 
+    pub fn get_block_entry(
+        &self,
+        note_path: &str,
+        block_id: &str,
+    ) -> Result<Option<Block>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT block_id, line_number, content, block_type, level FROM blocks WHERE note_path = ?1 AND block_id = ?2",
+            )
+            .map_err(|e| format!("Failed to prepare block query: {e}"))?;
+
+        let result = stmt
+            .query_row(params![note_path, block_id], |row| {
+                Ok(Block {
+                    block_id: row.get(0)?,
+                    line_number: row.get(1)?,
+                    content: row.get(2)?,
+                    block_type: row.get(3)?,
+                    level: row.get(4)?,
+                })
+            })
+            .optional()
+            .map_err(|e| format!("Failed to query block: {e}"))?;
+
+        Ok(result)
+    }
+
     pub fn get_blocks_for_note(
         &self,
         note_path: &str,
-    ) -> Result<Vec<(String, i32, String)>, String> {
+    ) -> Result<Vec<(String, i32, String, i32)>, String> {
         let mut stmt = self
             .conn
-            .prepare("SELECT block_id, line_number, content FROM blocks WHERE note_path = ?1 ORDER BY line_number")
+            .prepare("SELECT block_id, line_number, content, level FROM blocks WHERE note_path = ?1 ORDER BY line_number")
             .map_err(|e| format!("Failed to prepare blocks query: {e}"))?;
 
         let blocks = stmt
             .query_map(params![note_path], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
             })
             .map_err(|e| format!("Failed to query blocks: {e}"))?;
 
@@ -789,28 +1751,17 @@ impl CacheDb {
     }
 
     pub fn get_all_bookmarks(&self) -> Result<Vec<Bookmark>, String> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, url, title, description, note_path, line_number, domain, subdomain, path, created_at, tags
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {BOOKMARK_COLUMNS}
              FROM bookmarks
              ORDER BY created_at DESC"
-        ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let bookmarks = stmt
-            .query_map([], |row| {
-                Ok(Bookmark {
-                    id: row.get(0)?,
-                    url: row.get(1)?,
-                    title: row.get(2)?,
-                    description: row.get(3)?,
-                    note_path: row.get(4)?,
-                    line_number: row.get(5)?,
-                    domain: row.get(6)?,
-                    subdomain: row.get(7)?,
-                    path: row.get(8)?,
-                    created_at: row.get(9)?,
-                    tags: row.get(10)?,
-                })
-            })
+            .query_map([], bookmark_from_row)
             .map_err(|e| format!("Failed to query bookmarks: {e}"))?;
 
         let mut result = Vec::new();
@@ -824,32 +1775,21 @@ impl CacheDb {
     pub fn search_bookmarks(&self, query: &str) -> Result<Vec<Bookmark>, String> {
         let search_pattern = format!("%{}%", query.to_lowercase());
 
-        let mut stmt = self.conn.prepare(
-            "SELECT id, url, title, description, note_path, line_number, domain, subdomain, path, created_at, tags
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {BOOKMARK_COLUMNS}
              FROM bookmarks
              WHERE LOWER(url) LIKE ?1
                 OR LOWER(title) LIKE ?1
                 OR LOWER(description) LIKE ?1
                 OR LOWER(tags) LIKE ?1
              ORDER BY created_at DESC"
-        ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let bookmarks = stmt
-            .query_map(params![search_pattern], |row| {
-                Ok(Bookmark {
-                    id: row.get(0)?,
-                    url: row.get(1)?,
-                    title: row.get(2)?,
-                    description: row.get(3)?,
-                    note_path: row.get(4)?,
-                    line_number: row.get(5)?,
-                    domain: row.get(6)?,
-                    subdomain: row.get(7)?,
-                    path: row.get(8)?,
-                    created_at: row.get(9)?,
-                    tags: row.get(10)?,
-                })
-            })
+            .query_map(params![search_pattern], bookmark_from_row)
             .map_err(|e| format!("Failed to query bookmarks: {e}"))?;
 
         let mut result = Vec::new();
@@ -861,29 +1801,103 @@ impl CacheDb {
     }
 
     pub fn get_bookmarks_by_domain(&self, domain: &str) -> Result<Vec<Bookmark>, String> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, url, title, description, note_path, line_number, domain, subdomain, path, created_at, tags
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {BOOKMARK_COLUMNS}
              FROM bookmarks
              WHERE domain = ?1
              ORDER BY subdomain, path, created_at DESC"
-        ).map_err(|e| format!("Failed to prepare statement: {e}"))?;
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+
+        let bookmarks = stmt
+            .query_map(params![domain], bookmark_from_row)
+            .map_err(|e| format!("Failed to query bookmarks: {e}"))?;
+
+        let mut result = Vec::new();
+        for bookmark in bookmarks {
+            result.push(bookmark.map_err(|e| format!("Failed to get bookmark: {e}"))?);
+        }
+
+        Ok(result)
+    }
+
+    pub fn delete_bookmark(&self, id: i32) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM bookmarks WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete bookmark: {e}"))?;
+
+        Ok(())
+    }
+
+    pub fn update_bookmark(
+        &self,
+        id: i32,
+        title: Option<&str>,
+        description: Option<&str>,
+        tags: Option<&str>,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE bookmarks SET title = ?1, description = ?2, tags = ?3 WHERE id = ?4",
+                params![title, description, tags, id],
+            )
+            .map_err(|e| format!("Failed to update bookmark: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Flip a bookmark's favorite flag, returning the new value
+    pub fn toggle_bookmark_favorite(&self, id: i32) -> Result<bool, String> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE bookmarks SET is_favorite = NOT is_favorite WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|e| format!("Failed to update bookmark: {e}"))?;
+        if updated == 0 {
+            return Err(format!("Bookmark {id} not found"));
+        }
+
+        self.conn
+            .query_row(
+                "SELECT is_favorite FROM bookmarks WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read bookmark: {e}"))
+    }
+
+    /// Count a visit on every bookmark of `url`
+    pub fn record_bookmark_visit(&self, url: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE bookmarks SET visit_count = visit_count + 1 WHERE url = ?1",
+                params![url],
+            )
+            .map_err(|e| format!("Failed to record bookmark visit: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Visited bookmarks, most visited first, one per URL
+    pub fn get_frequent_bookmarks(&self, limit: usize) -> Result<Vec<Bookmark>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {BOOKMARK_COLUMNS}
+                 FROM bookmarks
+                 WHERE visit_count > 0
+                   AND id IN (SELECT MIN(id) FROM bookmarks GROUP BY url)
+                 ORDER BY visit_count DESC, is_favorite DESC, url
+                 LIMIT ?1"
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
 
         let bookmarks = stmt
-            .query_map(params![domain], |row| {
-                Ok(Bookmark {
-                    id: row.get(0)?,
-                    url: row.get(1)?,
-                    title: row.get(2)?,
-                    description: row.get(3)?,
-                    note_path: row.get(4)?,
-                    line_number: row.get(5)?,
-                    domain: row.get(6)?,
-                    subdomain: row.get(7)?,
-                    path: row.get(8)?,
-                    created_at: row.get(9)?,
-                    tags: row.get(10)?,
-                })
-            })
+            .query_map(params![limit as i64], bookmark_from_row)
             .map_err(|e| format!("Failed to query bookmarks: {e}"))?;
 
         let mut result = Vec::new();
@@ -894,31 +1908,6 @@ impl CacheDb {
         Ok(result)
     }
 
-    pub fn delete_bookmark(&self, id: i32) -> Result<(), String> {
-        self.conn
-            .execute("DELETE FROM bookmarks WHERE id = ?1", params![id])
-            .map_err(|e| format!("Failed to delete bookmark: {e}"))?;
-
-        Ok(())
-    }
-
-    pub fn update_bookmark(
-        &self,
-        id: i32,
-        title: Option<&str>,
-        description: Option<&str>,
-        tags: Option<&str>,
-    ) -> Result<(), String> {
-        self.conn
-            .execute(
-                "UPDATE bookmarks SET title = ?1, description = ?2, tags = ?3 WHERE id = ?4",
-                params![title, description, tags, id],
-            )
-            .map_err(|e| format!("Failed to update bookmark: {e}"))?;
-
-        Ok(())
-    }
-
     pub fn get_all_domains(&self) -> Result<Vec<String>, String> {
         let mut stmt = self
             .conn
@@ -965,6 +1954,19 @@ impl CacheDb {
         Ok(())
     }
 
+    /// Store many modification times in one transaction
+    pub fn set_cached_mtimes(&self, mtimes: &[(String, i64, u32)]) -> Result<(), String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+        for (path, secs, nanos) in mtimes {
+            self.set_cached_mtime(path, *secs, *nanos)?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit mtimes: {e}"))
+    }
+
     /// Get all paths that are currently cached
     pub fn get_all_cached_paths(&self) -> Result<Vec<String>, String> {
         let mut stmt = self
@@ -995,6 +1997,118 @@ impl CacheDb {
         Ok(())
     }
 
+    /// Creation time recorded for `path`, recording `observed` first if the
+    /// note hasn't been seen before
+    pub fn record_created_time(&self, path: &str, observed: i64) -> Result<i64, String> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO note_created (path, created) VALUES (?1, ?2)",
+                params![path, observed],
+            )
+            .map_err(|e| format!("Failed to record creation time: {e}"))?;
+        self.conn
+            .query_row(
+                "SELECT created FROM note_created WHERE path = ?1",
+                params![path],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to get creation time: {e}"))
+    }
+
+    /// Record creation times for every note in `observed` (path, filesystem
+    /// time) and return the recorded time of each. Notes no longer present
+    /// are forgotten, so a new note at an old path starts fresh.
+    pub fn sync_created_times(
+        &self,
+        observed: &[(String, i64)],
+    ) -> Result<HashMap<String, i64>, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {e}"))?;
+        let present: HashSet<&str> = observed.iter().map(|(path, _)| path.as_str()).collect();
+        let stale: Vec<String> = self
+            .get_created_times()?
+            .into_keys()
+            .filter(|path| !present.contains(path.as_str()))
+            .collect();
+        for path in &stale {
+            self.conn
+                .execute("DELETE FROM note_created WHERE path = ?1", params![path])
+                .map_err(|e| format!("Failed to forget creation time: {e}"))?;
+        }
+        for (path, created) in observed {
+            self.conn
+                .execute(
+                    "INSERT OR IGNORE INTO note_created (path, created) VALUES (?1, ?2)",
+                    params![path, created],
+                )
+                .map_err(|e| format!("Failed to record creation time: {e}"))?;
+        }
+        let recorded = self.get_created_times()?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit creation times: {e}"))?;
+        Ok(recorded)
+    }
+
+    fn get_created_times(&self) -> Result<HashMap<String, i64>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, created FROM note_created")
+            .map_err(|e| format!("Failed to prepare statement: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query creation times: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to get creation time: {e}"))
+    }
+
+    /// Carry the recorded creation time over when a note moves
+    pub fn move_created_time(&self, old_path: &str, new_path: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE OR REPLACE note_created SET path = ?2 WHERE path = ?1",
+                params![old_path, new_path],
+            )
+            .map_err(|e| format!("Failed to move creation time: {e}"))?;
+        Ok(())
+    }
+
+    /// Merge the full-text index segments and `VACUUM` the database to
+    /// reclaim the space left by edits and deletes. Returns the size of the
+    /// database files (including the WAL) before and after.
+    pub fn optimize(&self) -> Result<(u64, u64), String> {
+        let size_before = self.file_size();
+
+        self.conn
+            .execute(
+                "INSERT INTO note_content(note_content) VALUES('optimize')",
+                [],
+            )
+            .map_err(|e| format!("Failed to optimize FTS index: {e}"))?;
+        self.conn
+            .execute_batch("VACUUM")
+            .map_err(|e| format!("Failed to vacuum database: {e}"))?;
+        // Fold the WAL back into the database so the freed space shows up
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(|e| format!("Failed to checkpoint database: {e}"))?;
+
+        Ok((size_before, self.file_size()))
+    }
+
+    /// Bytes used by the database file and its WAL; 0 for in-memory databases
+    fn file_size(&self) -> u64 {
+        let Some(path) = self.conn.path().filter(|path| !path.is_empty()) else {
+            return 0;
+        };
+        ["", "-wal"]
+            .iter()
+            .filter_map(|suffix| std::fs::metadata(format!("{path}{suffix}")).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
     /// Clear all cached metadata (for force rebuild)
     pub fn clear_all_metadata(&self) -> Result<(), String> {
         self.conn
@@ -1005,20 +2119,202 @@ impl CacheDb {
 }
 
 pub fn extract_links(content: &str) -> Vec<String> {
-    let re = Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
-    re.captures_iter(content)
+    static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]]+)\]\]").unwrap());
+    let re = &*LINK_REGEX;
+    re.captures_iter(&strip_code(content))
         .map(|cap| cap[1].to_string())
         .collect()
 }
 
+/// `content` with fenced code blocks and inline code spans blanked out, so
+/// `#include` or `[[x]]` in code isn't read as a tag or link. Code is
+/// replaced by spaces byte for byte, so offsets into the result are offsets
+/// into `content`.
+pub(crate) fn strip_code(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut fence: Option<&str> = None;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let line_fence = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        match (fence, line_fence) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            _ => {}
+        }
+        if fence.is_some() || line_fence.is_some() {
+            let body = line.strip_suffix('\n').unwrap_or(line);
+            result.extend(std::iter::repeat_n(' ', body.len()));
+            result.push_str(&line[body.len()..]);
+            continue;
+        }
+        result.push_str(&strip_inline_code(line));
+    }
+
+    result
+}
+
+/// Blank out `` `code` `` spans in a line. A span closes at the next run of
+/// exactly as many backticks; an unmatched run is left as written.
+fn strip_inline_code(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find('`') {
+        result.push_str(&rest[..start]);
+        let after_start = &rest[start..];
+        let ticks = after_start.len() - after_start.trim_start_matches('`').len();
+        let body = &after_start[ticks..];
+
+        let mut search_from = 0;
+        let close = loop {
+            let Some(offset) = body[search_from..].find('`') else {
+                break None;
+            };
+            let run_start = search_from + offset;
+            let run = body[run_start..].len() - body[run_start..].trim_start_matches('`').len();
+            if run == ticks {
+                break Some(run_start);
+            }
+            search_from = run_start + run;
+        };
+
+        match close {
+            Some(close) => {
+                let span_len = ticks + close + ticks;
+                result.extend(std::iter::repeat_n(' ', span_len));
+                rest = &after_start[span_len..];
+            }
+            None => {
+                result.push_str(&after_start[..ticks]);
+                rest = body;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// `content` with `http(s)://` URLs replaced by spaces byte for byte, so a
+/// `page#section` fragment isn't taken for a tag
+pub(crate) fn blank_urls(content: &str) -> String {
+    static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://\S+").unwrap());
+    URL_REGEX
+        .replace_all(content, |caps: &regex::Captures| " ".repeat(caps[0].len()))
+        .into_owned()
+}
+
+/// A `#tag`: word characters and hyphens, with `/` between nesting levels
+pub(crate) const TAG_PATTERN: &str = r"#(\w[\w-]*(?:/[\w-]+)*)";
+
+/// Whether a `#word` counts as a tag. It needs a letter or underscore, so
+/// issue references like `#123` don't count, and mustn't be a hex color
+/// like `#fff` or `#FF0000`.
+pub(crate) fn is_tag_name(tag: &str) -> bool {
+    let is_hex_color =
+        matches!(tag.len(), 3 | 4 | 6 | 8) && tag.chars().all(|c| c.is_ascii_hexdigit());
+    tag.chars().any(|c| c.is_alphabetic() || c == '_') && !is_hex_color
+}
+
+/// Tags in `content`. A nested tag like `#project/work` is also returned
+/// as each of its ancestors (`project`), so filtering by a parent tag
+/// finds its children. Code and URLs are skipped, so `page#section`
+/// fragments aren't taken for tags.
 fn extract_tags(content: &str) -> Vec<String> {
-    let re = Regex::new(r"#(\w+)").unwrap();
-    re.captures_iter(content)
-        .map(|cap| cap[1].to_string())
+    static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(TAG_PATTERN).unwrap());
+    let re = &*TAG_REGEX;
+    let mut tags = Vec::new();
+    for cap in re.captures_iter(&blank_urls(&strip_code(content))) {
+        let tag = &cap[1];
+        let ancestors = tag.match_indices('/').map(|(index, _)| &tag[..index]);
+        tags.extend(
+            ancestors
+                .chain([tag])
+                .filter(|tag| is_tag_name(tag))
+                .map(str::to_string),
+        );
+    }
+    tags
+}
+
+/// Extract `aliases:` (or `alias:`) entries from a note's YAML frontmatter.
+/// Supports both the inline `[Foo, Bar]` form and the block list form.
+fn extract_aliases(content: &str) -> Vec<String> {
+    frontmatter_values(content, &["aliases", "alias"])
+}
+
+/// Values of the frontmatter keys in `keys`, each written as a single
+/// value, an inline `[a, b]` list or a block list. Surrounding quotes
+/// are removed and empty entries dropped.
+pub(crate) fn frontmatter_values(content: &str, keys: &[&str]) -> Vec<String> {
+    let frontmatter = match extract_frontmatter(content) {
+        Some(frontmatter) => frontmatter,
+        None => return Vec::new(),
+    };
+
+    let mut values = Vec::new();
+    let mut in_list = false;
+
+    for line in frontmatter.lines() {
+        if in_list {
+            if let Some(item) = line.trim_start().strip_prefix("- ") {
+                values.push(item.to_string());
+                continue;
+            }
+            in_list = false;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+
+        if !keys.contains(&key) {
+            continue;
+        }
+
+        if value.is_empty() {
+            in_list = true;
+        } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            values.extend(inner.split(',').map(|item| item.to_string()));
+        } else {
+            values.push(value.to_string());
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|value| {
+            value
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .filter(|value| !value.is_empty())
         .collect()
 }
 
-fn resolve_note_link(link_name: &str, notes_dir: &str) -> Result<String, String> {
+/// Return the body of a leading `---` delimited YAML frontmatter block, if any
+fn extract_frontmatter(content: &str) -> Option<&str> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some(&rest[..offset]);
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn find_note_by_stem(link_name: &str, notes_dir: &str) -> Result<String, String> {
     // Remove .md extension if present
     let name_without_ext = link_name.trim_end_matches(".md");
 
@@ -1047,10 +2343,45 @@ fn resolve_note_link(link_name: &str, notes_dir: &str) -> Result<String, String>
 
 // Calculate next occurrence date based on recurrence pattern
 pub fn calculate_next_occurrence(pattern: &str) -> Option<String> {
-    use chrono::{Datelike, Duration, Local, Weekday};
+    use chrono::Local;
+
+    calculate_next_occurrence_from(pattern, Local::now().date_naive())
+}
 
-    let today = Local::now().date_naive();
-    let pattern_lower = pattern.to_lowercase();
+// Calculate the occurrence following `today` for a recurrence pattern.
+// Besides the fixed keywords, supports intervals (`every:3d`, `every:2w`,
+// `every:1m`) and `every:weekday`, which skips Saturdays and Sundays.
+fn calculate_next_occurrence_from(pattern: &str, today: chrono::NaiveDate) -> Option<String> {
+    use chrono::{Datelike, Days, Duration, Months, Weekday};
+
+    let pattern_lower = pattern.trim().to_lowercase();
+
+    if let Some(interval) = pattern_lower.strip_prefix("every:") {
+        let next = match interval {
+            "weekday" => {
+                let days_to_add = match today.weekday() {
+                    Weekday::Fri => 3,
+                    Weekday::Sat => 2,
+                    _ => 1,
+                };
+                today + Duration::days(days_to_add)
+            }
+            _ => {
+                let (split_at, _) = interval.char_indices().last()?;
+                let (count, unit) = interval.split_at(split_at);
+                match (count.parse::<u32>(), unit) {
+                    (Ok(0), _) => return None,
+                    (Ok(count), "d") => today.checked_add_days(Days::new(count as u64))?,
+                    (Ok(count), "w") => today.checked_add_days(Days::new(count as u64 * 7))?,
+                    (Ok(count), "m") => today.checked_add_months(Months::new(count))?,
+                    (Ok(_), _) => return None,
+                    // `every:monday` and friends use the weekday rules below
+                    (Err(_), _) => return calculate_next_occurrence_from(interval, today),
+                }
+            }
+        };
+        return Some(next.format("%Y-%m-%d").to_string());
+    }
 
     match pattern_lower.as_str() {
         "daily" => {
@@ -1080,57 +2411,57 @@ pub fn calculate_next_occurrence(pattern: &str) -> Option<String> {
             Some(next.format("%Y-%m-%d").to_string())
         }
         "monday" => {
-            let days_until =
-                (Weekday::Mon.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Mon.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "tuesday" => {
-            let days_until =
-                (Weekday::Tue.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Tue.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "wednesday" => {
-            let days_until =
-                (Weekday::Wed.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Wed.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "thursday" => {
-            let days_until =
-                (Weekday::Thu.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Thu.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "friday" => {
-            let days_until =
-                (Weekday::Fri.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Fri.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "saturday" => {
-            let days_until =
-                (Weekday::Sat.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Sat.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
         }
         "sunday" => {
-            let days_until =
-                (Weekday::Sun.num_days_from_monday() - today.weekday().num_days_from_monday() + 7)
-                    % 7;
+            let days_until = (Weekday::Sun.num_days_from_monday() + 7
+                - today.weekday().num_days_from_monday())
+                % 7;
             let days_to_add = if days_until == 0 { 7 } else { days_until };
             let next = today + Duration::days(days_to_add as i64);
             Some(next.format("%Y-%m-%d").to_string())
@@ -1139,19 +2470,48 @@ pub fn calculate_next_occurrence(pattern: &str) -> Option<String> {
     }
 }
 
+/// Map the priority spellings used in notes (`!high`, `p:1`, `high`, ...)
+/// to the canonical `high`/`medium`/`low` stored in the cache
+fn normalize_priority(priority: &str) -> Option<&'static str> {
+    match priority
+        .trim()
+        .trim_start_matches('!')
+        .to_lowercase()
+        .as_str()
+    {
+        "high" | "p:1" => Some("high"),
+        "medium" | "p:2" => Some("medium"),
+        "low" | "p:3" => Some("low"),
+        _ => None,
+    }
+}
+
 fn extract_todos(content: &str) -> Vec<ExtractedTodo> {
     let mut todos = Vec::new();
-    let todo_regex = Regex::new(r"^(\s*)[-*]\s*\[([ xX])\]\s*(.+)$").unwrap();
+    static TODO_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(\s*)[-*]\s*\[([ xX])\]\s*(.+)$").unwrap());
+    let todo_regex = &*TODO_REGEX;
 
     // Regex patterns for due dates and priority
     // Due date formats: @due(2025-01-15), due:2025-01-15, 📅 2025-01-15
-    let due_date_regex = Regex::new(r"(?:@due\(|due:|📅\s*)(\d{4}-\d{2}-\d{2})(?:\))?").unwrap();
+    static DUE_DATE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:@due\(|due:|📅\s*)(\d{4}-\d{2}-\d{2})(?:\))?").unwrap());
+    let due_date_regex = &*DUE_DATE_REGEX;
+
+    // Scheduled (start) date formats: @scheduled(2025-01-15), ⏳ 2025-01-15
+    static SCHEDULED_DATE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:@scheduled\(|⏳\s*)(\d{4}-\d{2}-\d{2})").unwrap());
+    let scheduled_date_regex = &*SCHEDULED_DATE_REGEX;
 
     // Priority formats: !high, !medium, !low, p:1, p:2, p:3
-    let priority_regex = Regex::new(r"(?:!(high|medium|low)|p:([123]))").unwrap();
+    static PRIORITY_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"!(?:high|medium|low)|p:[123]").unwrap());
+    let priority_regex = &*PRIORITY_REGEX;
 
     // Recurrence formats: @every(Monday), @repeat(weekly), @repeat(daily), etc.
-    let recurrence_regex = Regex::new(r"(?:@every|@repeat)\(([^)]+)\)").unwrap();
+    static RECURRENCE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:@every|@repeat)\(([^)]+)\)").unwrap());
+    let recurrence_regex = &*RECURRENCE_REGEX;
 
     // Track todos by indent level to find parent relationships
     let mut indent_stack: Vec<(i32, i32)> = Vec::new(); // (indent_level, line_number)
@@ -1176,23 +2536,18 @@ fn extract_todos(content: &str) -> Vec<ExtractedTodo> {
                 .and_then(|c| c.get(1))
                 .map(|m| m.as_str().to_string());
 
+            // Extract scheduled date
+            let scheduled_date = scheduled_date_regex
+                .captures(&full_content)
+                .and_then(|c| c.get(1))
+                .map(|m| m.as_str().to_string());
+
             // Extract priority
-            let priority = if let Some(caps) = priority_regex.captures(&full_content) {
-                if let Some(text_priority) = caps.get(1) {
-                    Some(text_priority.as_str().to_string())
-                } else if let Some(num_priority) = caps.get(2) {
-                    match num_priority.as_str() {
-                        "1" => Some("high".to_string()),
-                        "2" => Some("medium".to_string()),
-                        "3" => Some("low".to_string()),
-                        _ => None,
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+            let priority = priority_regex
+                .captures(&full_content)
+                .and_then(|c| c.get(0))
+                .and_then(|m| normalize_priority(m.as_str()))
+                .map(str::to_string);
 
             // Extract recurrence pattern
             let recurrence_pattern = recurrence_regex
@@ -1200,6 +2555,22 @@ fn extract_todos(content: &str) -> Vec<ExtractedTodo> {
                 .and_then(|c| c.get(1))
                 .map(|m| m.as_str().to_lowercase().to_string());
 
+            // Strip the task markers so the todo reads as plain text
+            let mut clean_content = full_content.clone();
+            for regex in [
+                due_date_regex,
+                scheduled_date_regex,
+                priority_regex,
+                recurrence_regex,
+            ] {
+                clean_content = regex.replace_all(&clean_content, "").into_owned();
+            }
+            let clean_content = clean_content
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let links = extract_links(&full_content);
+
             // Find parent todo (last todo with indent level one less than current)
             let parent_line = if indent_level > 0 {
                 // Remove all items from stack that are at same or deeper level
@@ -1224,6 +2595,9 @@ fn extract_todos(content: &str) -> Vec<ExtractedTodo> {
                 indent_level,
                 parent_line,
                 recurrence_pattern,
+                scheduled_date,
+                clean_content,
+                links,
             ));
         }
     }
@@ -1231,52 +2605,128 @@ fn extract_todos(content: &str) -> Vec<ExtractedTodo> {
     todos
 }
 
-fn extract_blocks(content: &str) -> Vec<(String, i32, String)> {
+/// Block id for a heading: lowercase, whitespace runs become hyphens and
+/// other punctuation becomes underscores
+pub(crate) fn heading_slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c
+            } else if c.is_whitespace() {
+                '-'
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+pub(crate) fn extract_blocks(content: &str) -> Vec<Block> {
     let mut blocks = Vec::new();
+    let mut heading_ids = HashSet::new();
     // Match markdown headings: # Heading, ## Heading, etc.
-    let heading_regex = Regex::new(r"^(#{1,6})\s+(.+)$").unwrap();
-
-    for (line_number, line) in content.lines().enumerate() {
+    static HEADING_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.+)$").unwrap());
+    let heading_regex = &*HEADING_REGEX;
+    // Match an explicit block id at the end of a line: "Some text ^abc123"
+    static BLOCK_ID_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:^|\s)\^([a-zA-Z0-9-]+)\s*$").unwrap());
+    let block_id_regex = &*BLOCK_ID_REGEX;
+    static LIST_ITEM_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s").unwrap());
+    let list_item_regex = &*LIST_ITEM_REGEX;
+
+    let lines: Vec<&str> = content.lines().collect();
+    for (line_number, line) in lines.iter().enumerate() {
         if let Some(captures) = heading_regex.captures(line) {
             let heading_text = captures[2].trim();
 
-            // Generate block ID from heading text (slugify)
-            // Convert to lowercase, replace spaces and special chars with hyphens
-            let block_id = heading_text
-                .to_lowercase()
-                .chars()
-                .map(|c| {
-                    if c.is_alphanumeric() {
-                        c
-                    } else if c.is_whitespace() {
-                        '-'
-                    } else {
-                        '_'
+            let block_id = heading_slug(heading_text);
+
+            // Repeated headings get -2, -3, ... so each keeps its own block
+            let mut unique_id = block_id.clone();
+            let mut suffix = 2;
+            while !heading_ids.insert(unique_id.clone()) {
+                unique_id = format!("{block_id}-{suffix}");
+                suffix += 1;
+            }
+
+            blocks.push(Block {
+                block_id: unique_id,
+                line_number: line_number as i32 + 1,
+                content: heading_text.to_string(),
+                block_type: BLOCK_TYPE_HEADING.to_string(),
+                level: captures[1].len() as i32,
+            });
+        } else if let Some(captures) = block_id_regex.captures(line) {
+            let id_match = captures.get(0).unwrap();
+            let text = line[..id_match.start()].trim_end();
+
+            // A list item is its own block; otherwise the block is the
+            // paragraph ending at this line. A line holding only the id
+            // refers to the paragraph right above it.
+            let last = if text.trim().is_empty() {
+                match line_number.checked_sub(1) {
+                    Some(previous) if !lines[previous].trim().is_empty() => previous,
+                    _ => continue,
+                }
+            } else {
+                line_number
+            };
+            let mut first = last;
+            if !list_item_regex.is_match(lines[last]) {
+                while first > 0 {
+                    let previous = lines[first - 1];
+                    if previous.trim().is_empty()
+                        || heading_regex.is_match(previous)
+                        || list_item_regex.is_match(previous)
+                    {
+                        break;
                     }
-                })
-                .collect::<String>()
-                .split('-')
-                .filter(|s| !s.is_empty())
-                .collect::<Vec<&str>>()
-                .join("-");
+                    first -= 1;
+                }
+            }
+
+            let mut paragraph: Vec<&str> = lines[first..last].to_vec();
+            if last == line_number {
+                paragraph.push(text);
+            } else {
+                paragraph.push(lines[last]);
+            }
 
-            blocks.push((block_id, line_number as i32 + 1, heading_text.to_string()));
+            blocks.push(Block {
+                block_id: format!("^{}", &captures[1]),
+                line_number: first as i32 + 1,
+                content: paragraph.join("\n"),
+                block_type: BLOCK_TYPE_PARAGRAPH.to_string(),
+                level: 0,
+            });
         }
     }
 
     blocks
 }
 
-// Extract bookmarks from note content
+// Extract bookmarks from note content, one per URL (its first occurrence)
 // Returns: Vec<(url, title, line_number, tags)>
 fn extract_bookmarks(content: &str) -> Vec<(String, Option<String>, i32, Option<String>)> {
     let mut bookmarks = Vec::new();
 
     // Regex for markdown links: [text](url)
-    let markdown_link_regex = Regex::new(r"\[([^\]]+)\]\((https?://[^\)]+)\)").unwrap();
+    static MARKDOWN_LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\((https?://[^\)]+)\)").unwrap());
+    let markdown_link_regex = &*MARKDOWN_LINK_REGEX;
 
     // Regex for plain URLs: http:// or https://
-    let plain_url_regex = Regex::new(r"(?:^|\s)(https?://[^\s<>\)\]]+)").unwrap();
+    static PLAIN_URL_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?:^|\s)(https?://[^\s<>\)\]]+)").unwrap());
+    let plain_url_regex = &*PLAIN_URL_REGEX;
 
     for (line_number, line) in content.lines().enumerate() {
         let line_num = line_number as i32 + 1;
@@ -1286,6 +2736,9 @@ fn extract_bookmarks(content: &str) -> Vec<(String, Option<String>, i32, Option<
             if let (Some(title), Some(url)) = (captures.get(1), captures.get(2)) {
                 let url_str = url.as_str().to_string();
                 let title_str = title.as_str().to_string();
+                if bookmarks.iter().any(|(u, _, _, _)| u == &url_str) {
+                    continue;
+                }
 
                 // Extract tags from the same line
                 let tags = extract_tags_from_line(line);
@@ -1299,10 +2752,8 @@ fn extract_bookmarks(content: &str) -> Vec<(String, Option<String>, i32, Option<
             if let Some(url) = captures.get(1) {
                 let url_str = url.as_str().to_string();
 
-                // Check if this URL was already captured as a markdown link
-                let already_captured = bookmarks
-                    .iter()
-                    .any(|(u, _, ln, _)| u == &url_str && *ln == line_num);
+                // Skip URLs already captured, as a markdown link or earlier on
+                let already_captured = bookmarks.iter().any(|(u, _, _, _)| u == &url_str);
 
                 if !already_captured {
                     // Extract tags from the same line
@@ -1317,11 +2768,12 @@ fn extract_bookmarks(content: &str) -> Vec<(String, Option<String>, i32, Option<
     bookmarks
 }
 
+/// Tags on a bookmark's line, parsed like note tags
 fn extract_tags_from_line(line: &str) -> Option<String> {
-    let tag_regex = Regex::new(r"#(\w+)").unwrap();
-    let tags: Vec<String> = tag_regex
-        .captures_iter(line)
-        .map(|cap| cap[1].to_string())
+    let mut seen = HashSet::new();
+    let tags: Vec<String> = extract_tags(line)
+        .into_iter()
+        .filter(|tag| seen.insert(tag.clone()))
         .collect();
 
     if tags.is_empty() {
@@ -1399,6 +2851,39 @@ mod tests {
         assert_eq!(links[1], "Another Note#section");
     }
 
+    #[test]
+    fn test_extract_blocks_with_block_ids() {
+        let content = "# Intro\n\nFirst line\nsecond line ^para-1\n\n- item one ^item\n- item two\n\n| a | b |\n^table";
+        let blocks = extract_blocks(content);
+        let summary: Vec<(&str, i32, &str, &str, i32)> = blocks
+            .iter()
+            .map(|b| {
+                (
+                    b.block_id.as_str(),
+                    b.line_number,
+                    b.content.as_str(),
+                    b.block_type.as_str(),
+                    b.level,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("intro", 1, "Intro", BLOCK_TYPE_HEADING, 1),
+                (
+                    "^para-1",
+                    3,
+                    "First line\nsecond line",
+                    BLOCK_TYPE_PARAGRAPH,
+                    0
+                ),
+                ("^item", 6, "- item one", BLOCK_TYPE_PARAGRAPH, 0),
+                ("^table", 9, "| a | b |", BLOCK_TYPE_PARAGRAPH, 0),
+            ]
+        );
+    }
+
     #[test]
     fn test_extract_tags() {
         let content = "This has #tag1 and #tag2 tags";
@@ -1407,4 +2892,400 @@ mod tests {
         assert_eq!(tags[0], "tag1");
         assert_eq!(tags[1], "tag2");
     }
+
+    #[test]
+    fn test_extract_nested_tags_includes_ancestors() {
+        let tags = extract_tags("#project/work/q3 and #solo");
+        assert_eq!(
+            tags,
+            vec!["project", "project/work", "project/work/q3", "solo"]
+        );
+    }
+
+    #[test]
+    fn test_tag_suggestions_rank_by_usage() {
+        let db = CacheDb::new(":memory:").unwrap();
+        for (path, content) in [
+            ("/notes/a.md", "#project/work #plan"),
+            ("/notes/b.md", "#project/home #Plan_b"),
+            ("/notes/c.md", "#project/work #other"),
+        ] {
+            db.update_note_cache(path, content, "/nonexistent").unwrap();
+        }
+
+        let suggestions = db.get_tag_suggestions("proj", 10).unwrap();
+        assert_eq!(
+            suggestions,
+            vec![
+                ("project".to_string(), 3),
+                ("project/work".to_string(), 2),
+                ("project/home".to_string(), 1),
+            ]
+        );
+        assert_eq!(db.get_tag_suggestions("PROJECT/", 1).unwrap().len(), 1);
+        // `_` is matched literally, not as a LIKE wildcard
+        assert_eq!(
+            db.get_tag_suggestions("plan_", 10).unwrap(),
+            vec![("Plan_b".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_note_bookmarks_are_deduped_by_url() {
+        let db = CacheDb::new(":memory:").unwrap();
+        let content = "Read [Rust](https://www.rust-lang.org/learn) #lang\n\
+                       Again https://www.rust-lang.org/learn\n\
+                       Also https://docs.rs/regex\n\
+                       Broken http://:80/nowhere\n";
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+        // Re-indexing an edited note replaces its bookmarks
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+
+        let bookmarks = db.get_all_bookmarks().unwrap();
+        let mut summary: Vec<_> = bookmarks
+            .iter()
+            .map(|b| {
+                (
+                    b.url.as_str(),
+                    b.title.as_deref(),
+                    b.line_number,
+                    b.domain.as_str(),
+                )
+            })
+            .collect();
+        summary.sort();
+        assert_eq!(
+            summary,
+            vec![
+                ("https://docs.rs/regex", None, Some(3), "docs.rs"),
+                (
+                    "https://www.rust-lang.org/learn",
+                    Some("Rust"),
+                    Some(1),
+                    "rust-lang.org"
+                ),
+            ]
+        );
+        assert_eq!(db.get_bookmarks_by_domain("docs.rs").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bookmark_favorites_and_visits_survive_reindexing() {
+        let db = CacheDb::new(":memory:").unwrap();
+        let content = "https://docs.rs/regex\nhttps://crates.io\n";
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+        db.add_bookmark("https://crates.io", None, None, None, None, None)
+            .unwrap();
+
+        let id_of = |url: &str| {
+            db.get_all_bookmarks()
+                .unwrap()
+                .into_iter()
+                .find(|b| b.url == url && b.note_path.is_some())
+                .unwrap()
+                .id
+        };
+        assert!(db
+            .toggle_bookmark_favorite(id_of("https://docs.rs/regex"))
+            .unwrap());
+        db.record_bookmark_visit("https://crates.io").unwrap();
+        db.record_bookmark_visit("https://crates.io").unwrap();
+        db.record_bookmark_visit("https://docs.rs/regex").unwrap();
+        db.record_bookmark_visit("https://unknown.example").unwrap();
+
+        // Editing the note rebuilds its bookmark rows
+        db.update_note_cache("/notes/a.md", &format!("Intro\n{content}"), "/nonexistent")
+            .unwrap();
+
+        let frequent = db.get_frequent_bookmarks(10).unwrap();
+        let summary: Vec<_> = frequent
+            .iter()
+            .map(|b| (b.url.as_str(), b.visit_count, b.is_favorite))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("https://crates.io", 2, false),
+                ("https://docs.rs/regex", 1, true)
+            ]
+        );
+        assert_eq!(db.get_frequent_bookmarks(1).unwrap().len(), 1);
+        assert!(!db
+            .toggle_bookmark_favorite(id_of("https://docs.rs/regex"))
+            .unwrap());
+        assert!(db.toggle_bookmark_favorite(-1).is_err());
+    }
+
+    #[test]
+    fn test_extract_aliases() {
+        let inline = "---\naliases: [Foo, \"Bar Baz\"]\n---\n# Note\n";
+        assert_eq!(extract_aliases(inline), vec!["Foo", "Bar Baz"]);
+
+        let block = "---\ntitle: x\naliases:\n  - Foo\n  - Bar\ntags: [a]\n---\n";
+        assert_eq!(extract_aliases(block), vec!["Foo", "Bar"]);
+
+        let no_frontmatter = "aliases: [Foo]\n";
+        assert!(extract_aliases(no_frontmatter).is_empty());
+    }
+
+    #[test]
+    fn test_extract_todos_scheduled_and_due_dates() {
+        let content = "- [ ] Draft report @scheduled(2025-01-10) @due(2025-01-15)\n\
+                       - [ ] Call back ⏳ 2025-02-01\n\
+                       - [ ] Plain task\n";
+        let todos = extract_todos(content);
+        assert_eq!(todos.len(), 3);
+
+        assert_eq!(todos[0].3.as_deref(), Some("2025-01-15"));
+        assert_eq!(todos[0].8.as_deref(), Some("2025-01-10"));
+
+        assert_eq!(todos[1].3, None);
+        assert_eq!(todos[1].8.as_deref(), Some("2025-02-01"));
+
+        assert_eq!(todos[2].3, None);
+        assert_eq!(todos[2].8, None);
+    }
+
+    #[test]
+    fn test_get_overdue_todos_skips_future_missing_and_malformed_dates() {
+        let db = CacheDb::new(":memory:").unwrap();
+        let content = "- [ ] Later @due(2999-01-01)\n\
+                       - [ ] Newer overdue @due(2001-06-01)\n\
+                       - [ ] Older overdue @due(2000-01-01)\n\
+                       - [x] Done overdue @due(2000-01-01)\n\
+                       - [ ] Impossible @due(2000-13-45)\n\
+                       - [ ] No date\n";
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+
+        let overdue = db.get_overdue_todos().unwrap();
+        let dates: Vec<_> = overdue.iter().map(|t| t.due_date.as_deref()).collect();
+        assert_eq!(dates, vec![Some("2000-01-01"), Some("2001-06-01")]);
+    }
+
+    #[test]
+    fn test_todos_by_priority_and_sorted() {
+        let db = CacheDb::new(":memory:").unwrap();
+        let content = "- [ ] Plain\n\
+                       - [ ] Low numeric p:3\n\
+                       - [ ] High late !high @due(2030-01-02)\n\
+                       - [ ] High early p:1 @due(2030-01-01)\n\
+                       - [ ] High undated !high\n\
+                       - [x] Done !high\n\
+                       - [ ] Medium !medium\n";
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+
+        let high = db.get_todos_by_priority("high").unwrap();
+        assert_eq!(high.len(), 3);
+        assert_eq!(db.get_todos_by_priority("p:3").unwrap().len(), 1);
+        assert!(db.get_todos_by_priority("urgent").is_err());
+
+        let sorted = db.get_incomplete_todos_sorted().unwrap();
+        let lines: Vec<i32> = sorted.iter().map(|t| t.line_number).collect();
+        assert_eq!(lines, vec![4, 3, 5, 7, 2, 1]);
+    }
+
+    #[test]
+    fn test_get_all_tags_with_counts() {
+        let db = CacheDb::new(":memory:").unwrap();
+        db.update_note_cache(
+            "/notes/a.md",
+            "#rust and #rust again, #notes",
+            "/nonexistent",
+        )
+        .unwrap();
+        db.update_note_cache("/notes/b.md", "#rust", "/nonexistent")
+            .unwrap();
+
+        let counts = db.get_all_tags_with_counts().unwrap();
+        assert_eq!(
+            counts,
+            vec![("rust".to_string(), 2), ("notes".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_get_todos_agenda_buckets() {
+        use chrono::{Duration, Local};
+
+        let today = Local::now().date_naive();
+        let fmt = |offset: i64| {
+            (today + Duration::days(offset))
+                .format("%Y-%m-%d")
+                .to_string()
+        };
+        let content = format!(
+            "- [ ] Due today @due({})\n\
+             - [ ] Due in two days @repeat(daily) @due({})\n\
+             - [ ] Overdue @due({})\n\
+             - [ ] Too far out @due({})\n\
+             - [ ] Someday\n",
+            fmt(0),
+            fmt(2),
+            fmt(-3),
+            fmt(30)
+        );
+
+        let db = CacheDb::new(":memory:").unwrap();
+        db.update_note_cache("/notes/a.md", &content, "/nonexistent")
+            .unwrap();
+
+        let agenda = db.get_todos_agenda(7).unwrap();
+        assert_eq!(
+            agenda.len(),
+            8 + 2,
+            "one bucket per day plus overdue/no_date"
+        );
+        assert_eq!(agenda[&fmt(0)].len(), 1);
+        assert_eq!(agenda[&fmt(1)].len(), 0);
+        assert_eq!(agenda[&fmt(2)].len(), 1, "recurring todo listed once");
+        assert_eq!(agenda["overdue"].len(), 1);
+        assert_eq!(agenda["no_date"].len(), 1);
+        assert!(!agenda.contains_key(&fmt(30)));
+
+        let capped = db.get_todos_agenda(u32::MAX).unwrap();
+        assert_eq!(capped.len(), MAX_AGENDA_DAYS as usize + 1 + 2);
+    }
+
+    #[test]
+    fn test_calculate_next_occurrence_intervals() {
+        use chrono::NaiveDate;
+
+        let friday = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        let wednesday = NaiveDate::from_ymd_opt(2025, 1, 8).unwrap();
+        let jan_31 = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let next = |pattern: &str, from| calculate_next_occurrence_from(pattern, from);
+
+        assert_eq!(next("every:3d", friday).as_deref(), Some("2025-01-13"));
+        assert_eq!(next("every:2w", friday).as_deref(), Some("2025-01-24"));
+        assert_eq!(next("every:1m", jan_31).as_deref(), Some("2025-02-28"));
+        assert_eq!(next("every:weekday", friday).as_deref(), Some("2025-01-13"));
+        assert_eq!(
+            next("every:weekday", wednesday).as_deref(),
+            Some("2025-01-09")
+        );
+        assert_eq!(next("every:monday", friday).as_deref(), Some("2025-01-13"));
+
+        assert_eq!(next("every:0d", friday), None);
+        assert_eq!(next("every:xd", friday), None);
+        assert_eq!(next("every:", friday), None);
+        assert_eq!(next("every:4294967295d", friday), None);
+        assert_eq!(next("every:4294967295w", friday), None);
+        assert_eq!(next("fortnightly", friday), None);
+    }
+
+    #[test]
+    fn test_calculate_next_occurrence_keywords_unchanged() {
+        use chrono::NaiveDate;
+
+        let monday = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let next = |pattern: &str| calculate_next_occurrence_from(pattern, monday);
+
+        assert_eq!(next("daily").as_deref(), Some("2025-01-07"));
+        assert_eq!(next("Weekly").as_deref(), Some("2025-01-13"));
+        assert_eq!(next("monthly").as_deref(), Some("2025-02-06"));
+        assert_eq!(next("monday").as_deref(), Some("2025-01-13"));
+        assert_eq!(next("friday").as_deref(), Some("2025-01-10"));
+    }
+
+    #[test]
+    fn test_set_search_tokenizer_rebuilds_index() {
+        let db = CacheDb::new(":memory:").unwrap();
+        db.add_note_content("/notes/run.md", "Late", "I was running late")
+            .unwrap();
+        assert_eq!(
+            db.search_tokenizer().unwrap(),
+            Some(SearchTokenizer::Porter)
+        );
+        assert_eq!(db.search_notes_fts("run").unwrap(), ["/notes/run.md"]);
+        assert!(!db.set_search_tokenizer(SearchTokenizer::Porter).unwrap());
+
+        assert!(db.set_search_tokenizer(SearchTokenizer::Unicode61).unwrap());
+        assert_eq!(
+            db.search_tokenizer().unwrap(),
+            Some(SearchTokenizer::Unicode61)
+        );
+        assert!(db.search_notes_fts("run").unwrap().is_empty());
+        assert_eq!(db.search_notes_fts("running").unwrap(), ["/notes/run.md"]);
+
+        assert!(db.set_search_tokenizer(SearchTokenizer::Trigram).unwrap());
+        assert_eq!(db.search_notes_fts("unnin").unwrap(), ["/notes/run.md"]);
+
+        db.add_note_content("/notes/later.md", "Later", "added after the rebuild")
+            .unwrap();
+        assert_eq!(db.search_notes_fts("rebui").unwrap(), ["/notes/later.md"]);
+    }
+
+    #[test]
+    fn test_tags_and_links_in_code_are_ignored() {
+        let content = "#real tag and [[Real Link]]\n\
+            ```c\n#include <stdio.h>\nchar *s = \"[[not a link]]\";\n```\n\
+            Color `#000000` and `[[not a link]]` and ``a ` #tick``\n\
+            ~~~\n#fenced\n~~~\n\
+            After #after and a lone ` backtick #loose\n";
+
+        assert_eq!(extract_tags(content), ["real", "after", "loose"]);
+        assert_eq!(extract_links(content), ["Real Link"]);
+        assert_eq!(
+            strip_code("a `b` c\n```\nx\n```\nd")
+                .lines()
+                .collect::<Vec<_>>(),
+            ["a     c", "   ", " ", "   ", "d"]
+        );
+    }
+
+    #[test]
+    fn test_bookmark_tags_are_parsed_like_note_tags() {
+        let bookmarks = extract_bookmarks(
+            "[Docs](https://example.com/guide#setup) #project/work #read `#code` #read\n\
+             https://example.com/#top no tags here\n",
+        );
+        assert_eq!(bookmarks[0].3.as_deref(), Some("project,project/work,read"));
+        assert_eq!(bookmarks[1].3, None);
+    }
+
+    #[test]
+    fn test_numbers_and_hex_colors_are_not_tags() {
+        let content = "#todo fix #123 and #fff, #FF0000, #0a0b0c80 and #issue-42\n\
+            #2024-review #project/sub-task #123/notes #x1 #under_score #café";
+        assert_eq!(
+            extract_tags(content),
+            [
+                "todo",
+                "issue-42",
+                "2024-review",
+                "project",
+                "project/sub-task",
+                "123/notes",
+                "x1",
+                "under_score",
+                "café",
+            ]
+        );
+        assert!(is_tag_name("todo"));
+        assert!(!is_tag_name("123"));
+        assert!(!is_tag_name("fff"));
+    }
+
+    #[test]
+    fn test_todo_clean_content_strips_markers() {
+        let db = CacheDb::new(":memory:").unwrap();
+        let content = "- [ ] Buy milk @due(2025-01-01) !high\n\
+                       - [ ] Water   plants @every(Monday) ⏳ 2025-02-01 p:3\n";
+        db.update_note_cache("/notes/a.md", content, "/nonexistent")
+            .unwrap();
+
+        let todos = db.get_incomplete_todos().unwrap();
+        assert_eq!(todos[0].content, "Buy milk @due(2025-01-01) !high");
+        assert_eq!(todos[0].clean_content, "Buy milk");
+        assert_eq!(todos[0].due_date.as_deref(), Some("2025-01-01"));
+        assert_eq!(todos[0].priority.as_deref(), Some("high"));
+
+        assert_eq!(todos[1].clean_content, "Water plants");
+        assert_eq!(todos[1].recurrence_pattern.as_deref(), Some("monday"));
+    }
 }
@@ -1,13 +1,24 @@
-use crate::cache::{Bookmark, CacheDb, Todo};
+use crate::attachments;
+use crate::cache::{heading_slug, Bookmark, CacheDb, SearchTokenizer, Todo, BLOCK_TYPE_PARAGRAPH};
 use crate::error::AppError;
-use crate::git_manager::{GitBlameInfo, GitManager};
-use crate::note_manager::{self, read_file_with_encoding, Note, NoteMetadata};
-use crate::utils::{ensure_dir_exists, safe_read_file, safe_write_file, validate_path_security};
+use crate::export;
+use crate::favicons;
+use crate::folds;
+use crate::git_manager::{GitBlameInfo, GitManager, GitStatus};
+use crate::import;
+use crate::links::{self, LinkChange, LinkStyle};
+use crate::note_manager::{self, read_file_with_encoding, Note, NoteMetadata, UnlinkedMention};
+use crate::trash;
+use crate::utils::{
+    ensure_dir_exists, is_valid_css_color, safe_read_file, safe_write_file, validate_path_security,
+};
+use crate::web;
 use chrono::{Duration as ChronoDuration, Local, TimeZone};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{State, WebviewWindow};
 
@@ -26,7 +37,7 @@ pub struct CustomTheme {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppSettings {
     pub theme: String, // "dark", "light", "custom"
-    pub font_size: u8, // 12-24
+    pub font_size: u8, // MIN_FONT_SIZE-MAX_FONT_SIZE
     pub custom_theme: Option<CustomTheme>,
     pub show_git_blame: bool, // whether to show git blame info in editor
     pub window_width: Option<f64>,
@@ -34,6 +45,72 @@ pub struct AppSettings {
     pub window_x: Option<f64>,
     pub window_y: Option<f64>,
     pub window_maximized: Option<bool>,
+    // Minutes of inactivity before auto-committing; None or 0 disables auto-commit
+    #[serde(default = "default_git_autocommit_minutes")]
+    pub git_autocommit_minutes: Option<u64>,
+    #[serde(default)]
+    pub git_include_attachments: bool, // also commit images/ and attachments/
+    #[serde(default)]
+    pub git_author_name: Option<String>,
+    #[serde(default)]
+    pub git_author_email: Option<String>,
+    #[serde(default)]
+    pub max_attachment_mb: Option<u64>, // None means no size limit
+    #[serde(default)]
+    pub allowed_attachment_extensions: Option<Vec<String>>, // None allows any type
+    #[serde(default)]
+    pub safe_attachments: bool, // reject executables and scripts
+    // Vault-relative folder for daily, weekly and monthly notes
+    #[serde(default = "default_daily_notes_folder")]
+    pub daily_notes_folder: String,
+    #[serde(default)]
+    pub link_style: LinkStyle, // how the editor writes new links
+    // Lets the app fetch bookmark details from the web; off keeps it fully offline
+    #[serde(default = "default_allow_network_requests")]
+    pub allow_network_requests: bool,
+    #[serde(default = "default_favicon_cache_days")]
+    pub favicon_cache_days: u32, // refetch cached site icons after this long
+    #[serde(default)]
+    pub search_tokenizer: SearchTokenizer, // changing it rebuilds the search index
+}
+
+pub const MIN_FONT_SIZE: u8 = 12;
+pub const MAX_FONT_SIZE: u8 = 24;
+
+impl CustomTheme {
+    /// Names of the fields that aren't valid CSS colors
+    fn invalid_colors(&self) -> Vec<&'static str> {
+        [
+            ("bg_primary", &self.bg_primary),
+            ("bg_secondary", &self.bg_secondary),
+            ("text_primary", &self.text_primary),
+            ("text_secondary", &self.text_secondary),
+            ("border_color", &self.border_color),
+            ("accent_color", &self.accent_color),
+            ("hover_color", &self.hover_color),
+            ("active_color", &self.active_color),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !is_valid_css_color(value))
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+fn default_git_autocommit_minutes() -> Option<u64> {
+    Some(5)
+}
+
+fn default_allow_network_requests() -> bool {
+    true
+}
+
+fn default_favicon_cache_days() -> u32 {
+    30
+}
+
+fn default_daily_notes_folder() -> String {
+    note_manager::DEFAULT_DAILY_NOTES_FOLDER.to_string()
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -56,6 +133,18 @@ impl Default for AppSettings {
             window_x: None,
             window_y: None,
             window_maximized: None,
+            git_autocommit_minutes: default_git_autocommit_minutes(),
+            git_include_attachments: false,
+            git_author_name: None,
+            git_author_email: None,
+            max_attachment_mb: None,
+            allowed_attachment_extensions: None,
+            safe_attachments: false,
+            daily_notes_folder: default_daily_notes_folder(),
+            link_style: LinkStyle::default(),
+            allow_network_requests: default_allow_network_requests(),
+            favicon_cache_days: default_favicon_cache_days(),
+            search_tokenizer: SearchTokenizer::default(),
         }
     }
 }
@@ -63,11 +152,68 @@ impl Default for AppSettings {
 pub struct AppState {
     pub cache_db: Mutex<CacheDb>,
     pub git_manager: Mutex<GitManager>,
-    pub notes_dir: String,
+    // Changed at runtime by set_notes_directory; read through notes_dir()
+    notes_dir: RwLock<String>,
     pub recent_notes: Mutex<VecDeque<RecentNote>>,
+    pub notes_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    // Set by cancel_rebuild; checked between notes by force_rebuild_cache
+    pub rebuild_cancelled: AtomicBool,
+    // Syncs and rebuilds in progress; see begin_cache_job
+    cache_jobs: AtomicUsize,
+}
+
+/// Marks a sync or rebuild as running until dropped
+pub(crate) struct CacheJobGuard<'a>(&'a AtomicUsize);
+
+impl Drop for CacheJobGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl AppState {
+    pub fn new(cache_db: CacheDb, git_manager: GitManager, notes_dir: String) -> Self {
+        Self {
+            cache_db: Mutex::new(cache_db),
+            git_manager: Mutex::new(git_manager),
+            notes_dir: RwLock::new(notes_dir),
+            recent_notes: Mutex::new(VecDeque::new()),
+            notes_watcher: Mutex::new(None),
+            rebuild_cancelled: AtomicBool::new(false),
+            cache_jobs: AtomicUsize::new(0),
+        }
+    }
+
+    /// Record that a sync or rebuild is running. These release the cache
+    /// lock between notes, so the lock alone can't keep `optimize_cache`
+    /// from running in the middle of one.
+    pub(crate) fn begin_cache_job(&self) -> CacheJobGuard<'_> {
+        self.cache_jobs.fetch_add(1, Ordering::SeqCst);
+        CacheJobGuard(&self.cache_jobs)
+    }
+
+    /// Whether a sync or rebuild is running
+    pub fn cache_job_running(&self) -> bool {
+        self.cache_jobs.load(Ordering::SeqCst) > 0
+    }
+
+    /// The notes directory currently in use
+    pub fn notes_dir(&self) -> String {
+        match self.notes_dir.read() {
+            Ok(notes_dir) => notes_dir.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn set_notes_dir(&self, notes_dir: String) {
+        match self.notes_dir.write() {
+            Ok(mut current) => *current = notes_dir,
+            Err(poisoned) => *poisoned.into_inner() = notes_dir,
+        }
+    }
 }
 
-fn get_file_mtime(path: &str) -> Result<(i64, u32), String> {
+pub(crate) fn get_file_mtime(path: &str) -> Result<(i64, u32), String> {
     let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to get metadata: {e}"))?;
     let modified = metadata
         .modified()
@@ -84,23 +230,120 @@ fn update_cached_mtime(cache_db: &CacheDb, path: &str) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn get_notes_list(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
-    note_manager::list_notes(&state.notes_dir)
+pub async fn get_notes_list(
+    sort_by: Option<note_manager::SortBy>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NoteMetadata>, String> {
+    let mut notes = note_manager::list_notes(&state.notes_dir())?;
+    {
+        let cache_db = lock_mutex!(
+            state.cache_db,
+            "Cache database mutex was poisoned during get_notes_list"
+        );
+        apply_created_times(&cache_db, &mut notes)?;
+    }
+    if let Some(sort_by) = sort_by {
+        note_manager::sort_notes(&mut notes, sort_by);
+    }
+    Ok(notes)
+}
+
+/// Replace each note's filesystem creation time with the one first recorded
+/// for its path. Saves replace the file, so the filesystem value only
+/// stands in for notes seen for the first time.
+pub(crate) fn apply_created_times(
+    cache_db: &CacheDb,
+    notes: &mut [NoteMetadata],
+) -> Result<(), String> {
+    let observed: Vec<(String, i64)> = notes
+        .iter()
+        .map(|note| (note.path.clone(), note.created))
+        .collect();
+    let recorded = cache_db.sync_created_times(&observed)?;
+    for note in notes {
+        if let Some(created) = recorded.get(&note.path) {
+            note.created = *created;
+        }
+    }
+    Ok(())
+}
+
+/// One slice of the sorted notes list
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotesPage {
+    pub notes: Vec<NoteMetadata>,
+    /// Number of notes in the whole list
+    pub total: usize,
+}
+
+#[tauri::command]
+pub async fn get_notes_list_page(
+    offset: usize,
+    limit: usize,
+    sort_by: Option<note_manager::SortBy>,
+    state: State<'_, AppState>,
+) -> Result<NotesPage, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_notes_list_page"
+    );
+    notes_page(
+        &state.notes_dir(),
+        &cache_db,
+        offset,
+        limit,
+        sort_by.unwrap_or_default(),
+    )
+}
+
+/// Up to `limit` notes starting at `offset` in `sort_by` order. An offset
+/// past the end yields an empty page.
+pub(crate) fn notes_page(
+    notes_dir: &str,
+    cache_db: &CacheDb,
+    offset: usize,
+    limit: usize,
+    sort_by: note_manager::SortBy,
+) -> Result<NotesPage, String> {
+    let mut notes = note_manager::list_notes(notes_dir)?;
+    apply_created_times(cache_db, &mut notes)?;
+    let total = notes.len();
+    note_manager::sort_notes(&mut notes, sort_by);
+    let notes = notes.into_iter().skip(offset).take(limit).collect();
+    Ok(NotesPage { notes, total })
 }
 
 #[tauri::command]
 pub async fn read_note(path: String, state: State<'_, AppState>) -> Result<Note, String> {
-    validate_path_security(&path, &state.notes_dir).map_err(|e| e.to_string())?;
-    note_manager::read_note(&path)
+    validate_path_security(&path, &state.notes_dir()).map_err(|e| e.to_string())?;
+    let mut note = note_manager::read_note(&path)?;
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during read_note"
+    );
+    note.created = cache_db.record_created_time(&path, note.created)?;
+    Ok(note)
 }
 
 #[tauri::command]
 pub async fn save_note(
     path: String,
     content: String,
+    expected_mtime: Option<(i64, u32)>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    validate_path_security(&path, &state.notes_dir).map_err(|e| e.to_string())?;
+) -> Result<(i64, u32), String> {
+    validate_path_security(&path, &state.notes_dir()).map_err(|e| e.to_string())?;
+    if let Some(expected_mtime) = expected_mtime {
+        check_save_conflict(&path, expected_mtime)?;
+    }
+    // The editor works with LF; keep CRLF files as CRLF
+    let content = match read_file_with_encoding(&path) {
+        Ok(existing) => {
+            note_manager::with_line_ending(&content, note_manager::line_ending(&existing))
+        }
+        Err(_) => content,
+    };
     note_manager::write_note(&path, &content)?;
 
     // Add to recent notes and get title
@@ -111,7 +354,7 @@ pub async fn save_note(
         "Cache database mutex was poisoned during save_note"
     );
     // Update cache including FTS5 index
-    cache_db.update_note_cache_with_fts(&path, &note.title, &content, &state.notes_dir)?;
+    cache_db.update_note_cache_with_fts(&path, &note.title, &content, &state.notes_dir())?;
     update_cached_mtime(&cache_db, &path)?;
     let folder = std::path::Path::new(&path)
         .parent()
@@ -128,7 +371,25 @@ pub async fn save_note(
         "Git manager mutex was poisoned during save_note"
     );
     if git_manager.is_git_repo() {
-        git_manager.schedule_auto_commit();
+        git_manager.schedule_auto_commit(app);
+    }
+
+    Ok((note.last_modified, note.last_modified_nanos))
+}
+
+/// Fail with `AppError::Conflict` if `path` was modified after
+/// `expected_mtime`, given as (seconds, nanoseconds) since the epoch like
+/// `Note::last_modified` and `Note::last_modified_nanos`. Comparing the
+/// nanoseconds catches edits made within the same second.
+pub(crate) fn check_save_conflict(path: &str, expected_mtime: (i64, u32)) -> Result<(), AppError> {
+    if !Path::new(path).exists() {
+        return Ok(());
+    }
+
+    if get_file_mtime(path)? > expected_mtime {
+        return Err(AppError::Conflict(format!(
+            "{path} was modified on disk since it was opened"
+        )));
     }
 
     Ok(())
@@ -136,20 +397,51 @@ pub async fn save_note(
 
 #[tauri::command]
 pub async fn create_note(filename: String, state: State<'_, AppState>) -> Result<String, String> {
-    let path = std::path::Path::new(&state.notes_dir)
-        .join(&filename)
+    create_note_with_content(&filename, &state, |_| Ok(format!("# {filename}\n\n")))
+}
+
+#[tauri::command]
+pub async fn create_note_from_template(
+    filename: String,
+    template_name: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    create_note_with_content(&filename, &state, |title| {
+        note_manager::render_template(&state.notes_dir(), &template_name, title)
+    })
+}
+
+#[tauri::command]
+pub async fn list_templates(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    note_manager::list_templates(&state.notes_dir())
+}
+
+/// Create `filename` with the content built from its title, and index it.
+/// An existing note is left alone and its path returned.
+pub(crate) fn create_note_with_content(
+    filename: &str,
+    state: &AppState,
+    content: impl FnOnce(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&state.notes_dir())
+        .join(filename)
         .with_extension("md");
 
     let path_str = path.to_string_lossy().to_string();
 
-    validate_path_security(&path_str, &state.notes_dir).map_err(|e| e.to_string())?;
+    validate_path_security(&path_str, &state.notes_dir()).map_err(|e| e.to_string())?;
 
-    if path.exists() {
-        // Return the existing path instead of an error
-        return Ok(path_str);
+    // Return the existing path instead of an error. Wikilinks ignore case,
+    // so `foo` opens an existing `Foo.md` rather than creating a twin.
+    if let Some(existing) = note_manager::find_case_insensitive_match(&path) {
+        return Ok(existing.to_string_lossy().to_string());
     }
 
-    let content = format!("# {filename}\n\n");
+    let title = path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+    let content = content(title)?;
     note_manager::write_note(&path_str, &content)?;
 
     // Update cache for the new note
@@ -158,226 +450,1416 @@ pub async fn create_note(filename: String, state: State<'_, AppState>) -> Result
         "Cache database mutex was poisoned during create_note"
     );
     let note = note_manager::read_note(&path_str)?;
-    cache_db.update_note_cache_with_fts(&path_str, &note.title, &content, &state.notes_dir)?;
+    cache_db.update_note_cache_with_fts(&path_str, &note.title, &content, &state.notes_dir())?;
     update_cached_mtime(&cache_db, &path_str)?;
 
     // Also need to check if any existing notes link to this new note
     // and update their cache entries
     drop(cache_db);
-    rebuild_cache_for_new_note(&filename, &state)?;
+    rebuild_cache_for_new_note(filename, state)?;
 
     Ok(path_str)
 }
 
 #[tauri::command]
 pub async fn delete_note(path: String, state: State<'_, AppState>) -> Result<(), String> {
-    validate_path_security(&path, &state.notes_dir).map_err(|e| e.to_string())?;
-    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete note: {e}"))?;
-
     let cache_db = lock_mutex!(
         state.cache_db,
         "Cache database mutex was poisoned during delete_note"
     );
-    let stale_paths = vec![path];
-    cache_db.remove_stale_entries(&stale_paths)?;
+    trash_note(&path, &state.notes_dir(), &cache_db)?;
 
     Ok(())
 }
 
-#[tauri::command]
-pub async fn search_notes(query: String, state: State<'_, AppState>) -> Result<Vec<Note>, String> {
-    note_manager::search_notes(&state.notes_dir, &query)
+/// Move a note to the trash and drop it from the cache. Returns the path
+/// inside the trash.
+pub(crate) fn trash_note(
+    path: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<String, String> {
+    validate_path_security(path, notes_dir).map_err(|e| e.to_string())?;
+    let trash_path = trash::move_to_trash(path, notes_dir)?;
+
+    let stale_paths = vec![path.to_string()];
+    cache_db.remove_stale_entries(&stale_paths)?;
+
+    Ok(trash_path)
 }
 
 #[tauri::command]
-pub async fn search_notes_enhanced(
-    query: String,
+pub async fn restore_from_trash(
+    trash_path: String,
     state: State<'_, AppState>,
-) -> Result<Vec<note_manager::SearchResult>, String> {
+) -> Result<String, String> {
     let cache_db = lock_mutex!(
         state.cache_db,
-        "Cache DB mutex was poisoned during search_notes_enhanced"
+        "Cache database mutex was poisoned during restore_from_trash"
     );
+    restore_trashed_note(&trash_path, &state.notes_dir(), &cache_db)
+}
+
+/// Move a note back out of the trash and index it again. Returns the
+/// restored path.
+pub(crate) fn restore_trashed_note(
+    trash_path: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<String, String> {
+    validate_path_security(trash_path, notes_dir).map_err(|e| e.to_string())?;
+    let restored_path = trash::restore_from_trash(trash_path, notes_dir)?;
+
+    let content = read_file_with_encoding(&restored_path)?;
+    let title = Path::new(&restored_path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+    cache_db.update_note_cache_with_fts(&restored_path, title, &content, notes_dir)?;
+    update_cached_mtime(cache_db, &restored_path)?;
 
-    note_manager::search_notes_enhanced(&state.notes_dir, &query, &cache_db)
+    Ok(restored_path)
 }
 
 #[tauri::command]
-pub async fn get_daily_note(state: State<'_, AppState>) -> Result<String, String> {
-    // Get the template
-    let template = get_daily_note_template(state.clone()).await?;
-    note_manager::create_daily_note(&state.notes_dir, Some(&template))
+pub async fn list_trash(state: State<'_, AppState>) -> Result<Vec<trash::TrashEntry>, String> {
+    trash::list_trash(&state.notes_dir())
 }
 
 #[tauri::command]
-pub async fn get_block_reference(
-    note_path: String,
-    block_id: String,
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<usize, String> {
+    trash::empty_trash(&state.notes_dir())
+}
+
+#[tauri::command]
+pub async fn get_fold_state(
+    path: String,
     state: State<'_, AppState>,
-) -> Result<Option<(i32, String)>, String> {
+) -> Result<Vec<usize>, String> {
+    let notes_dir = state.notes_dir();
+    validate_path_security(&path, &notes_dir).map_err(|e| e.to_string())?;
+    folds::get_fold_state(&path, &notes_dir)
+}
+
+#[tauri::command]
+pub async fn save_fold_state(
+    path: String,
+    collapsed_lines: Vec<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let notes_dir = state.notes_dir();
+    validate_path_security(&path, &notes_dir).map_err(|e| e.to_string())?;
+    folds::save_fold_state(&path, collapsed_lines, &notes_dir)
+}
+
+/// Re-key fold state after a move or rename. Fold state is cosmetic, so a
+/// failure is only logged.
+fn carry_fold_state(old_path: &str, new_path: &str, notes_dir: &str) {
+    if let Err(e) = folds::move_fold_state(old_path, new_path, notes_dir) {
+        eprintln!("Warning: Failed to move fold state: {e}");
+    }
+}
+
+#[tauri::command]
+pub async fn search_notes(
+    query: String,
+    regex: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<SearchNotesResponse, String> {
+    if regex.unwrap_or(false) {
+        note_manager::search_notes_regex(&state.notes_dir(), &query).map(SearchNotesResponse::Regex)
+    } else {
+        note_manager::search_notes(&state.notes_dir(), &query).map(SearchNotesResponse::Notes)
+    }
+}
+
+/// Plain searches keep returning a list of notes; regex searches return
+/// per-line matches and how many files were scanned
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum SearchNotesResponse {
+    Notes(Vec<Note>),
+    Regex(note_manager::RegexSearchResults),
+}
+
+#[tauri::command]
+pub async fn search_notes_enhanced(
+    query: String,
+    scope: Option<note_manager::SearchScope>,
+    state: State<'_, AppState>,
+) -> Result<Vec<note_manager::SearchResult>, String> {
     let cache_db = lock_mutex!(
         state.cache_db,
-        "Cache DB mutex was poisoned during get_block_reference"
+        "Cache DB mutex was poisoned during search_notes_enhanced"
     );
 
-    cache_db.get_block(&note_path, &block_id)
+    note_manager::search_notes_enhanced(&state.notes_dir(), &query, scope.as_ref(), &cache_db)
 }
 
 #[tauri::command]
-pub async fn get_blocks_for_note(
-    note_path: String,
+pub async fn search_notes_fast(
+    query: String,
     state: State<'_, AppState>,
-) -> Result<Vec<(String, i32, String)>, String> {
+) -> Result<Vec<crate::cache::FtsSnippet>, String> {
     let cache_db = lock_mutex!(
         state.cache_db,
-        "Cache DB mutex was poisoned during get_blocks_for_note"
+        "Cache DB mutex was poisoned during search_notes_fast"
     );
 
-    cache_db.get_blocks_for_note(&note_path)
+    note_manager::search_notes_fast(&query, &cache_db)
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
 }
 
 #[tauri::command]
-pub async fn resolve_transclusion(
-    link: String,
+pub async fn save_search(
+    name: String,
+    query: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    // Parse the link to extract note name and optional block ID
-    let (note_name, block_id) = if let Some(pos) = link.find('#') {
-        let (name, block) = link.split_at(pos);
-        (name, Some(&block[1..])) // Skip the # character
-    } else {
-        (link.as_str(), None)
-    };
+) -> Result<SavedSearch, String> {
+    add_saved_search(&state.notes_dir(), &name, &query)
+}
 
-    // Find the note path
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+#[tauri::command]
+pub async fn list_saved_searches(state: State<'_, AppState>) -> Result<Vec<SavedSearch>, String> {
+    load_saved_searches(&state.notes_dir()).map(|searches| {
+        searches
+            .into_iter()
+            .map(|(name, query)| SavedSearch { name, query })
+            .collect()
+    })
+}
 
-    let note_path = notes
-        .iter()
-        .find(|n| n.title.eq_ignore_ascii_case(note_name))
-        .or_else(|| {
-            let name_without_ext = note_name.trim_end_matches(".md");
-            notes
-                .iter()
-                .find(|n| n.title.eq_ignore_ascii_case(name_without_ext))
-        })
-        .map(|n| n.path.clone())
-        .ok_or_else(|| format!("Note '{}' not found", note_name))?;
+#[tauri::command]
+pub async fn delete_saved_search(name: String, state: State<'_, AppState>) -> Result<(), String> {
+    remove_saved_search(&state.notes_dir(), &name)
+}
 
-    // Read the note content
-    let content =
-        std::fs::read_to_string(&note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+#[tauri::command]
+pub async fn run_saved_search(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<note_manager::SearchResult>, String> {
+    let notes_dir = state.notes_dir();
+    let query = load_saved_searches(&notes_dir)?
+        .remove(&sanitize_saved_search_name(&name)?)
+        .ok_or_else(|| format!("No saved search named '{name}'"))?;
 
-    // If block ID is specified, extract just that block's content
-    if let Some(block_id) = block_id {
-        let cache_db = lock_mutex!(
-            state.cache_db,
-            "Cache DB mutex was poisoned during resolve_transclusion"
-        );
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during run_saved_search"
+    );
+    note_manager::search_notes_enhanced(&notes_dir, &query, None, &cache_db)
+}
 
-        if let Some((line_number, _heading_text)) = cache_db.get_block(&note_path, block_id)? {
-            // Extract the content from the heading to the next heading of same or higher level
-            let lines: Vec<&str> = content.lines().collect();
-            if line_number > 0 && (line_number as usize) <= lines.len() {
-                let start_idx = (line_number - 1) as usize;
-                let start_line = lines[start_idx];
+fn saved_searches_file(notes_dir: &str) -> std::path::PathBuf {
+    Path::new(notes_dir)
+        .join(".plainflux")
+        .join("saved_searches.json")
+}
 
-                // Determine the heading level
-                let heading_level = start_line.chars().take_while(|&c| c == '#').count();
+/// Saved searches by name, kept in `.plainflux/saved_searches.json`
+pub(crate) fn load_saved_searches(notes_dir: &str) -> Result<BTreeMap<String, String>, String> {
+    match safe_read_file(saved_searches_file(notes_dir)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse saved searches: {e}")),
+        Err(AppError::NotFound(_)) => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read saved searches: {e}")),
+    }
+}
 
-                // Find the end of this block (next heading of same or higher level)
-                let mut block_lines = vec![start_line];
-                for line in &lines[(start_idx + 1)..] {
-                    if line.starts_with('#') {
-                        let line_level = line.chars().take_while(|&c| c == '#').count();
-                        if line_level <= heading_level {
-                            break;
-                        }
-                    }
-                    block_lines.push(line);
-                }
+fn write_saved_searches(
+    notes_dir: &str,
+    searches: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(searches)
+        .map_err(|e| format!("Failed to serialize saved searches: {e}"))?;
+    safe_write_file(saved_searches_file(notes_dir), &json)
+        .map_err(|e| format!("Failed to save saved searches: {e}"))
+}
 
-                return Ok(block_lines.join("\n"));
-            }
-        }
+/// Trim and collapse whitespace; names must be non-empty printable text
+fn sanitize_saved_search_name(name: &str) -> Result<String, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return Err("Saved search name cannot be empty".to_string());
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Saved search name contains invalid characters".to_string());
+    }
+    if name.chars().count() > 100 {
+        return Err("Saved search name is too long".to_string());
+    }
+    Ok(name)
+}
 
-        return Err(format!("Block '{}' not found in note", block_id));
+pub(crate) fn add_saved_search(
+    notes_dir: &str,
+    name: &str,
+    query: &str,
+) -> Result<SavedSearch, String> {
+    let name = sanitize_saved_search_name(name)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Saved search query cannot be empty".to_string());
     }
 
-    // Return the entire note content
-    Ok(content)
+    let mut searches = load_saved_searches(notes_dir)?;
+    if searches
+        .keys()
+        .any(|existing| existing.to_lowercase() == name.to_lowercase())
+    {
+        return Err(format!("A saved search named '{name}' already exists"));
+    }
+    searches.insert(name.clone(), query.to_string());
+    write_saved_searches(notes_dir, &searches)?;
+
+    Ok(SavedSearch {
+        name,
+        query: query.to_string(),
+    })
+}
+
+pub(crate) fn remove_saved_search(notes_dir: &str, name: &str) -> Result<(), String> {
+    let name = sanitize_saved_search_name(name)?;
+    let mut searches = load_saved_searches(notes_dir)?;
+    if searches.remove(&name).is_none() {
+        return Err(format!("No saved search named '{name}'"));
+    }
+    write_saved_searches(notes_dir, &searches)
+}
+
+#[tauri::command]
+pub async fn get_daily_note(state: State<'_, AppState>) -> Result<String, String> {
+    // Get the template
+    let template = get_daily_note_template(state.clone()).await?;
+    let notes_dir = state.notes_dir();
+    note_manager::create_daily_note(
+        &notes_dir,
+        &daily_notes_folder(&notes_dir)?,
+        Some(&template),
+    )
+}
+
+#[tauri::command]
+pub async fn get_daily_note_for_date(
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let template = get_daily_note_template(state.clone()).await?;
+    let notes_dir = state.notes_dir();
+    note_manager::create_daily_note_for_date(
+        &notes_dir,
+        &daily_notes_folder(&notes_dir)?,
+        &date,
+        Some(&template),
+    )
+}
+
+#[tauri::command]
+pub async fn rollover_incomplete_todos(
+    from_date: String,
+    to_date: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let template = get_daily_note_template(state.clone()).await?;
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during rollover_incomplete_todos"
+    );
+    rollover_todos(
+        &state.notes_dir(),
+        &from_date,
+        &to_date,
+        &template,
+        &cache_db,
+    )
+}
+
+/// Move the incomplete todos of one daily note into the `## Tasks`
+/// section of another, creating it from the template if needed. Returns
+/// the number of todos moved.
+pub(crate) fn rollover_todos(
+    notes_dir: &str,
+    from_date: &str,
+    to_date: &str,
+    template: &str,
+    cache_db: &CacheDb,
+) -> Result<usize, String> {
+    note_manager::parse_daily_note_date(from_date)?;
+    note_manager::parse_daily_note_date(to_date)?;
+    if from_date == to_date {
+        return Err("Cannot roll todos over into the same daily note".to_string());
+    }
+
+    let daily_folder = daily_notes_folder(notes_dir)?;
+    let from_path = Path::new(notes_dir)
+        .join(&daily_folder)
+        .join(format!("{from_date}.md"));
+    if !from_path.exists() {
+        return Err(format!("No daily note for {from_date}"));
+    }
+    let from_path = from_path.to_string_lossy().to_string();
+
+    let from_content = read_file_with_encoding(&from_path)?;
+    let (remaining, moved, todo_count) = note_manager::take_incomplete_todos(&from_content);
+    if moved.is_empty() {
+        return Ok(0);
+    }
+
+    let to_path = note_manager::create_daily_note_for_date(
+        notes_dir,
+        &daily_folder,
+        to_date,
+        Some(template),
+    )?;
+    let to_content = read_file_with_encoding(&to_path)?;
+    let updated = note_manager::append_to_tasks_section(&to_content, &moved);
+
+    // Add to the new note first so a failure can't lose the todos
+    note_manager::write_note(&to_path, &updated)?;
+    note_manager::write_note(&from_path, &remaining)?;
+
+    for (path, title, content) in [
+        (&to_path, to_date, &updated),
+        (&from_path, from_date, &remaining),
+    ] {
+        cache_db.update_note_cache_with_fts(path, title, content, notes_dir)?;
+        update_cached_mtime(cache_db, path)?;
+    }
+
+    Ok(todo_count)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyNoteEntry {
+    pub date: String,
+    /// Only filled in when todo status was requested
+    pub has_incomplete_todos: Option<bool>,
+}
+
+#[tauri::command]
+pub async fn list_daily_notes(
+    include_todo_status: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DailyNoteEntry>, String> {
+    let cache_db = if include_todo_status.unwrap_or(false) {
+        Some(lock_mutex!(
+            state.cache_db,
+            "Cache DB mutex was poisoned during list_daily_notes"
+        ))
+    } else {
+        None
+    };
+
+    build_daily_note_entries(&state.notes_dir(), cache_db.as_deref())
+}
+
+/// List the daily notes on disk, flagging outstanding todos when a cache is
+/// given
+pub(crate) fn build_daily_note_entries(
+    notes_dir: &str,
+    cache_db: Option<&CacheDb>,
+) -> Result<Vec<DailyNoteEntry>, String> {
+    let daily_folder = daily_notes_folder(notes_dir)?;
+    let dates = note_manager::list_daily_notes(notes_dir, &daily_folder)?;
+
+    let with_todos: Option<HashSet<std::path::PathBuf>> = match cache_db {
+        Some(cache_db) => Some(
+            cache_db
+                .get_notes_with_incomplete_todos()?
+                .into_iter()
+                .map(std::path::PathBuf::from)
+                .collect(),
+        ),
+        None => None,
+    };
+
+    let daily_notes_dir = Path::new(notes_dir).join(&daily_folder);
+    Ok(dates
+        .into_iter()
+        .map(|date| {
+            let has_incomplete_todos = with_todos
+                .as_ref()
+                .map(|paths| paths.contains(&daily_notes_dir.join(format!("{date}.md"))));
+            DailyNoteEntry {
+                date,
+                has_incomplete_todos,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn create_periodic_note(
+    period: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    // `period` names the template file, so check it before building the path
+    if !note_manager::NOTE_PERIODS.contains(&period.as_str()) {
+        return Err(format!("Unknown note period: {period}"));
+    }
+    let template_path = Path::new(&state.notes_dir())
+        .join(".plainflux")
+        .join(format!("{period}_note_template.md"));
+
+    let template = match safe_read_file(&template_path) {
+        Ok(content) => Some(content),
+        Err(AppError::NotFound(_)) => None,
+        Err(e) => return Err(format!("Failed to read template: {e}")),
+    };
+
+    let notes_dir = state.notes_dir();
+    note_manager::create_periodic_note(
+        &notes_dir,
+        &daily_notes_folder(&notes_dir)?,
+        &period,
+        template.as_deref(),
+    )
+}
+
+#[tauri::command]
+pub async fn get_block_reference(
+    note_path: String,
+    block_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<(i32, String)>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_block_reference"
+    );
+
+    cache_db.get_block(&note_path, &block_id)
+}
+
+#[tauri::command]
+pub async fn get_blocks_for_note(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, i32, String, i32)>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_blocks_for_note"
+    );
+
+    cache_db.get_blocks_for_note(&note_path)
+}
+
+#[tauri::command]
+pub async fn get_note_toc(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<note_manager::TocEntry>, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+    let content = read_file_with_encoding(&note_path)?;
+    Ok(note_manager::extract_toc(&content))
+}
+
+#[tauri::command]
+pub async fn resolve_transclusion(
+    link: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during resolve_transclusion"
+    );
+    expand_transclusion(&link, &state.notes_dir(), &cache_db)
+}
+
+/// How many levels of `![[...]]` embeds are expanded inside a transclusion
+const MAX_TRANSCLUSION_DEPTH: usize = 5;
+
+/// Resolve `link` (`Note` or `Note#block`) and expand the embeds inside it,
+/// recursively. An embed that leads back to one of its ancestors is replaced
+/// by a note describing the loop instead of being expanded again.
+pub(crate) fn expand_transclusion(
+    link: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<String, String> {
+    let notes = note_manager::list_notes(notes_dir)?;
+    let (key, title, content) = resolve_transclusion_target(link, &notes, cache_db)?;
+    let mut chain = vec![(key, title)];
+    Ok(expand_embeds(&content, &notes, cache_db, &mut chain))
+}
+
+fn expand_embeds(
+    content: &str,
+    notes: &[NoteMetadata],
+    cache_db: &CacheDb,
+    chain: &mut Vec<(String, String)>,
+) -> String {
+    static EMBED_REGEX: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"!\[\[([^\]]+)\]\]").unwrap());
+
+    EMBED_REGEX
+        .replace_all(content, |caps: &regex::Captures| {
+            let link = caps[1].split('|').next().unwrap_or("").trim();
+            let (key, title, embedded) = match resolve_transclusion_target(link, notes, cache_db) {
+                Ok(target) => target,
+                // Leave embeds we cannot resolve (images, missing notes) as written
+                Err(_) => return caps[0].to_string(),
+            };
+
+            if let Some(start) = chain.iter().position(|(visited, _)| *visited == key) {
+                let loop_path: Vec<&str> = chain[start..]
+                    .iter()
+                    .map(|(_, title)| title.as_str())
+                    .chain(std::iter::once(title.as_str()))
+                    .collect();
+                return format!("[Transclusion loop: {}]", loop_path.join(" → "));
+            }
+            if chain.len() > MAX_TRANSCLUSION_DEPTH {
+                return format!("[Transclusion depth limit reached: {}]", &caps[0]);
+            }
+
+            chain.push((key, title));
+            let expanded = expand_embeds(&embedded, notes, cache_db, chain);
+            chain.pop();
+            expanded
+        })
+        .into_owned()
+}
+
+/// Find the note (and optional block) a transclusion link points at.
+/// Returns a key identifying the target, the link's display name and the
+/// transcluded text.
+fn resolve_transclusion_target(
+    link: &str,
+    notes: &[NoteMetadata],
+    cache_db: &CacheDb,
+) -> Result<(String, String, String), String> {
+    // Parse the link to extract note name and optional block ID
+    let (note_name, block_id) = if let Some(pos) = link.find('#') {
+        let (name, block) = link.split_at(pos);
+        (name, Some(&block[1..])) // Skip the # character
+    } else {
+        (link, None)
+    };
+
+    // Find the note path
+    let note_path = notes
+        .iter()
+        .find(|n| n.title.eq_ignore_ascii_case(note_name))
+        .or_else(|| {
+            let name_without_ext = note_name.trim_end_matches(".md");
+            notes
+                .iter()
+                .find(|n| n.title.eq_ignore_ascii_case(name_without_ext))
+        })
+        .map(|n| n.path.clone())
+        .ok_or_else(|| format!("Note '{}' not found", note_name))?;
+
+    // Read the note content
+    let content =
+        std::fs::read_to_string(&note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+
+    // If block ID is specified, extract just that block's content
+    if let Some(block_id) = block_id {
+        // Accept heading text as well as its slug, e.g. "Notes 2" for the
+        // second "## Notes" heading, stored as "notes-2"
+        let block = match cache_db.get_block_entry(&note_path, block_id)? {
+            Some(block) => Some(block),
+            None if !block_id.starts_with('^') => {
+                cache_db.get_block_entry(&note_path, &heading_slug(block_id))?
+            }
+            None => None,
+        };
+
+        // A ^id block is just the referenced paragraph
+        if let Some(block) = block
+            .as_ref()
+            .filter(|b| b.block_type == BLOCK_TYPE_PARAGRAPH)
+        {
+            return Ok((
+                format!("{note_path}#{block_id}"),
+                link.to_string(),
+                block.content.clone(),
+            ));
+        }
+
+        if let Some(line_number) = block.map(|b| b.line_number) {
+            // Extract the content from the heading to the next heading of same or higher level
+            let lines: Vec<&str> = content.lines().collect();
+            if line_number > 0 && (line_number as usize) <= lines.len() {
+                let start_idx = (line_number - 1) as usize;
+                let start_line = lines[start_idx];
+
+                // Determine the heading level
+                let heading_level = start_line.chars().take_while(|&c| c == '#').count();
+
+                // Find the end of this block (next heading of same or higher level)
+                let mut block_lines = vec![start_line];
+                for line in &lines[(start_idx + 1)..] {
+                    if line.starts_with('#') {
+                        let line_level = line.chars().take_while(|&c| c == '#').count();
+                        if line_level <= heading_level {
+                            break;
+                        }
+                    }
+                    block_lines.push(line);
+                }
+
+                return Ok((
+                    format!("{note_path}#{block_id}"),
+                    link.to_string(),
+                    block_lines.join("\n"),
+                ));
+            }
+        }
+
+        return Err(format!("Block '{}' not found in note", block_id));
+    }
+
+    // Return the entire note content
+    Ok((note_path, note_name.to_string(), content))
+}
+
+#[tauri::command]
+pub async fn get_backlinks(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let cache_db = state
+        .cache_db
+        .lock()
+        .map_err(|_| "Failed to lock cache database")?;
+    cache_db.get_backlinks(&note_path)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BacklinkContext {
+    pub source_path: String,
+    pub source_title: String,
+    pub snippets: Vec<note_manager::SearchSnippet>,
+}
+
+#[tauri::command]
+pub async fn get_backlinks_with_context(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<BacklinkContext>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_backlinks_with_context"
+    );
+    backlinks_with_context(&note_path, &cache_db)
+}
+
+/// Backlinks of a note with the lines that link to it. Links through an
+/// alias are found as well as links by title.
+pub(crate) fn backlinks_with_context(
+    note_path: &str,
+    cache_db: &CacheDb,
+) -> Result<Vec<BacklinkContext>, String> {
+    let mut names = cache_db.get_aliases_for_note(note_path)?;
+    if let Some(title) = Path::new(note_path).file_stem().and_then(|s| s.to_str()) {
+        names.push(title.to_string());
+    }
+
+    let mut sources = cache_db.get_backlinks(note_path)?;
+    sources.sort();
+    sources.dedup();
+
+    let mut backlinks = Vec::new();
+    for source_path in sources {
+        let content = match read_file_with_encoding(&source_path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Failed to read backlink source '{source_path}': {e}");
+                continue;
+            }
+        };
+        let source_title = Path::new(&source_path)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled")
+            .to_string();
+
+        backlinks.push(BacklinkContext {
+            snippets: note_manager::find_link_snippets(&content, &names),
+            source_path,
+            source_title,
+        });
+    }
+
+    Ok(backlinks)
+}
+
+#[tauri::command]
+pub async fn get_outgoing_links(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    use crate::cache::extract_links;
+
+    // Read the note content
+    let content =
+        read_file_with_encoding(&note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+
+    // Extract links from the content
+    let links = extract_links(&content);
+
+    Ok(links)
+}
+
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub from_note: String,
+    pub target: String,
+    pub line_number: usize,
+}
+
+#[tauri::command]
+pub async fn get_broken_links(state: State<'_, AppState>) -> Result<Vec<BrokenLink>, String> {
+    let notes = note_manager::list_notes(&state.notes_dir())?;
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_broken_links"
+    );
+    find_broken_links(&notes, &cache_db)
+}
+
+/// `[[links]]` in `notes` that match neither a note name nor an alias
+pub(crate) fn find_broken_links(
+    notes: &[NoteMetadata],
+    cache_db: &CacheDb,
+) -> Result<Vec<BrokenLink>, String> {
+    use crate::cache::{extract_links, strip_code};
+
+    let stems: HashSet<String> = notes
+        .iter()
+        .filter_map(|note| Path::new(&note.path).file_stem())
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .collect();
+
+    let mut broken_links = Vec::new();
+    for note in notes {
+        let content = match read_file_with_encoding(&note.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        // Strip code from the whole note so fences spanning lines are seen,
+        // then scan line by line so each dangling link carries its location
+        for (line_index, line) in strip_code(&content).lines().enumerate() {
+            for link in extract_links(line) {
+                // Strip block reference and display text (e.g., "Note#heading|text" -> "Note")
+                let target = link.split(['#', '|']).next().unwrap_or(&link);
+                if target.trim().is_empty() {
+                    continue;
+                }
+
+                let name = target.trim_end_matches(".md");
+                if stems.contains(&name.to_lowercase())
+                    || cache_db.find_note_by_alias(name)?.is_some()
+                {
+                    continue;
+                }
+                broken_links.push(BrokenLink {
+                    from_note: note.path.clone(),
+                    target: target.to_string(),
+                    line_number: line_index + 1,
+                });
+            }
+        }
+    }
+
+    Ok(broken_links)
+}
+
+#[tauri::command]
+pub async fn get_unlinked_mentions(
+    note_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<UnlinkedMention>, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    let title = Path::new(&note_path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid note path")?
+        .to_string();
+
+    let mut names = {
+        let cache_db = lock_mutex!(
+            state.cache_db,
+            "Cache DB mutex was poisoned during get_unlinked_mentions"
+        );
+        cache_db.get_aliases_for_note(&note_path)?
+    };
+    names.push(title);
+
+    note_manager::find_unlinked_mentions(&state.notes_dir(), &note_path, &names)
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LinkSuggestion {
+    pub title: String,
+    pub path: String,
+    /// Set when the prefix matched one of the note's aliases
+    pub alias: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_link_suggestions(
+    prefix: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<LinkSuggestion>, String> {
+    // Most recently opened first
+    let recent: Vec<String> = lock_mutex!(state.recent_notes)
+        .iter()
+        .rev()
+        .map(|note| note.path.clone())
+        .collect();
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_link_suggestions"
+    );
+    link_suggestions(&prefix, limit, &recent, &cache_db)
+}
+
+/// Notes whose title or alias starts with `prefix`, recently opened notes
+/// first and then alphabetically. Reads only the cache.
+pub(crate) fn link_suggestions(
+    prefix: &str,
+    limit: usize,
+    recent: &[String],
+    cache_db: &CacheDb,
+) -> Result<Vec<LinkSuggestion>, String> {
+    const MAX_LINK_SUGGESTIONS: usize = 50;
+    let prefix = prefix.trim().to_lowercase();
+    let title_of = |path: &str| {
+        Path::new(path)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled")
+            .to_string()
+    };
+
+    let mut suggestions: Vec<LinkSuggestion> = cache_db
+        .get_all_cached_paths()?
+        .into_iter()
+        .filter_map(|path| {
+            let title = title_of(&path);
+            title
+                .to_lowercase()
+                .starts_with(&prefix)
+                .then_some(LinkSuggestion {
+                    title,
+                    path,
+                    alias: None,
+                })
+        })
+        .collect();
+    for (alias, path) in cache_db.get_all_aliases()? {
+        if alias.to_lowercase().starts_with(&prefix) {
+            suggestions.push(LinkSuggestion {
+                title: title_of(&path),
+                path,
+                alias: Some(alias),
+            });
+        }
+    }
+
+    let recent_rank = |path: &str| {
+        recent
+            .iter()
+            .position(|recent_path| recent_path == path)
+            .unwrap_or(usize::MAX)
+    };
+    suggestions.sort_by_cached_key(|suggestion| {
+        (
+            recent_rank(&suggestion.path),
+            suggestion
+                .alias
+                .as_ref()
+                .unwrap_or(&suggestion.title)
+                .to_lowercase(),
+            suggestion.path.clone(),
+        )
+    });
+    suggestions.truncate(limit.min(MAX_LINK_SUGGESTIONS));
+
+    Ok(suggestions)
+}
+
+#[tauri::command]
+pub async fn get_all_tags(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let cache_db = state
+        .cache_db
+        .lock()
+        .map_err(|_| "Failed to lock cache database")?;
+    cache_db.get_all_tags()
+}
+
+#[tauri::command]
+pub async fn get_all_tags_with_counts(
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, usize)>, String> {
+    let cache_db = state
+        .cache_db
+        .lock()
+        .map_err(|_| "Failed to lock cache database")?;
+    cache_db.get_all_tags_with_counts()
+}
+
+/// Tags for `#` autocomplete, matched by prefix and ranked by usage
+#[tauri::command]
+pub async fn get_tag_suggestions(
+    prefix: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, usize)>, String> {
+    const MAX_TAG_SUGGESTIONS: usize = 50;
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_tag_suggestions"
+    );
+    cache_db.get_tag_suggestions(
+        prefix.trim().trim_start_matches('#'),
+        limit.min(MAX_TAG_SUGGESTIONS),
+    )
+}
+
+#[tauri::command]
+pub async fn get_notes_by_tag(
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let cache_db = state
+        .cache_db
+        .lock()
+        .map_err(|_| "Failed to lock cache database")?;
+    cache_db.get_notes_by_tag(&tag)
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    old: String,
+    new: String,
+    recursive: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during rename_tag"
+    );
+
+    rename_tag_in_notes(
+        &old,
+        &new,
+        recursive.unwrap_or(false),
+        &state.notes_dir(),
+        &cache_db,
+    )
+}
+
+/// Rewrite `#old` to `#new` in every note using it and re-index those notes.
+/// Nested tags like `#old/child` are only renamed when `recursive` is set.
+/// Returns the number of notes modified.
+pub(crate) fn rename_tag_in_notes(
+    old: &str,
+    new: &str,
+    recursive: bool,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<usize, String> {
+    let old = old.trim().trim_start_matches('#');
+    let new = new.trim().trim_start_matches('#');
+
+    static TAG_REGEX: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(crate::cache::TAG_PATTERN).unwrap());
+    let tag_regex = &*TAG_REGEX;
+    let is_valid_tag = |tag: &str| {
+        crate::cache::is_tag_name(tag)
+            && tag_regex
+                .captures(&format!("#{tag}"))
+                .is_some_and(|c| c[1].len() == tag.len())
+    };
+    if !is_valid_tag(old) {
+        return Err(format!("Invalid tag: {old}"));
+    }
+    if !is_valid_tag(new) {
+        return Err(format!("Invalid tag: {new}"));
+    }
+    if old == new {
+        return Ok(0);
+    }
+
+    // Nested tags are also indexed under their root segment
+    let indexed_tag = old.split('/').next().unwrap_or(old);
+    let nested_prefix = format!("{old}/");
+
+    let mut modified = 0;
+    for note_path in cache_db.get_notes_by_tag(indexed_tag)? {
+        let content = match read_file_with_encoding(&note_path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Failed to read '{note_path}' while renaming tag: {e}");
+                continue;
+            }
+        };
+
+        // Match against a copy with code and URLs blanked out; it lines up
+        // byte for byte with `content`, so the edits apply at the same offsets
+        let searchable = crate::cache::blank_urls(&crate::cache::strip_code(&content));
+        let mut updated = String::with_capacity(content.len());
+        let mut copied_to = 0;
+        for caps in tag_regex.captures_iter(&searchable) {
+            let tag = &caps[1];
+            let renamed = if tag == old {
+                format!("#{new}")
+            } else if recursive && tag.starts_with(&nested_prefix) {
+                format!("#{new}/{}", &tag[nested_prefix.len()..])
+            } else {
+                continue;
+            };
+            let whole = caps.get(0).unwrap();
+            updated.push_str(&content[copied_to..whole.start()]);
+            updated.push_str(&renamed);
+            copied_to = whole.end();
+        }
+        if copied_to == 0 {
+            continue;
+        }
+        updated.push_str(&content[copied_to..]);
+
+        note_manager::write_note(&note_path, &updated)?;
+
+        let title = Path::new(&note_path)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled");
+        cache_db.update_note_cache_with_fts(&note_path, title, &updated, notes_dir)?;
+        update_cached_mtime(cache_db, &note_path)?;
+
+        modified += 1;
+    }
+
+    Ok(modified)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceResult {
+    pub path: String,
+    pub replacements: usize,
+    pub error: Option<String>,
+}
+
+#[tauri::command]
+pub async fn replace_in_notes(
+    query: String,
+    replacement: String,
+    paths: Vec<String>,
+    dry_run: Option<bool>,
+    skip_code_blocks: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ReplaceResult>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during replace_in_notes"
+    );
+
+    replace_in_note_files(
+        &query,
+        &replacement,
+        &paths,
+        dry_run.unwrap_or(false),
+        skip_code_blocks.unwrap_or(false),
+        &state.notes_dir(),
+        &cache_db,
+    )
+}
+
+/// Literal find/replace across the given notes. With `dry_run` the notes
+/// are left untouched and only the counts are reported.
+pub(crate) fn replace_in_note_files(
+    query: &str,
+    replacement: &str,
+    paths: &[String],
+    dry_run: bool,
+    skip_code_blocks: bool,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<Vec<ReplaceResult>, String> {
+    if query.is_empty() {
+        return Err("Search text cannot be empty".to_string());
+    }
+
+    let replace_one = |path: &str| -> Result<usize, String> {
+        validate_path_security(path, notes_dir).map_err(|e| e.to_string())?;
+        let content = read_file_with_encoding(path)?;
+        let (updated, count) =
+            note_manager::replace_literal(&content, query, replacement, skip_code_blocks);
+        if dry_run || count == 0 {
+            return Ok(count);
+        }
+
+        note_manager::write_note(path, &updated)?;
+        let title = Path::new(path)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled");
+        cache_db.update_note_cache_with_fts(path, title, &updated, notes_dir)?;
+        update_cached_mtime(cache_db, path)?;
+        Ok(count)
+    };
+
+    Ok(paths
+        .iter()
+        .map(|path| match replace_one(path) {
+            Ok(replacements) => ReplaceResult {
+                path: path.clone(),
+                replacements,
+                error: None,
+            },
+            Err(e) => ReplaceResult {
+                path: path.clone(),
+                replacements: 0,
+                error: Some(e),
+            },
+        })
+        .collect())
+}
+
+/// Rewrite a note's links as wikilinks or markdown links. With `dry_run`
+/// the note is left untouched and only the proposed changes are returned.
+#[tauri::command]
+pub async fn convert_links(
+    note_path: String,
+    to_style: LinkStyle,
+    dry_run: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<LinkChange>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during convert_links"
+    );
+    convert_note_links(&note_path, to_style, dry_run, &state.notes_dir(), &cache_db)
+}
+
+pub(crate) fn convert_note_links(
+    note_path: &str,
+    to_style: LinkStyle,
+    dry_run: bool,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<Vec<LinkChange>, String> {
+    validate_path_security(note_path, notes_dir).map_err(|e| e.to_string())?;
+    let content = read_file_with_encoding(note_path)?;
+    let (updated, changes) =
+        links::convert_links(&content, note_path, notes_dir, to_style, |target| {
+            cache_db
+                .resolve_note_link(target, notes_dir, Some(note_path))
+                .ok()
+        });
+    if dry_run || changes.is_empty() {
+        return Ok(changes);
+    }
+
+    note_manager::write_note(note_path, &updated)?;
+    let title = Path::new(note_path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+    cache_db.update_note_cache_with_fts(note_path, title, &updated, notes_dir)?;
+    update_cached_mtime(cache_db, note_path)?;
+    Ok(changes)
 }
 
 #[tauri::command]
-pub async fn get_backlinks(
-    note_path: String,
+pub async fn set_notes_directory(
+    path: String,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<String>, String> {
-    let cache_db = state
-        .cache_db
-        .lock()
-        .map_err(|_| "Failed to lock cache database")?;
-    cache_db.get_backlinks(&note_path)
+) -> Result<(), String> {
+    let notes_dir = resolve_vault_path(&path)?;
+
+    let app_data_dir = app_data_dir(&app)?;
+    let mut config = load_app_config(&app_data_dir);
+    let vault = config.vault_for_path(&notes_dir);
+    activate_vault(&app, &state, &app_data_dir, &mut config, vault)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultInfo {
+    pub name: String,
+    pub path: String,
+    pub is_active: bool,
 }
 
 #[tauri::command]
-pub async fn get_outgoing_links(
-    note_path: String,
+pub async fn get_vaults(
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<String>, String> {
-    validate_path_security(&note_path, &state.notes_dir).map_err(|e| e.to_string())?;
-
-    use crate::cache::extract_links;
-
-    // Read the note content
-    let content =
-        read_file_with_encoding(&note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+) -> Result<Vec<VaultInfo>, String> {
+    let config = load_app_config(&app_data_dir(&app)?);
+    let notes_dir = state.notes_dir();
 
-    // Extract links from the content
-    let links = extract_links(&content);
-
-    Ok(links)
+    Ok(config
+        .vaults
+        .into_iter()
+        .map(|vault| VaultInfo {
+            is_active: Path::new(&vault.path) == Path::new(&notes_dir),
+            name: vault.name,
+            path: vault.path,
+        })
+        .collect())
 }
 
 #[tauri::command]
-pub async fn get_all_tags(state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    let cache_db = state
-        .cache_db
-        .lock()
-        .map_err(|_| "Failed to lock cache database")?;
-    cache_db.get_all_tags()
+pub async fn add_vault(name: String, path: String, app: tauri::AppHandle) -> Result<(), String> {
+    let path = resolve_vault_path(&path)?;
+
+    let app_data_dir = app_data_dir(&app)?;
+    let mut config = load_app_config(&app_data_dir);
+    config.add_vault(&name, &path)?;
+    save_app_config(&app_data_dir, &config)
 }
 
 #[tauri::command]
-pub async fn get_notes_by_tag(
-    tag: String,
+pub async fn switch_vault(
+    name: String,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<Vec<String>, String> {
-    let cache_db = state
-        .cache_db
-        .lock()
-        .map_err(|_| "Failed to lock cache database")?;
-    cache_db.get_notes_by_tag(&tag)
+) -> Result<(), String> {
+    let app_data_dir = app_data_dir(&app)?;
+    let mut config = load_app_config(&app_data_dir);
+    let vault = config
+        .vaults
+        .iter()
+        .find(|vault| vault.name == name)
+        .cloned()
+        .ok_or_else(|| format!("Vault '{name}' not found"))?;
+    if !Path::new(&vault.path).is_dir() {
+        return Err(format!("Vault directory '{}' does not exist", vault.path));
+    }
+
+    activate_vault(&app, &state, &app_data_dir, &mut config, vault)
 }
 
-#[tauri::command]
-pub async fn set_notes_directory(path: String, _state: State<'_, AppState>) -> Result<(), String> {
-    if !std::path::Path::new(&path).exists() {
+fn app_data_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    use tauri::Manager;
+
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {e}"))
+}
+
+fn resolve_vault_path(path: &str) -> Result<String, String> {
+    let notes_dir = Path::new(path);
+    if !notes_dir.is_dir() {
         return Err("Directory does not exist".to_string());
     }
+    Ok(notes_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve notes directory: {e}"))?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Open the vault's own cache, make it the active vault, remember it for the
+/// next launch and tell the frontend to reload
+fn activate_vault(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    app_data_dir: &Path,
+    config: &mut AppConfig,
+    vault: Vault,
+) -> Result<(), String> {
+    use tauri::Emitter;
+
+    let cache_db =
+        CacheDb::open_or_recreate(&app_data_dir.join(&vault.cache_db).to_string_lossy())?;
+    configure_search_tokenizer(&cache_db, &vault.path);
+
+    // Persist first so a failed write leaves the current vault untouched
+    config.notes_dir = Some(vault.path.clone());
+    save_app_config(app_data_dir, config)?;
+
+    switch_notes_directory(state, &vault.path, cache_db)?;
 
-    // This would need proper state management in a real app
-    // For now, we'll just validate the path
+    // Watch the new directory; dropping the old watcher stops it
+    let new_watcher = match crate::watcher::watch_notes_dir(app.clone(), &vault.path) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("Warning: Failed to start file watcher: {e}");
+            None
+        }
+    };
+    let mut watcher_slot = lock_mutex!(state.notes_watcher);
+    *watcher_slot = new_watcher;
+    drop(watcher_slot);
+
+    if let Err(e) = app.emit("vault-switched", &vault.name) {
+        eprintln!("Warning: Failed to emit vault-switched event: {e}");
+    }
 
     Ok(())
 }
 
+/// Point the app state at `notes_dir` and its `cache_db` in one step, start a
+/// fresh git manager with that vault's settings and bring the cache up to date
+pub(crate) fn switch_notes_directory(
+    state: &AppState,
+    notes_dir: &str,
+    cache_db: CacheDb,
+) -> Result<(), String> {
+    let new_git_manager = GitManager::new(notes_dir);
+    configure_git_manager(&new_git_manager, notes_dir);
+
+    // Hold both locks so no command sees a mix of the old and new vault
+    let mut current_cache = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during vault switch"
+    );
+    let mut current_git = lock_mutex!(
+        state.git_manager,
+        "Git manager mutex was poisoned during vault switch"
+    );
+    *current_cache = cache_db;
+    *current_git = new_git_manager;
+    state.set_notes_dir(notes_dir.to_string());
+    drop(current_git);
+    drop(current_cache);
+
+    lock_mutex!(state.recent_notes).clear();
+
+    crate::sync_cache(state).map_err(|e| e.to_string())
+}
+
+/// Note a wikilink name opens. With `prefer_local`, a note of that name in
+/// the same folder as `from_path` wins over duplicates elsewhere.
 #[tauri::command]
 pub async fn find_note_by_name(
     name: String,
+    from_path: Option<String>,
+    prefer_local: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<Option<String>, String> {
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    let notes = note_manager::list_notes(&state.notes_dir())?;
+
+    if prefer_local.unwrap_or(false) {
+        let local_folder = from_path
+            .as_deref()
+            .and_then(|from| Path::new(from).parent());
+        let name_without_ext = name.trim_end_matches(".md");
+        if let Some(note) = notes.iter().find(|n| {
+            n.title.eq_ignore_ascii_case(name_without_ext)
+                && Path::new(&n.path).parent() == local_folder
+        }) {
+            return Ok(Some(note.path.clone()));
+        }
+    }
 
     // Try exact match first
     if let Some(note) = notes.iter().find(|n| n.title.eq_ignore_ascii_case(&name)) {
@@ -393,7 +1875,52 @@ pub async fn find_note_by_name(
         return Ok(Some(note.path.clone()));
     }
 
-    Ok(None)
+    // Fall back to frontmatter aliases
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during find_note_by_name"
+    );
+    cache_db.find_note_by_alias(name_without_ext)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateTitle {
+    pub title: String,
+    pub paths: Vec<String>,
+}
+
+/// Titles shared by more than one note, which makes `[[Title]]` ambiguous
+#[tauri::command]
+pub async fn get_duplicate_titles(
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateTitle>, String> {
+    let notes = note_manager::list_notes(&state.notes_dir())?;
+    Ok(group_duplicate_titles(notes))
+}
+
+/// Group notes whose titles match ignoring case, keeping groups with more
+/// than one note. Groups are ordered by title, paths within a group by path.
+pub(crate) fn group_duplicate_titles(notes: Vec<NoteMetadata>) -> Vec<DuplicateTitle> {
+    let mut groups: BTreeMap<String, DuplicateTitle> = BTreeMap::new();
+    for note in notes {
+        groups
+            .entry(note.title.to_lowercase())
+            .or_insert_with(|| DuplicateTitle {
+                title: note.title.clone(),
+                paths: Vec::new(),
+            })
+            .paths
+            .push(note.path);
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.paths.len() > 1)
+        .map(|mut group| {
+            group.paths.sort();
+            group
+        })
+        .collect()
 }
 
 #[tauri::command]
@@ -407,27 +1934,122 @@ pub async fn move_note(
         read_file_with_encoding(&old_path).map_err(|e| format!("Failed to read note: {e}"))?;
 
     // Move the note
-    let new_path = note_manager::move_note(&old_path, &new_folder, &state.notes_dir)?;
+    let new_path = note_manager::move_note(&old_path, &new_folder, &state.notes_dir())?;
 
     // Update cache for the new location
     let cache_db = state
         .cache_db
         .lock()
         .map_err(|_| "Failed to lock cache database")?;
+    reindex_moved_note(
+        &cache_db,
+        &old_path,
+        &new_path,
+        &content,
+        &state.notes_dir(),
+    )?;
+    carry_fold_state(&old_path, &new_path, &state.notes_dir());
 
+    Ok(new_path)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MoveResult {
+    pub old_path: String,
+    /// Where the note is after the call; unchanged if the move failed
+    pub new_path: String,
+    pub error: Option<String>,
+}
+
+#[tauri::command]
+pub async fn move_notes(
+    paths: Vec<String>,
+    new_folder: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<MoveResult>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during move_notes"
+    );
+    move_notes_to_folder(&paths, &new_folder, &state.notes_dir(), &cache_db)
+}
+
+/// Move each note into `new_folder`, reporting per note so one failure does
+/// not stop the rest. Only an invalid destination fails the whole batch.
+pub(crate) fn move_notes_to_folder(
+    paths: &[String],
+    new_folder: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<Vec<MoveResult>, String> {
+    note_manager::validate_relative_folder_path(new_folder, true)?;
+
+    Ok(paths
+        .iter()
+        .map(
+            |old_path| match move_one_note(old_path, new_folder, notes_dir, cache_db) {
+                Ok(new_path) => MoveResult {
+                    old_path: old_path.clone(),
+                    new_path,
+                    error: None,
+                },
+                Err(e) => MoveResult {
+                    old_path: old_path.clone(),
+                    new_path: old_path.clone(),
+                    error: Some(e),
+                },
+            },
+        )
+        .collect())
+}
+
+fn move_one_note(
+    old_path: &str,
+    new_folder: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<String, String> {
+    validate_path_security(old_path, notes_dir).map_err(|e| e.to_string())?;
+    let content =
+        read_file_with_encoding(old_path).map_err(|e| format!("Failed to read note: {e}"))?;
+
+    // Never let one note of the batch replace another
+    let filename = Path::new(old_path)
+        .file_name()
+        .ok_or_else(|| "Invalid file path".to_string())?;
+    let destination = Path::new(notes_dir).join(new_folder).join(filename);
+    if destination.exists() && destination != Path::new(old_path) {
+        return Err(format!(
+            "A note named '{}' already exists in the destination",
+            filename.to_string_lossy()
+        ));
+    }
+
+    let new_path = note_manager::move_note(old_path, new_folder, notes_dir)?;
+    reindex_moved_note(cache_db, old_path, &new_path, &content, notes_dir)?;
+    carry_fold_state(old_path, &new_path, notes_dir);
+    Ok(new_path)
+}
+
+fn reindex_moved_note(
+    cache_db: &CacheDb,
+    old_path: &str,
+    new_path: &str,
+    content: &str,
+    notes_dir: &str,
+) -> Result<(), String> {
     // Clear old cache and stale metadata
-    let stale_paths = vec![old_path];
+    let stale_paths = vec![old_path.to_string()];
     cache_db.remove_stale_entries(&stale_paths)?;
+    cache_db.move_created_time(old_path, new_path)?;
 
     // Update cache and FTS with new path
-    let title = Path::new(&new_path)
+    let title = Path::new(new_path)
         .file_stem()
         .and_then(|name| name.to_str())
         .unwrap_or("Untitled");
-    cache_db.update_note_cache_with_fts(&new_path, title, &content, &state.notes_dir)?;
-    update_cached_mtime(&cache_db, &new_path)?;
-
-    Ok(new_path)
+    cache_db.update_note_cache_with_fts(new_path, title, content, notes_dir)?;
+    update_cached_mtime(cache_db, new_path)
 }
 
 #[tauri::command]
@@ -435,33 +2057,51 @@ pub async fn get_folder_contents(
     folder_path: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<String>, String> {
-    note_manager::delete_folder(&folder_path, &state.notes_dir)
+    note_manager::delete_folder(&folder_path, &state.notes_dir())
 }
 
 #[tauri::command]
 pub async fn delete_folder(folder_path: String, state: State<'_, AppState>) -> Result<(), String> {
-    // Delete the folder
-    note_manager::delete_folder_confirmed(&folder_path, &state.notes_dir)?;
-
-    // Rebuild cache from scratch to remove stale entries and refresh FTS.
-    crate::force_rebuild_cache(&state).map_err(|e| e.to_string())?;
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during delete_folder"
+    );
+    delete_folder_and_cache(&folder_path, &state.notes_dir(), &cache_db)
+}
 
-    Ok(())
+/// Delete a folder and clear the cache entries of just the notes it held
+pub(crate) fn delete_folder_and_cache(
+    folder_path: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<(), String> {
+    let deleted_notes = note_manager::delete_folder_confirmed(folder_path, notes_dir)?;
+    cache_db.remove_stale_entries(&deleted_notes)
 }
 
 #[tauri::command]
 pub async fn create_folder(folder_path: String, state: State<'_, AppState>) -> Result<(), String> {
-    note_manager::create_folder(&folder_path, &state.notes_dir)
+    note_manager::create_folder(&folder_path, &state.notes_dir())
+}
+
+#[tauri::command]
+pub async fn get_folder_tree(
+    state: State<'_, AppState>,
+) -> Result<note_manager::FolderNode, String> {
+    let notes_dir = state.notes_dir();
+    note_manager::get_folder_tree(&notes_dir, &daily_notes_folder(&notes_dir)?)
 }
 
 #[tauri::command]
 pub async fn get_all_folders(state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    note_manager::get_all_folders(&state.notes_dir)
+    let notes_dir = state.notes_dir();
+    note_manager::get_all_folders(&notes_dir, &daily_notes_folder(&notes_dir)?)
 }
 
 fn rebuild_cache_for_new_note(note_name: &str, state: &AppState) -> Result<(), String> {
     // Get all notes
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    let notes_dir = state.notes_dir();
+    let notes = note_manager::list_notes(&notes_dir)?;
     let cache_db = state
         .cache_db
         .lock()
@@ -480,7 +2120,7 @@ fn rebuild_cache_for_new_note(note_name: &str, state: &AppState) -> Result<(), S
                     &note.path,
                     &note.title,
                     &content,
-                    &state.notes_dir,
+                    &notes_dir,
                 );
             }
         }
@@ -491,25 +2131,30 @@ fn rebuild_cache_for_new_note(note_name: &str, state: &AppState) -> Result<(), S
 
 #[derive(Serialize, Deserialize)]
 pub struct GraphNode {
-    id: String,
-    label: String,
+    pub(crate) id: String,
+    pub(crate) label: String,
     title: String,
     #[serde(rename = "connectionCount")]
-    connection_count: usize,
+    pub(crate) connection_count: usize,
     #[serde(rename = "isCenter")]
     is_center: bool,
+    /// Number of edges in the returned graph touching this node
+    pub(crate) degree: usize,
+    pub(crate) tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GraphEdge {
-    from: String,
-    to: String,
+    pub(crate) from: String,
+    pub(crate) to: String,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GraphData {
-    nodes: Vec<GraphNode>,
-    edges: Vec<GraphEdge>,
+    pub(crate) nodes: Vec<GraphNode>,
+    pub(crate) edges: Vec<GraphEdge>,
+    /// Set when the graph hit the node cap and was cut short
+    pub(crate) truncated: bool,
 }
 
 /// Calculate connection count for each node
@@ -522,15 +2167,262 @@ fn calculate_connection_counts(links: &[crate::cache::Link]) -> HashMap<String,
     counts
 }
 
+/// Collapse links that connect the same pair of notes in both directions
+/// into a single edge, keeping the first direction seen
+fn dedupe_links(links: Vec<crate::cache::Link>) -> Vec<crate::cache::Link> {
+    let mut seen = HashSet::new();
+    links
+        .into_iter()
+        .filter(|link| {
+            let pair = if link.from_note <= link.to_note {
+                (link.from_note.clone(), link.to_note.clone())
+            } else {
+                (link.to_note.clone(), link.from_note.clone())
+            };
+            seen.insert(pair)
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_orphan_notes(
+    exclude_daily_notes: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NoteMetadata>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_orphan_notes"
+    );
+    find_orphan_notes(
+        &cache_db,
+        &state.notes_dir(),
+        exclude_daily_notes.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub async fn get_dead_end_notes(
+    exclude_daily_notes: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NoteMetadata>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_dead_end_notes"
+    );
+    find_dead_end_notes(
+        &cache_db,
+        &state.notes_dir(),
+        exclude_daily_notes.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub async fn get_hub_notes(
+    threshold: usize,
+    exclude_daily_notes: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NoteMetadata>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_hub_notes"
+    );
+    find_hub_notes(
+        &cache_db,
+        &state.notes_dir(),
+        threshold,
+        exclude_daily_notes.unwrap_or(false),
+    )
+}
+
+/// Notes with neither incoming nor outgoing links
+pub(crate) fn find_orphan_notes(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    exclude_daily_notes: bool,
+) -> Result<Vec<NoteMetadata>, String> {
+    let NoteLinkStats {
+        notes,
+        outgoing,
+        incoming,
+    } = note_link_stats(cache_db, notes_dir, exclude_daily_notes)?;
+    Ok(notes
+        .into_iter()
+        .filter(|note| !outgoing.contains_key(&note.path) && !incoming.contains(&note.path))
+        .collect())
+}
+
+/// Notes that are linked to but link nowhere themselves
+pub(crate) fn find_dead_end_notes(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    exclude_daily_notes: bool,
+) -> Result<Vec<NoteMetadata>, String> {
+    let NoteLinkStats {
+        notes,
+        outgoing,
+        incoming,
+    } = note_link_stats(cache_db, notes_dir, exclude_daily_notes)?;
+    Ok(notes
+        .into_iter()
+        .filter(|note| !outgoing.contains_key(&note.path) && incoming.contains(&note.path))
+        .collect())
+}
+
+/// Notes with at least `threshold` outgoing links, most links first
+pub(crate) fn find_hub_notes(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    threshold: usize,
+    exclude_daily_notes: bool,
+) -> Result<Vec<NoteMetadata>, String> {
+    let NoteLinkStats {
+        notes, outgoing, ..
+    } = note_link_stats(cache_db, notes_dir, exclude_daily_notes)?;
+    let mut hubs: Vec<(usize, NoteMetadata)> = notes
+        .into_iter()
+        .filter_map(|note| {
+            let count = outgoing.get(&note.path).copied().unwrap_or(0);
+            (count > 0 && count >= threshold).then_some((count, note))
+        })
+        .collect();
+    hubs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)));
+    Ok(hubs.into_iter().map(|(_, note)| note).collect())
+}
+
+/// Orderings for `get_note_link_stats`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStatsSort {
+    /// Most referenced notes first
+    #[default]
+    IncomingDesc,
+    /// Notes linking out the most first
+    OutgoingDesc,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteLinkCounts {
+    pub path: String,
+    pub title: String,
+    pub incoming: usize,
+    pub outgoing: usize,
+}
+
+#[tauri::command]
+pub async fn get_note_link_stats(
+    sort: Option<LinkStatsSort>,
+    include_unlinked: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<NoteLinkCounts>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during get_note_link_stats"
+    );
+    note_link_counts(
+        &cache_db,
+        &state.notes_dir(),
+        sort.unwrap_or_default(),
+        include_unlinked.unwrap_or(false),
+    )
+}
+
+/// Incoming and outgoing link counts for every note with links, or for
+/// every note when `include_unlinked` is set. Ties are broken by the other
+/// count, then by title.
+pub(crate) fn note_link_counts(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    sort: LinkStatsSort,
+    include_unlinked: bool,
+) -> Result<Vec<NoteLinkCounts>, String> {
+    let outgoing = cache_db.get_outgoing_link_counts()?;
+    let incoming = cache_db.get_incoming_link_counts()?;
+
+    let mut counts: Vec<NoteLinkCounts> = note_manager::list_notes(notes_dir)?
+        .into_iter()
+        .map(|note| NoteLinkCounts {
+            incoming: incoming.get(&note.path).copied().unwrap_or(0),
+            outgoing: outgoing.get(&note.path).copied().unwrap_or(0),
+            path: note.path,
+            title: note.title,
+        })
+        .filter(|note| include_unlinked || note.incoming + note.outgoing > 0)
+        .collect();
+
+    counts.sort_by(|a, b| {
+        let (a_key, b_key) = match sort {
+            LinkStatsSort::IncomingDesc => ((a.incoming, a.outgoing), (b.incoming, b.outgoing)),
+            LinkStatsSort::OutgoingDesc => ((a.outgoing, a.incoming), (b.outgoing, b.incoming)),
+        };
+        b_key
+            .cmp(&a_key)
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(counts)
+}
+
+struct NoteLinkStats {
+    notes: Vec<NoteMetadata>,
+    outgoing: HashMap<String, usize>,
+    incoming: HashSet<String>,
+}
+
+/// All notes (optionally without daily notes), their outgoing link counts
+/// and the set of notes with backlinks. Self-links are ignored.
+fn note_link_stats(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    exclude_daily_notes: bool,
+) -> Result<NoteLinkStats, String> {
+    let mut notes = note_manager::list_notes(notes_dir)?;
+    if exclude_daily_notes {
+        let daily_folder = daily_notes_folder(notes_dir)?;
+        notes.retain(|note| !Path::new(&note.relative_path).starts_with(&daily_folder));
+    }
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut outgoing: HashMap<String, usize> = HashMap::new();
+    let mut incoming = HashSet::new();
+    for link in cache_db.get_all_links()? {
+        if link.from_note == link.to_note {
+            continue;
+        }
+        *outgoing.entry(link.from_note).or_insert(0) += 1;
+        incoming.insert(link.to_note);
+    }
+
+    Ok(NoteLinkStats {
+        notes,
+        outgoing,
+        incoming,
+    })
+}
+
 #[tauri::command]
-pub async fn get_global_graph(state: State<'_, AppState>) -> Result<GraphData, String> {
+pub async fn get_global_graph(
+    include_orphans: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<GraphData, String> {
     let cache_db = state
         .cache_db
         .lock()
         .map_err(|_| "Failed to lock cache database")?;
 
-    let links = cache_db.get_all_links()?;
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    build_global_graph(
+        &cache_db,
+        &state.notes_dir(),
+        include_orphans.unwrap_or(false),
+    )
+}
+
+pub(crate) fn build_global_graph(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    include_orphans: bool,
+) -> Result<GraphData, String> {
+    let links = dedupe_links(cache_db.get_all_links()?);
+    let notes = note_manager::list_notes(notes_dir)?;
 
     // Calculate connection counts
     let connection_counts = calculate_connection_counts(&links);
@@ -542,11 +2434,11 @@ pub async fn get_global_graph(state: State<'_, AppState>) -> Result<GraphData, S
         linked_notes.insert(link.to_note.clone());
     }
 
-    // Create nodes only for notes that have links
+    // Create nodes for notes that have links, or for every note when orphans are wanted
     let mut nodes = Vec::new();
 
     for note in notes {
-        if linked_notes.contains(&note.path) {
+        if include_orphans || linked_notes.contains(&note.path) {
             let connection_count = connection_counts.get(&note.path).copied().unwrap_or(0);
             nodes.push(GraphNode {
                 id: note.path.clone(),
@@ -554,6 +2446,8 @@ pub async fn get_global_graph(state: State<'_, AppState>) -> Result<GraphData, S
                 title: note.title,
                 connection_count,
                 is_center: false,
+                degree: connection_count,
+                tags: cache_db.get_tags_for_note(&note.path)?,
             });
         }
     }
@@ -567,7 +2461,28 @@ pub async fn get_global_graph(state: State<'_, AppState>) -> Result<GraphData, S
         });
     }
 
-    Ok(GraphData { nodes, edges })
+    Ok(GraphData {
+        nodes,
+        edges,
+        truncated: false,
+    })
+}
+
+#[tauri::command]
+pub async fn export_graph(
+    format: export::GraphFormat,
+    out_path: String,
+    include_orphans: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during export_graph"
+    );
+
+    let notes_dir = state.notes_dir();
+    let graph = build_global_graph(&cache_db, &notes_dir, include_orphans.unwrap_or(false))?;
+    export::export_graph(&graph, &notes_dir, &out_path, format)
 }
 
 #[tauri::command]
@@ -582,7 +2497,7 @@ pub async fn get_filtered_graph(
         .map_err(|_| "Failed to lock cache database")?;
 
     let all_links = cache_db.get_all_links()?;
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    let notes = note_manager::list_notes(&state.notes_dir())?;
 
     // Build note title lookup
     let note_map: HashMap<String, String> = notes
@@ -597,6 +2512,7 @@ pub async fn get_filtered_graph(
             return Ok(GraphData {
                 nodes: vec![],
                 edges: vec![],
+                truncated: false,
             })
         }
     };
@@ -614,6 +2530,7 @@ pub async fn get_filtered_graph(
         return Ok(GraphData {
             nodes: vec![],
             edges: vec![],
+            truncated: false,
         });
     }
 
@@ -666,22 +2583,21 @@ pub async fn get_filtered_graph(
     }
 
     // Create nodes
-    let nodes: Vec<GraphNode> = visited
-        .iter()
-        .filter_map(|path| {
-            note_map.get(path).map(|title| {
-                let connection_count = connection_counts.get(path).copied().unwrap_or(0);
-                let is_center = matching_nodes.contains(path);
-                GraphNode {
-                    id: path.clone(),
-                    label: title.clone(),
-                    title: title.clone(),
-                    connection_count,
-                    is_center,
-                }
-            })
-        })
-        .collect();
+    let mut nodes = Vec::new();
+    for path in &visited {
+        if let Some(title) = note_map.get(path) {
+            let connection_count = connection_counts.get(path).copied().unwrap_or(0);
+            nodes.push(GraphNode {
+                id: path.clone(),
+                label: title.clone(),
+                title: title.clone(),
+                connection_count,
+                is_center: matching_nodes.contains(path),
+                degree: connection_count,
+                tags: cache_db.get_tags_for_note(path)?,
+            });
+        }
+    }
 
     // Create edges
     let edges: Vec<GraphEdge> = filtered_links
@@ -692,12 +2608,105 @@ pub async fn get_filtered_graph(
         })
         .collect();
 
-    Ok(GraphData { nodes, edges })
+    Ok(GraphData {
+        nodes,
+        edges,
+        truncated: false,
+    })
+}
+
+/// Longest chain of links `get_connection_path` searches before giving up
+const MAX_CONNECTION_DEPTH: usize = 6;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectionPath {
+    /// Note paths from `from` to `to`, or `None` when they aren't connected
+    /// within `MAX_CONNECTION_DEPTH` links
+    pub path: Option<Vec<String>>,
+    /// Notes visited by the search
+    pub explored: usize,
+}
+
+#[tauri::command]
+pub async fn get_connection_path(
+    from: String,
+    to: String,
+    state: State<'_, AppState>,
+) -> Result<ConnectionPath, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_connection_path"
+    );
+
+    find_connection_path(&cache_db, &from, &to)
+}
+
+/// Shortest chain of notes linking `from` to `to`, following links in
+/// either direction
+pub(crate) fn find_connection_path(
+    cache_db: &CacheDb,
+    from: &str,
+    to: &str,
+) -> Result<ConnectionPath, String> {
+    let mut adjacency: HashMap<String, Vec<String>> = HashMap::new();
+    for link in cache_db.get_all_links()? {
+        adjacency
+            .entry(link.from_note.clone())
+            .or_default()
+            .push(link.to_note.clone());
+        adjacency
+            .entry(link.to_note)
+            .or_default()
+            .push(link.from_note);
+    }
+
+    // Each visited note maps to the note it was reached from
+    let mut previous: HashMap<&str, Option<&str>> = HashMap::from([(from, None)]);
+    let mut frontier = vec![from];
+
+    for _ in 0..MAX_CONNECTION_DEPTH {
+        if previous.contains_key(to) {
+            break;
+        }
+        let mut next_frontier = Vec::new();
+        for node in frontier {
+            for neighbor in adjacency.get(node).into_iter().flatten() {
+                if !previous.contains_key(neighbor.as_str()) {
+                    previous.insert(neighbor, Some(node));
+                    next_frontier.push(neighbor.as_str());
+                }
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+
+    let path = previous.contains_key(to).then(|| {
+        let mut path = vec![to.to_string()];
+        let mut current = to;
+        while let Some(Some(prev)) = previous.get(current) {
+            path.push(prev.to_string());
+            current = prev;
+        }
+        path.reverse();
+        path
+    });
+
+    Ok(ConnectionPath {
+        path,
+        explored: previous.len(),
+    })
 }
 
+/// Upper bound on nodes returned by the local graph, however deep it goes
+const MAX_LOCAL_GRAPH_NODES: usize = 500;
+
 #[tauri::command]
 pub async fn get_local_graph(
     note_path: String,
+    depth: Option<u32>,
     state: State<'_, AppState>,
 ) -> Result<GraphData, String> {
     let cache_db = state
@@ -705,8 +2714,23 @@ pub async fn get_local_graph(
         .lock()
         .map_err(|_| "Failed to lock cache database")?;
 
-    let links = cache_db.get_links_for_note(&note_path)?;
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    build_local_graph(
+        &cache_db,
+        &state.notes_dir(),
+        &note_path,
+        depth.unwrap_or(1),
+    )
+}
+
+/// Graph of the notes within `depth` links of `note_path`, expanded breadth
+/// first and capped at `MAX_LOCAL_GRAPH_NODES`
+pub(crate) fn build_local_graph(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    note_path: &str,
+    depth: u32,
+) -> Result<GraphData, String> {
+    let notes = note_manager::list_notes(notes_dir)?;
 
     // Create a map for quick lookup
     let note_map: HashMap<String, String> = notes
@@ -714,35 +2738,67 @@ pub async fn get_local_graph(
         .map(|note| (note.path, note.title))
         .collect();
 
-    // Collect all connected notes
+    // Collect all connected notes, one hop at a time
     let mut connected_notes = HashSet::new();
-    connected_notes.insert(note_path.clone());
+    connected_notes.insert(note_path.to_string());
+    let mut links = Vec::new();
+    let mut frontier = vec![note_path.to_string()];
+    let mut truncated = false;
+
+    'expand: for _ in 0..depth {
+        let mut next_frontier = Vec::new();
+        for path in &frontier {
+            for link in cache_db.get_links_for_note(path)? {
+                let neighbor = if link.from_note == *path {
+                    &link.to_note
+                } else {
+                    &link.from_note
+                };
+
+                if !connected_notes.contains(neighbor) {
+                    if connected_notes.len() >= MAX_LOCAL_GRAPH_NODES {
+                        truncated = true;
+                        break 'expand;
+                    }
+                    connected_notes.insert(neighbor.clone());
+                    next_frontier.push(neighbor.clone());
+                }
 
-    for link in &links {
-        connected_notes.insert(link.from_note.clone());
-        connected_notes.insert(link.to_note.clone());
+                links.push(link);
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
     }
 
-    // Calculate connection counts for the subgraph
-    let mut connection_counts: HashMap<String, usize> = HashMap::new();
-    for link in &links {
-        *connection_counts.entry(link.from_note.clone()).or_insert(0) += 1;
-        *connection_counts.entry(link.to_note.clone()).or_insert(0) += 1;
-    }
+    // Count each pair of notes once, as the global graph does
+    let links = dedupe_links(links);
+    let connection_counts = calculate_connection_counts(&links);
 
-    // Create nodes
+    // Create nodes; the center note is always present, even if it isn't listed yet
     let mut nodes = Vec::new();
     for path in &connected_notes {
-        if let Some(title) = note_map.get(path) {
-            let connection_count = connection_counts.get(path).copied().unwrap_or(0);
-            nodes.push(GraphNode {
-                id: path.clone(),
-                label: title.clone(),
-                title: title.clone(),
-                connection_count,
-                is_center: path == &note_path,
-            });
-        }
+        let title = match note_map.get(path) {
+            Some(title) => title.clone(),
+            None if path == note_path => Path::new(path)
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .unwrap_or("Untitled")
+                .to_string(),
+            None => continue,
+        };
+        let connection_count = connection_counts.get(path).copied().unwrap_or(0);
+        nodes.push(GraphNode {
+            id: path.clone(),
+            label: title.clone(),
+            title,
+            connection_count,
+            is_center: path == note_path,
+            degree: connection_count,
+            tags: cache_db.get_tags_for_note(path)?,
+        });
     }
 
     // Create edges
@@ -754,7 +2810,11 @@ pub async fn get_local_graph(
         });
     }
 
-    Ok(GraphData { nodes, edges })
+    Ok(GraphData {
+        nodes,
+        edges,
+        truncated,
+    })
 }
 #[tauri::command]
 pub async fn save_image(
@@ -763,7 +2823,7 @@ pub async fn save_image(
     note_path: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    validate_path_security(&note_path, &state.notes_dir).map_err(|e| e.to_string())?;
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
 
     // Sanitize filename to prevent path traversal
     let filename = filename
@@ -788,8 +2848,43 @@ pub async fn save_image(
             .map_err(|e| format!("Failed to create images directory: {e}"))?;
     }
 
+    let final_filename = store_image(&images_dir, &filename, &image_data)?;
+
+    // Return relative path from note location
+    Ok(format!("images/{final_filename}"))
+}
+
+/// Write `image_data` into `images_dir` and return the file name used. If
+/// an identical image is already there, its name is returned instead of
+/// writing a duplicate.
+pub(crate) fn store_image(
+    images_dir: &Path,
+    filename: &str,
+    image_data: &[u8],
+) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let hash = Sha256::digest(image_data);
+    let entries = std::fs::read_dir(images_dir)
+        .map_err(|e| format!("Failed to read images directory: {e}"))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let same_size = entry
+            .metadata()
+            .map(|metadata| metadata.is_file() && metadata.len() == image_data.len() as u64)
+            .unwrap_or(false);
+        if !same_size {
+            continue;
+        }
+        if let Ok(existing) = std::fs::read(&path) {
+            if Sha256::digest(&existing) == hash {
+                return Ok(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
     // Generate unique filename if file already exists
-    let mut final_filename = filename.clone();
+    let mut final_filename = filename.to_string();
     let mut counter = 1;
     while images_dir.join(&final_filename).exists() {
         let name_parts: Vec<&str> = filename.rsplitn(2, '.').collect();
@@ -805,8 +2900,26 @@ pub async fn save_image(
     let image_path = images_dir.join(&final_filename);
     std::fs::write(&image_path, image_data).map_err(|e| format!("Failed to save image: {e}"))?;
 
-    // Return relative path from note location
-    Ok(format!("images/{final_filename}"))
+    Ok(final_filename)
+}
+
+#[tauri::command]
+pub async fn get_unreferenced_assets(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    attachments::find_unreferenced_assets(&state.notes_dir())
+}
+
+#[tauri::command]
+pub async fn delete_unreferenced_assets(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    attachments::delete_assets(&paths, &state.notes_dir())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedAttachment {
+    pub path: String, // relative to the note's folder
+    pub content_type: String,
 }
 
 #[tauri::command]
@@ -815,8 +2928,8 @@ pub async fn save_attachment(
     filename: String,
     note_path: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    validate_path_security(&note_path, &state.notes_dir).map_err(|e| e.to_string())?;
+) -> Result<SavedAttachment, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
 
     // Sanitize filename to prevent path traversal
     let filename = filename
@@ -828,6 +2941,10 @@ pub async fn save_attachment(
         return Err("Invalid filename".to_string());
     }
 
+    // Check the attachment before anything touches the disk
+    let settings = load_app_settings(&state.notes_dir())?;
+    let content_type = attachments::validate_attachment(&filename, &file_data, &settings)?;
+
     // Get the directory of the current note
     let note_path_buf = std::path::Path::new(&note_path);
     let note_dir = note_path_buf
@@ -860,7 +2977,10 @@ pub async fn save_attachment(
         .map_err(|e| format!("Failed to save attachment: {e}"))?;
 
     // Return relative path from note location
-    Ok(format!("attachments/{final_filename}"))
+    Ok(SavedAttachment {
+        path: format!("attachments/{final_filename}"),
+        content_type: content_type.to_string(),
+    })
 }
 
 #[tauri::command]
@@ -872,7 +2992,7 @@ pub async fn open_file_external(
 ) -> Result<(), String> {
     use tauri_plugin_opener::OpenerExt;
 
-    validate_path_security(&note_path, &state.notes_dir).map_err(|e| e.to_string())?;
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
     // Get the directory of the current note
     let note_path_buf = std::path::Path::new(&note_path);
     let note_dir = note_path_buf
@@ -911,6 +3031,98 @@ pub async fn open_file_external(
         .map_err(|e| format!("Failed to open file: {e}"))
 }
 
+/// Show a note or folder in the system file manager, selecting the note
+/// where the platform supports it. An empty path opens the notes directory.
+#[tauri::command]
+pub async fn reveal_in_file_manager(
+    path: String,
+    window: tauri::WebviewWindow,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let notes_dir = state.notes_dir();
+    let target = if path.trim().is_empty() {
+        notes_dir.clone()
+    } else {
+        path
+    };
+    validate_path_security(&target, &notes_dir).map_err(|e| e.to_string())?;
+
+    let target_path = Path::new(&target);
+    if target_path.is_dir() {
+        window
+            .opener()
+            .open_path(target.as_str(), None::<String>)
+            .map_err(|e| format!("Failed to open folder: {e}"))
+    } else if target_path.exists() {
+        window
+            .opener()
+            .reveal_item_in_dir(target_path)
+            .map_err(|e| format!("Failed to reveal file: {e}"))
+    } else {
+        Err("File not found".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn import_markdown_dir(
+    source: String,
+    state: State<'_, AppState>,
+) -> Result<import::ImportSummary, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during import_markdown_dir"
+    );
+
+    import::import_markdown_dir(&source, &state.notes_dir(), &cache_db)
+}
+
+#[tauri::command]
+pub async fn export_note_html(
+    note_path: String,
+    out_path: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    export::export_note(&note_path, &out_path)
+}
+
+#[tauri::command]
+pub async fn export_vault_html(
+    out_dir: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during export_vault_html"
+    );
+
+    export::export_vault(&state.notes_dir(), &out_dir, &cache_db)
+}
+
+/// Save every bookmark as a browser-importable HTML file, in folders by
+/// domain (the default) or by the note it came from
+#[tauri::command]
+pub async fn export_bookmarks_html(
+    out_path: String,
+    group_by: Option<export::BookmarkGrouping>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during export_bookmarks_html"
+    );
+
+    export::export_bookmarks(
+        &cache_db,
+        &state.notes_dir(),
+        &out_path,
+        group_by.unwrap_or_default(),
+    )
+}
+
 #[tauri::command]
 pub async fn get_incomplete_todos(state: State<'_, AppState>) -> Result<Vec<Todo>, String> {
     let cache_db = state
@@ -931,12 +3143,69 @@ pub async fn get_all_todos(state: State<'_, AppState>) -> Result<Vec<Todo>, Stri
     cache_db.get_all_todos()
 }
 
-// Helper function to create a new instance of a recurring todo
+#[tauri::command]
+pub async fn get_todos_scheduled_today(state: State<'_, AppState>) -> Result<Vec<Todo>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_todos_scheduled_today"
+    );
+
+    cache_db.get_todos_scheduled_today()
+}
+
+#[tauri::command]
+pub async fn get_overdue_todos(state: State<'_, AppState>) -> Result<Vec<Todo>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_overdue_todos"
+    );
+
+    cache_db.get_overdue_todos()
+}
+
+#[tauri::command]
+pub async fn get_todos_by_priority(
+    priority: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Todo>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_todos_by_priority"
+    );
+
+    cache_db.get_todos_by_priority(&priority)
+}
+
+#[tauri::command]
+pub async fn get_incomplete_todos_sorted(state: State<'_, AppState>) -> Result<Vec<Todo>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_incomplete_todos_sorted"
+    );
+
+    cache_db.get_incomplete_todos_sorted()
+}
+
+#[tauri::command]
+pub async fn get_todos_agenda(
+    days: u32,
+    state: State<'_, AppState>,
+) -> Result<BTreeMap<String, Vec<Todo>>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_todos_agenda"
+    );
+
+    cache_db.get_todos_agenda(days)
+}
+
+/// Append the next instance of a recurring todo to today's daily note.
+/// Returns `false` when an identical open instance is already there.
 fn create_recurring_todo_instance(
     todo: &Todo,
     notes_dir: &str,
     cache_db: &CacheDb,
-) -> Result<(), String> {
+) -> Result<bool, String> {
     use crate::cache::calculate_next_occurrence;
     use chrono::Local;
 
@@ -950,7 +3219,7 @@ fn create_recurring_todo_instance(
     let next_due_date = calculate_next_occurrence(pattern);
 
     // Get today's daily note path
-    let daily_notes_dir = Path::new(notes_dir).join("Daily Notes");
+    let daily_notes_dir = Path::new(notes_dir).join(daily_notes_folder(notes_dir)?);
     let today = Local::now().format("%Y-%m-%d").to_string();
     let daily_note_path = daily_notes_dir.join(format!("{}.md", today));
 
@@ -958,7 +3227,7 @@ fn create_recurring_todo_instance(
     if !daily_note_path.exists() {
         let template = format!("# {}\n\n## Tasks\n\n", today);
         std::fs::create_dir_all(&daily_notes_dir)
-            .map_err(|e| format!("Failed to create Daily Notes directory: {e}"))?;
+            .map_err(|e| format!("Failed to create daily notes directory: {e}"))?;
         std::fs::write(&daily_note_path, template)
             .map_err(|e| format!("Failed to create daily note: {e}"))?;
     }
@@ -967,9 +3236,16 @@ fn create_recurring_todo_instance(
     let mut content = std::fs::read_to_string(&daily_note_path)
         .map_err(|e| format!("Failed to read daily note: {e}"))?;
 
-    // Create new todo line with updated due date and same metadata
+    // Create new todo line with updated due date and same metadata.
+    // Tags and other inline markers are carried over as part of the content.
     let mut new_todo = format!("- [ ] {}", todo.content);
 
+    // Keep the recurrence marker so the new instance recurs as well
+    let recurrence_regex = regex::Regex::new(r"(?:@every|@repeat)\([^)]+\)").unwrap();
+    if !recurrence_regex.is_match(&new_todo) {
+        new_todo = format!("{} @repeat({})", new_todo, pattern);
+    }
+
     // Preserve priority
     if let Some(priority) = &todo.priority {
         if !new_todo.contains(&format!("!{}", priority)) {
@@ -987,11 +3263,17 @@ fn create_recurring_todo_instance(
         new_todo = format!("{} @due({})", content_without_date.trim(), due_date);
     }
 
+    // Toggling the same todo back and forth must not pile up copies
+    if content.lines().any(|line| line.trim() == new_todo) {
+        return Ok(false);
+    }
+
     // Append the new todo to the daily note
+    let ending = note_manager::line_ending(&content);
     if !content.ends_with('\n') {
-        content.push('\n');
+        content.push_str(ending);
     }
-    content.push_str(&format!("{}\n", new_todo));
+    content.push_str(&format!("{new_todo}{ending}"));
 
     // Write back to daily note
     std::fs::write(&daily_note_path, &content)
@@ -1006,102 +3288,254 @@ fn create_recurring_todo_instance(
     )?;
     update_cached_mtime(cache_db, &daily_note_path.to_string_lossy())?;
 
-    Ok(())
+    Ok(true)
 }
 
 #[tauri::command]
 pub async fn toggle_todo(
     note_path: String,
     line_number: i32,
+    cascade: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    validate_path_security(&note_path, &state.notes_dir).map_err(|e| e.to_string())?;
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    toggle_todo_in_note(
+        &note_path,
+        line_number,
+        cascade.unwrap_or(false),
+        &state.notes_dir(),
+        &state.cache_db,
+    )
+}
 
+/// Flip the checkbox on `line_number`, refresh the cache, and spawn the next
+/// instance of a recurring todo when it gets completed. With `cascade`, nested
+/// todos below the line get the same state. Returns the note's content as it
+/// is on disk afterwards.
+pub(crate) fn toggle_todo_in_note(
+    note_path: &str,
+    line_number: i32,
+    cascade: bool,
+    notes_dir: &str,
+    cache_db: &Mutex<CacheDb>,
+) -> Result<String, String> {
     // Extract todo info and toggle state (in a scope to drop the mutex guard)
     let (todo_info, new_state) = {
-        let cache_db = state
-            .cache_db
+        let cache_db = cache_db
             .lock()
             .map_err(|_| "Failed to lock cache database")?;
 
         // Get todo info before toggling (to check for recurrence)
-        let todo = cache_db.get_todo(&note_path, line_number)?;
+        let todo = cache_db.get_todo(note_path, line_number)?;
 
         // Toggle the todo in the database
-        let state = cache_db.toggle_todo(&note_path, line_number)?;
+        let state = cache_db.toggle_todo(note_path, line_number)?;
 
         (todo, state)
     }; // MutexGuard is dropped here
 
     // Read the note content
     let mut content =
-        read_file_with_encoding(&note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+        read_file_with_encoding(note_path).map_err(|e| format!("Failed to read note: {e}"))?;
 
     // Update the content
     let lines: Vec<&str> = content.lines().collect();
     let line_index = (line_number - 1) as usize;
 
     if line_index < lines.len() {
-        let line = lines[line_index];
-        let updated_line = if new_state {
-            line.replace("- [ ]", "- [x]").replace("* [ ]", "* [x]")
+        // Descendants come from the file as it is now; cached line numbers may be stale.
+        // The re-index below brings the cache in line with the file.
+        let last_index = if cascade {
+            last_descendant_index(&lines, line_index)
         } else {
-            line.replace("- [x]", "- [ ]")
-                .replace("* [x]", "* [ ]")
-                .replace("- [X]", "- [ ]")
-                .replace("* [X]", "* [ ]")
+            line_index
         };
 
         // Reconstruct the content
-        let mut new_lines = lines.to_vec();
-        new_lines[line_index] = &updated_line;
-        content = new_lines.join("\n");
+        let mut new_lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        for line in &mut new_lines[line_index..=last_index] {
+            *line = set_todo_checkbox(line, new_state);
+        }
+        let ending = note_manager::line_ending(&content);
+        let mut new_content = new_lines.join(ending);
 
         // If original content ended with newline, preserve it
-        if read_file_with_encoding(&note_path)
-            .map_err(|e| format!("Failed to read note: {e}"))?
-            .ends_with('\n')
-        {
-            content.push('\n');
+        if content.ends_with('\n') {
+            new_content.push_str(ending);
         }
+        content = new_content;
 
         // Save the updated content
-        std::fs::write(&note_path, &content).map_err(|e| format!("Failed to write note: {e}"))?;
-
-        // Handle recurring tasks: if marked as complete and has recurrence pattern, create new instance
-        if new_state && todo_info.recurrence_pattern.is_some() {
-            // Lock cache again for recurring task creation
-            let cache_db = state
-                .cache_db
-                .lock()
-                .map_err(|_| "Failed to lock cache database")?;
-
-            if let Err(e) = create_recurring_todo_instance(&todo_info, &state.notes_dir, &cache_db)
-            {
-                eprintln!("Failed to create recurring todo instance: {}", e);
-                // Don't fail the whole operation if recurring creation fails
-            }
-        }
+        std::fs::write(note_path, &content).map_err(|e| format!("Failed to write note: {e}"))?;
 
-        // Refresh cache/FTS for the updated note content
-        let cache_db = state
-            .cache_db
+        let cache_db = cache_db
             .lock()
             .map_err(|_| "Failed to lock cache database")?;
-        let title = Path::new(&note_path)
+
+        // Refresh cache/FTS for the updated note content
+        let title = Path::new(note_path)
             .file_stem()
             .and_then(|name| name.to_str())
             .unwrap_or("Untitled");
-        cache_db.update_note_cache_with_fts(&note_path, title, &content, &state.notes_dir)?;
-        update_cached_mtime(&cache_db, &note_path)?;
+        cache_db.update_note_cache_with_fts(note_path, title, &content, notes_dir)?;
+        update_cached_mtime(&cache_db, note_path)?;
+
+        // Handle recurring tasks: if marked as complete and has recurrence pattern, create new instance.
+        // This runs after the refresh above so that completing a todo inside today's daily note
+        // doesn't overwrite the freshly appended instance with stale content.
+        if new_state && todo_info.recurrence_pattern.is_some() {
+            match create_recurring_todo_instance(&todo_info, notes_dir, &cache_db) {
+                Ok(false) => {}
+                Ok(true) => {
+                    // The new instance may have been appended to this very note
+                    content = read_file_with_encoding(note_path)
+                        .map_err(|e| format!("Failed to read note: {e}"))?;
+                }
+                Err(e) => {
+                    eprintln!("Failed to create recurring todo instance: {}", e);
+                    // Don't fail the whole operation if recurring creation fails
+                }
+            }
+        }
+    }
+
+    Ok(content)
+}
+
+fn set_todo_checkbox(line: &str, completed: bool) -> String {
+    if completed {
+        line.replace("- [ ]", "- [x]").replace("* [ ]", "* [x]")
+    } else {
+        line.replace("- [x]", "- [ ]")
+            .replace("* [x]", "* [ ]")
+            .replace("- [X]", "- [ ]")
+            .replace("* [X]", "* [ ]")
+    }
+}
+
+/// Index of the last line nested under the line at `index`, i.e. the end of
+/// the run of blank or more deeply indented lines that follows it
+fn last_descendant_index(lines: &[&str], index: usize) -> usize {
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    let parent_indent = indent_of(lines[index]);
+
+    let mut last = index;
+    for (offset, line) in lines[index + 1..].iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent_of(line) <= parent_indent {
+            break;
+        }
+        last = index + 1 + offset;
+    }
+    last
+}
+
+#[tauri::command]
+pub async fn update_todo(
+    note_path: String,
+    line_number: i32,
+    new_content: String,
+    priority: Option<String>,
+    due_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    validate_path_security(&note_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned while updating a todo"
+    );
+    update_todo_in_note(
+        &note_path,
+        line_number,
+        &new_content,
+        priority.as_deref(),
+        due_date.as_deref(),
+        &state.notes_dir(),
+        &cache_db,
+    )
+}
+
+/// Rewrite the text, priority and due date of the todo on `line_number`,
+/// keeping its indentation and checkbox. Returns the updated note content.
+pub(crate) fn update_todo_in_note(
+    note_path: &str,
+    line_number: i32,
+    new_content: &str,
+    priority: Option<&str>,
+    due_date: Option<&str>,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<String, String> {
+    if let Some(priority) = priority {
+        if !matches!(priority, "high" | "medium" | "low") {
+            return Err(format!("Invalid priority: {priority}"));
+        }
+    }
+    if let Some(due_date) = due_date {
+        chrono::NaiveDate::parse_from_str(due_date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid due date: {due_date}"))?;
+    }
+
+    // Priority and due date are passed separately, so drop any inline copies
+    let metadata_regex = regex::Regex::new(
+        r"(?:!(?:high|medium|low)\b|\bp:[123]\b|@due\([^)]+\)|\bdue:\d{4}-\d{2}-\d{2}|📅\s*\d{4}-\d{2}-\d{2})",
+    )
+    .unwrap();
+    let text = metadata_regex.replace_all(new_content, "");
+    let mut text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return Err("Todo content cannot be empty".to_string());
+    }
+    if let Some(priority) = priority {
+        text = format!("{text} !{priority}");
+    }
+    if let Some(due_date) = due_date {
+        text = format!("{text} @due({due_date})");
     }
 
-    Ok(content)
+    let content =
+        read_file_with_encoding(note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let line = usize::try_from(line_number - 1)
+        .ok()
+        .and_then(|index| lines.get_mut(index))
+        .ok_or_else(|| format!("Line {line_number} is out of range"))?;
+
+    // The note may have changed since the todo was indexed
+    let todo_regex = regex::Regex::new(r"^(\s*[-*]\s*\[[ xX]\])\s*\S").unwrap();
+    let marker = todo_regex
+        .captures(line)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_string())
+        .ok_or_else(|| format!("Line {line_number} is no longer a todo"))?;
+    *line = format!("{marker} {text}");
+
+    let ending = note_manager::line_ending(&content);
+    let mut new_content = lines.join(ending);
+    if content.ends_with('\n') {
+        new_content.push_str(ending);
+    }
+
+    std::fs::write(note_path, &new_content).map_err(|e| format!("Failed to write note: {e}"))?;
+
+    let title = Path::new(note_path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+    cache_db.update_note_cache_with_fts(note_path, title, &new_content, notes_dir)?;
+    update_cached_mtime(cache_db, note_path)?;
+
+    Ok(new_content)
 }
 
 #[tauri::command]
 pub async fn get_daily_note_template(state: State<'_, AppState>) -> Result<String, String> {
-    let settings_path = Path::new(&state.notes_dir).join(".plainflux");
+    let settings_path = Path::new(&state.notes_dir()).join(".plainflux");
     let template_path = settings_path.join("daily_note_template.md");
 
     match safe_read_file(&template_path) {
@@ -1121,7 +3555,7 @@ pub async fn save_daily_note_template(
     template: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let settings_path = Path::new(&state.notes_dir).join(".plainflux");
+    let settings_path = Path::new(&state.notes_dir()).join(".plainflux");
     let template_path = settings_path.join("daily_note_template.md");
 
     // Ensure settings directory exists with proper error handling
@@ -1129,7 +3563,7 @@ pub async fn save_daily_note_template(
         .map_err(|e| format!("Failed to create settings directory: {e}"))?;
 
     // Validate the template path is within notes directory
-    validate_path_security(&template_path, &state.notes_dir)
+    validate_path_security(&template_path, &state.notes_dir())
         .map_err(|e| format!("Security error: {e}"))?;
 
     // Save the template with atomic write
@@ -1145,7 +3579,7 @@ pub async fn rename_note(
     new_name: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    validate_path_security(&old_path, &state.notes_dir).map_err(|e| e.to_string())?;
+    validate_path_security(&old_path, &state.notes_dir()).map_err(|e| e.to_string())?;
     // Reject new_name containing path separators or traversal
     if new_name.contains('/') || new_name.contains('\\') || new_name.contains("..") {
         return Err("Invalid note name: must not contain path separators".to_string());
@@ -1161,8 +3595,9 @@ pub async fn rename_note(
         .map_err(|_| "Failed to lock cache database")?;
 
     // Clear old cache and stale metadata
-    let stale_paths = vec![old_path];
+    let stale_paths = vec![old_path.clone()];
     cache_db.remove_stale_entries(&stale_paths)?;
+    cache_db.move_created_time(&old_path, &new_path)?;
 
     // Read content and update cache/FTS with new path
     let content = read_file_with_encoding(&new_path)?;
@@ -1170,8 +3605,9 @@ pub async fn rename_note(
         .file_stem()
         .and_then(|name| name.to_str())
         .unwrap_or("Untitled");
-    cache_db.update_note_cache_with_fts(&new_path, title, &content, &state.notes_dir)?;
+    cache_db.update_note_cache_with_fts(&new_path, title, &content, &state.notes_dir())?;
     update_cached_mtime(&cache_db, &new_path)?;
+    carry_fold_state(&old_path, &new_path, &state.notes_dir());
 
     Ok(new_path)
 }
@@ -1183,13 +3619,13 @@ pub async fn rename_folder(
     state: State<'_, AppState>,
 ) -> Result<String, String> {
     // Get all notes in the folder before renaming
-    let notes_in_folder = note_manager::list_notes(&state.notes_dir)?
+    let notes_in_folder = note_manager::list_notes(&state.notes_dir())?
         .into_iter()
         .filter(|note| note.path.contains(&format!("{}/", &old_path)))
         .collect::<Vec<_>>();
 
     // Rename the folder
-    let new_path = note_manager::rename_folder(&old_path, &new_name, &state.notes_dir)?;
+    let new_path = note_manager::rename_folder(&old_path, &new_name, &state.notes_dir())?;
 
     // Update cache for all notes in the renamed folder
     let cache_db = state
@@ -1209,6 +3645,7 @@ pub async fn rename_folder(
     for old_note in notes_in_folder {
         // Calculate new note path
         let new_note_path = old_note.path.replace(&old_path, &new_path);
+        cache_db.move_created_time(&old_note.path, &new_note_path)?;
 
         // Update cache and FTS with new path
         let content = read_file_with_encoding(&new_note_path)?;
@@ -1216,10 +3653,11 @@ pub async fn rename_folder(
             &new_note_path,
             &old_note.title,
             &content,
-            &state.notes_dir,
+            &state.notes_dir(),
         )?;
         update_cached_mtime(&cache_db, &new_note_path)?;
     }
+    carry_fold_state(&old_path, &new_path, &state.notes_dir());
 
     Ok(new_path)
 }
@@ -1254,6 +3692,31 @@ pub async fn get_git_blame(
     git_manager.get_blame_info(&file_path)
 }
 
+#[tauri::command]
+pub async fn get_note_diff(
+    file_path: String,
+    old_commit: String,
+    new_commit: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    validate_path_security(&file_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    let git_manager = lock_mutex!(
+        state.git_manager,
+        "Git manager mutex was poisoned during get_note_diff"
+    );
+    git_manager.get_note_diff(&file_path, &old_commit, new_commit.as_deref())
+}
+
+#[tauri::command]
+pub async fn get_git_status(state: State<'_, AppState>) -> Result<GitStatus, String> {
+    let git_manager = lock_mutex!(
+        state.git_manager,
+        "Git manager mutex was poisoned during get_git_status"
+    );
+    git_manager.get_git_status()
+}
+
 #[tauri::command]
 pub async fn git_commit(message: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
     let git_manager = lock_mutex!(
@@ -1263,9 +3726,268 @@ pub async fn git_commit(message: Option<String>, state: State<'_, AppState>) ->
     git_manager.commit_changes(message.as_deref())
 }
 
+#[tauri::command]
+pub async fn restore_note_version(
+    file_path: String,
+    commit_hash: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    validate_path_security(&file_path, &state.notes_dir()).map_err(|e| e.to_string())?;
+
+    // Same lock order as save_note: cache first, then git
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during restore_note_version"
+    );
+    let git_manager = lock_mutex!(
+        state.git_manager,
+        "Git manager mutex was poisoned during restore_note_version"
+    );
+    restore_note_from_commit(
+        &git_manager,
+        &cache_db,
+        &state.notes_dir(),
+        &file_path,
+        &commit_hash,
+    )
+}
+
+/// Write the note's content from `commit_hash` back to disk, re-index it and
+/// commit the restore. Returns the restored content.
+pub(crate) fn restore_note_from_commit(
+    git_manager: &GitManager,
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    file_path: &str,
+    commit_hash: &str,
+) -> Result<String, String> {
+    let content = git_manager.get_note_at_commit(file_path, commit_hash)?;
+
+    safe_write_file(file_path, &content).map_err(|e| format!("Failed to restore note: {e}"))?;
+
+    let title = Path::new(file_path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+    cache_db.update_note_cache_with_fts(file_path, title, &content, notes_dir)?;
+    update_cached_mtime(cache_db, file_path)?;
+
+    let short_hash = commit_hash.get(..8).unwrap_or(commit_hash);
+    git_manager.commit_changes(Some(&format!("Restore {title} to {short_hash}")))?;
+
+    Ok(content)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub notes_dir: String,
+    pub app_version: String,
+    pub cache_db_path: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_app_info(state: State<'_, AppState>) -> Result<AppInfo, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_app_info"
+    );
+
+    Ok(AppInfo {
+        notes_dir: state.notes_dir(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        cache_db_path: cache_db.db_path(),
+    })
+}
+
 #[tauri::command]
 pub async fn get_app_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
-    let settings_path = Path::new(&state.notes_dir).join(".plainflux");
+    load_app_settings(&state.notes_dir())
+}
+
+/// App-wide configuration kept outside any vault, in the app data directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub notes_dir: Option<String>, // the active vault
+    #[serde(default)]
+    pub vaults: Vec<Vault>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vault {
+    pub name: String,
+    pub path: String,
+    pub cache_db: String, // file name in the app data directory
+}
+
+const APP_CONFIG_FILE: &str = "config.json";
+const DEFAULT_CACHE_DB: &str = "notes_cache.db";
+
+impl AppConfig {
+    /// Register a vault. The first vault keeps the original cache file so an
+    /// existing cache is reused; later ones get a file of their own.
+    pub(crate) fn add_vault(&mut self, name: &str, path: &str) -> Result<Vault, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Vault name cannot be empty".to_string());
+        }
+        if self.vaults.iter().any(|vault| vault.name == name) {
+            return Err(format!("A vault named '{name}' already exists"));
+        }
+        if let Some(existing) = self.find_vault_by_path(path) {
+            return Err(format!(
+                "That directory is already the vault '{}'",
+                existing.name
+            ));
+        }
+
+        let cache_db = if self.vaults.is_empty() {
+            DEFAULT_CACHE_DB.to_string()
+        } else {
+            let slug: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            let mut candidate = format!("notes_cache-{slug}.db");
+            let mut counter = 1;
+            while self.vaults.iter().any(|vault| vault.cache_db == candidate) {
+                candidate = format!("notes_cache-{slug}-{counter}.db");
+                counter += 1;
+            }
+            candidate
+        };
+
+        let vault = Vault {
+            name: name.to_string(),
+            path: path.to_string(),
+            cache_db,
+        };
+        self.vaults.push(vault.clone());
+        Ok(vault)
+    }
+
+    pub(crate) fn find_vault_by_path(&self, path: &str) -> Option<&Vault> {
+        self.vaults
+            .iter()
+            .find(|vault| Path::new(&vault.path) == Path::new(path))
+    }
+
+    /// The vault for `path`, registering it under its folder name if needed
+    pub(crate) fn vault_for_path(&mut self, path: &str) -> Vault {
+        if let Some(vault) = self.find_vault_by_path(path) {
+            return vault.clone();
+        }
+
+        let folder_name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Notes".to_string());
+        let mut name = folder_name.clone();
+        let mut counter = 2;
+        while self.vaults.iter().any(|vault| vault.name == name) {
+            name = format!("{folder_name} {counter}");
+            counter += 1;
+        }
+
+        self.add_vault(&name, path)
+            .expect("vault name and path were checked to be unused")
+    }
+}
+
+pub(crate) fn load_app_config(app_data_dir: &Path) -> AppConfig {
+    match safe_read_file(app_data_dir.join(APP_CONFIG_FILE)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring invalid app config: {e}");
+            AppConfig::default()
+        }),
+        Err(_) => AppConfig::default(),
+    }
+}
+
+pub(crate) fn save_app_config(app_data_dir: &Path, config: &AppConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize app config: {e}"))?;
+    safe_write_file(app_data_dir.join(APP_CONFIG_FILE), &json)
+        .map_err(|e| format!("Failed to save app config: {e}"))
+}
+
+/// Apply the git options from a vault's settings file to `git_manager`
+pub(crate) fn configure_git_manager(git_manager: &GitManager, notes_dir: &str) {
+    match load_app_settings(notes_dir) {
+        Ok(settings) => {
+            git_manager.set_autocommit_minutes(settings.git_autocommit_minutes);
+            git_manager.set_include_attachments(settings.git_include_attachments);
+            if let Err(e) = git_manager.set_author(
+                settings.git_author_name.as_deref(),
+                settings.git_author_email.as_deref(),
+            ) {
+                eprintln!("Warning: Ignoring git author from settings: {e}");
+            }
+        }
+        Err(e) => eprintln!("Warning: Failed to load settings: {e}"),
+    }
+}
+
+/// Rebuild the search index if the vault's settings ask for a different
+/// tokenizer than it was built with
+pub(crate) fn configure_search_tokenizer(cache_db: &CacheDb, notes_dir: &str) {
+    match load_app_settings(notes_dir) {
+        Ok(settings) => {
+            if let Err(e) = cache_db.set_search_tokenizer(settings.search_tokenizer) {
+                eprintln!("Warning: Failed to apply search tokenizer: {e}");
+            }
+        }
+        Err(e) => eprintln!("Warning: Failed to load settings: {e}"),
+    }
+}
+
+/// Reject custom theme colors the UI can't render and clamp the font size
+/// to the supported range
+pub(crate) fn normalize_app_settings(
+    mut settings: AppSettings,
+) -> crate::error::Result<AppSettings> {
+    if let Some(theme) = &settings.custom_theme {
+        let invalid = theme.invalid_colors();
+        if !invalid.is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid custom theme colors: {}",
+                invalid.join(", ")
+            )));
+        }
+    }
+    settings.font_size = settings.font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    Ok(settings)
+}
+
+/// The vault's configured daily notes folder, relative to `notes_dir`.
+/// settings.json may be synced or edited by hand, so an invalid folder falls
+/// back to the default instead of pointing outside the vault.
+pub(crate) fn daily_notes_folder(notes_dir: &str) -> Result<String, String> {
+    let folder = load_app_settings(notes_dir)?.daily_notes_folder;
+    match validate_daily_notes_folder(&folder) {
+        Ok(()) => Ok(folder),
+        Err(_) => Ok(default_daily_notes_folder()),
+    }
+}
+
+/// Reject daily notes folders outside the vault or inside internal folders
+pub(crate) fn validate_daily_notes_folder(folder: &str) -> Result<(), String> {
+    note_manager::validate_relative_folder_path(folder, false)
+        .map_err(|e| format!("Invalid daily notes folder: {e}"))?;
+    if folder != folder.trim() || note_manager::is_in_hidden_folder(Path::new(folder)) {
+        return Err(format!("Invalid daily notes folder: '{folder}'"));
+    }
+    Ok(())
+}
+
+pub(crate) fn load_app_settings(notes_dir: &str) -> Result<AppSettings, String> {
+    let settings_path = Path::new(notes_dir).join(".plainflux");
     let settings_file = settings_path.join("settings.json");
 
     match safe_read_file(&settings_file) {
@@ -1285,22 +4007,267 @@ pub async fn save_app_settings(
     settings: AppSettings,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let settings_path = Path::new(&state.notes_dir).join(".plainflux");
-    let settings_file = settings_path.join("settings.json");
+    let settings = normalize_app_settings(settings)?;
+    validate_daily_notes_folder(&settings.daily_notes_folder)?;
+
+    // Apply git settings first so an invalid author is rejected before saving
+    apply_git_settings(&state, &settings)?;
+    apply_search_tokenizer(&state, settings.search_tokenizer)?;
+    write_app_settings(&state.notes_dir(), &settings)
+}
+
+fn apply_search_tokenizer(state: &AppState, tokenizer: SearchTokenizer) -> Result<(), String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned while applying the search tokenizer"
+    );
+    cache_db.set_search_tokenizer(tokenizer).map(|_| ())
+}
+
+fn apply_git_settings(state: &AppState, settings: &AppSettings) -> Result<(), String> {
+    let git_manager = lock_mutex!(
+        state.git_manager,
+        "Git manager mutex was poisoned while applying git settings"
+    );
+    git_manager.set_author(
+        settings.git_author_name.as_deref(),
+        settings.git_author_email.as_deref(),
+    )?;
+    git_manager.set_autocommit_minutes(settings.git_autocommit_minutes);
+    git_manager.set_include_attachments(settings.git_include_attachments);
+    Ok(())
+}
 
-    // Ensure settings directory exists
+pub(crate) fn write_app_settings(notes_dir: &str, settings: &AppSettings) -> Result<(), String> {
+    let settings_path = Path::new(notes_dir).join(".plainflux");
     ensure_dir_exists(&settings_path)
         .map_err(|e| format!("Failed to create settings directory: {e}"))?;
 
-    // Serialize settings to JSON
-    let settings_json = serde_json::to_string_pretty(&settings)
+    let settings_json = serde_json::to_string_pretty(settings)
         .map_err(|e| format!("Failed to serialize settings: {e}"))?;
-
-    // Save settings to file
-    safe_write_file(&settings_file, &settings_json)
+    safe_write_file(settings_path.join("settings.json"), &settings_json)
         .map_err(|e| format!("Failed to save settings: {e}"))
 }
 
+/// Identifies a settings bundle written by `export_settings`
+const SETTINGS_BUNDLE_FORMAT: &str = "plainflux-settings";
+const SETTINGS_BUNDLE_VERSION: u32 = 1;
+
+/// The vault's configuration in one file, for moving a setup between
+/// machines. Notes are not included.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub format: String,
+    pub version: u32,
+    pub settings: Option<AppSettings>,
+    /// Template contents by path relative to `.plainflux`, e.g.
+    /// `daily_note_template.md` or `templates/Meeting.md`
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+    #[serde(default)]
+    pub saved_searches: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SettingsImportSummary {
+    pub settings_applied: bool,
+    pub templates_imported: usize,
+    pub saved_searches_imported: usize,
+    /// Templates and saved searches that already exist and were kept as they are
+    pub conflicts: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn export_settings(out_path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let bundle = build_settings_bundle(&state.notes_dir())?;
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize settings bundle: {e}"))?;
+    safe_write_file(&out_path, &json).map_err(|e| format!("Failed to export settings: {e}"))
+}
+
+#[tauri::command]
+pub async fn import_settings(
+    in_path: String,
+    state: State<'_, AppState>,
+) -> Result<SettingsImportSummary, String> {
+    let content =
+        safe_read_file(&in_path).map_err(|e| format!("Failed to read settings bundle: {e}"))?;
+    let bundle = parse_settings_bundle(&content)?;
+
+    let notes_dir = state.notes_dir();
+    let summary = apply_settings_bundle(&notes_dir, bundle)?;
+    if summary.settings_applied {
+        let settings = load_app_settings(&notes_dir)?;
+        apply_git_settings(&state, &settings)?;
+        apply_search_tokenizer(&state, settings.search_tokenizer)?;
+    }
+    Ok(summary)
+}
+
+pub(crate) fn build_settings_bundle(notes_dir: &str) -> Result<SettingsBundle, String> {
+    let config_dir = Path::new(notes_dir).join(".plainflux");
+    let settings = if config_dir.join("settings.json").is_file() {
+        Some(load_app_settings(notes_dir)?)
+    } else {
+        None
+    };
+
+    let mut templates = BTreeMap::new();
+    for name in settings_template_files(&config_dir)? {
+        let content = safe_read_file(config_dir.join(&name))
+            .map_err(|e| format!("Failed to read template {name}: {e}"))?;
+        templates.insert(name, content);
+    }
+
+    Ok(SettingsBundle {
+        format: SETTINGS_BUNDLE_FORMAT.to_string(),
+        version: SETTINGS_BUNDLE_VERSION,
+        settings,
+        templates,
+        saved_searches: load_saved_searches(notes_dir)?,
+    })
+}
+
+/// Markdown files directly in `.plainflux` (the daily and periodic note
+/// templates) and in `.plainflux/templates`, relative to `.plainflux`
+fn settings_template_files(config_dir: &Path) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for folder in ["", "templates"] {
+        let dir = config_dir.join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| format!("Failed to read templates: {e}"))?;
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            let is_markdown = path.extension().and_then(|ext| ext.to_str()) == Some("md");
+            let name = path.file_name().and_then(|name| name.to_str());
+            if let (true, true, Some(name)) = (path.is_file(), is_markdown, name) {
+                files.push(if folder.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{folder}/{name}")
+                });
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse and validate a settings bundle before anything is written
+pub(crate) fn parse_settings_bundle(content: &str) -> Result<SettingsBundle, String> {
+    let bundle: SettingsBundle =
+        serde_json::from_str(content).map_err(|e| format!("Invalid settings bundle: {e}"))?;
+    if bundle.format != SETTINGS_BUNDLE_FORMAT {
+        return Err("Invalid settings bundle: not a Plainflux settings export".to_string());
+    }
+    if bundle.version > SETTINGS_BUNDLE_VERSION {
+        return Err(format!(
+            "Settings bundle version {} is newer than this version of Plainflux supports",
+            bundle.version
+        ));
+    }
+
+    for name in bundle.templates.keys() {
+        let (folder, file) = name.rsplit_once('/').unwrap_or(("", name));
+        let file_is_plain = file.ends_with(".md")
+            && file.len() > ".md".len()
+            && !file.contains('\\')
+            && !file.starts_with('.');
+        if !file_is_plain || !matches!(folder, "" | "templates") {
+            return Err(format!("Invalid template in settings bundle: {name}"));
+        }
+    }
+    for name in bundle.saved_searches.keys() {
+        sanitize_saved_search_name(name)?;
+    }
+    Ok(bundle)
+}
+
+/// Write a validated bundle into the vault. Settings replace the current
+/// ones except for the window layout, which belongs to this machine.
+/// Templates and saved searches are merged in; existing ones are kept and
+/// reported as conflicts.
+pub(crate) fn apply_settings_bundle(
+    notes_dir: &str,
+    bundle: SettingsBundle,
+) -> Result<SettingsImportSummary, String> {
+    let mut summary = SettingsImportSummary::default();
+
+    let settings = match bundle.settings {
+        Some(imported) => {
+            let imported = normalize_app_settings(imported)?;
+            validate_daily_notes_folder(&imported.daily_notes_folder)?;
+            // Checked here because import_settings only applies the author
+            // after the bundle has been written
+            crate::git_manager::validate_author(
+                imported.git_author_name.as_deref(),
+                imported.git_author_email.as_deref(),
+            )?;
+            let current = load_app_settings(notes_dir)?;
+            Some(AppSettings {
+                window_width: current.window_width,
+                window_height: current.window_height,
+                window_x: current.window_x,
+                window_y: current.window_y,
+                window_maximized: current.window_maximized,
+                ..imported
+            })
+        }
+        None => None,
+    };
+
+    let mut searches = load_saved_searches(notes_dir)?;
+    let mut searches_changed = false;
+    for (name, query) in bundle.saved_searches {
+        let name = sanitize_saved_search_name(&name)?;
+        let query = query.trim();
+        if query.is_empty() {
+            continue;
+        }
+        if searches
+            .keys()
+            .any(|existing| existing.to_lowercase() == name.to_lowercase())
+        {
+            summary.conflicts.push(format!("Saved search: {name}"));
+            continue;
+        }
+        searches.insert(name, query.to_string());
+        summary.saved_searches_imported += 1;
+        searches_changed = true;
+    }
+
+    let config_dir = Path::new(notes_dir).join(".plainflux");
+    for (name, content) in bundle.templates {
+        let template_path = config_dir.join(&name);
+        if template_path.exists() {
+            summary.conflicts.push(format!("Template: {name}"));
+            continue;
+        }
+        if let Some(parent) = template_path.parent() {
+            ensure_dir_exists(parent)
+                .map_err(|e| format!("Failed to create templates folder: {e}"))?;
+        }
+        safe_write_file(&template_path, &content)
+            .map_err(|e| format!("Failed to import template {name}: {e}"))?;
+        summary.templates_imported += 1;
+    }
+
+    if searches_changed {
+        write_saved_searches(notes_dir, &searches)?;
+    }
+    if let Some(settings) = settings {
+        write_app_settings(notes_dir, &settings)?;
+        summary.settings_applied = true;
+    }
+
+    Ok(summary)
+}
+
 /// Time filter for recent notes query
 #[derive(Debug, Deserialize)]
 pub enum RecentNotesFilter {
@@ -1308,6 +4275,12 @@ pub enum RecentNotesFilter {
     Week,
     Month,
     All,
+    /// Notes modified between two Unix timestamps (seconds, inclusive);
+    /// without `until` the range is open-ended
+    Custom {
+        since: u64,
+        until: Option<u64>,
+    },
 }
 
 #[tauri::command]
@@ -1315,9 +4288,18 @@ pub async fn get_recent_notes(
     state: State<'_, AppState>,
     filter: Option<RecentNotesFilter>,
 ) -> Result<Vec<RecentNote>, String> {
-    let filter = filter.unwrap_or(RecentNotesFilter::Today);
+    list_recent_notes(
+        &state.notes_dir(),
+        filter.unwrap_or(RecentNotesFilter::Today),
+    )
+}
 
+pub(crate) fn list_recent_notes(
+    notes_dir: &str,
+    filter: RecentNotesFilter,
+) -> Result<Vec<RecentNote>, String> {
     let now_local = Local::now();
+    let mut until = None;
 
     // Calculate cutoff timestamp in local time based on filter
     let cutoff_timestamp = match filter {
@@ -1336,16 +4318,29 @@ pub async fn get_recent_notes(
         RecentNotesFilter::Week => (now_local - ChronoDuration::days(7)).timestamp(),
         RecentNotesFilter::Month => (now_local - ChronoDuration::days(30)).timestamp(),
         RecentNotesFilter::All => 0,
+        RecentNotesFilter::Custom {
+            since,
+            until: custom_until,
+        } => {
+            if custom_until.is_some_and(|custom_until| since > custom_until) {
+                return Err("Invalid date range: start is after end".to_string());
+            }
+            until = custom_until;
+            since.min(i64::MAX as u64) as i64
+        }
     };
     let cutoff = cutoff_timestamp.max(0) as u64;
 
     // Get all notes from filesystem
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+    let notes = note_manager::list_notes(notes_dir)?;
 
     // Filter by time and convert to RecentNote format
     let mut recent_notes: Vec<RecentNote> = notes
         .into_iter()
-        .filter(|note| (note.last_modified as u64) >= cutoff)
+        .filter(|note| {
+            let modified = note.last_modified as u64;
+            modified >= cutoff && until.is_none_or(|until| modified <= until)
+        })
         .map(|note| RecentNote {
             path: note.path,
             title: note.title,
@@ -1457,6 +4452,82 @@ pub async fn add_bookmark_manual(
     )
 }
 
+/// Title and description of a web page, to pre-fill `add_bookmark_manual`
+#[tauri::command]
+pub async fn fetch_bookmark_metadata(
+    url: String,
+    state: State<'_, AppState>,
+) -> Result<web::PageMetadata, String> {
+    ensure_network_allowed(&state.notes_dir())?;
+    web::fetch_page_metadata(&url).await
+}
+
+/// Path of the cached icon for a bookmark domain, fetching it on first use
+/// and again once it expires. `None` when the site has no icon, or when
+/// nothing is cached and network requests are disabled.
+#[tauri::command]
+pub async fn get_bookmark_favicon(
+    domain: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let notes_dir = state.notes_dir();
+    let domain = favicons::validate_domain(&domain)?;
+    let settings = load_app_settings(&notes_dir)?;
+
+    let cached = favicons::cached_favicon(&notes_dir, &domain, settings.favicon_cache_days);
+    let cached_icon = |path: &Path| {
+        (!favicons::is_missing_marker(path)).then(|| path.to_string_lossy().to_string())
+    };
+    match &cached {
+        Some((path, expired)) if !expired || !settings.allow_network_requests => {
+            return Ok(cached_icon(path));
+        }
+        None if !settings.allow_network_requests => return Ok(None),
+        _ => {}
+    }
+
+    match favicons::fetch_favicon(&domain).await {
+        Ok(icon) => Ok(favicons::store_favicon(&notes_dir, &domain, icon)?
+            .map(|path| path.to_string_lossy().to_string())),
+        Err(e) => {
+            // Keep showing a stale icon while the site is unreachable
+            eprintln!("Warning: Failed to fetch favicon for {domain}: {e}");
+            Ok(cached.and_then(|(path, _)| cached_icon(&path)))
+        }
+    }
+}
+
+pub(crate) fn ensure_network_allowed(notes_dir: &str) -> Result<(), String> {
+    if load_app_settings(notes_dir)?.allow_network_requests {
+        Ok(())
+    } else {
+        Err("Network requests are disabled in settings".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn toggle_bookmark_favorite(id: i32, state: State<'_, AppState>) -> Result<bool, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during toggle_bookmark_favorite"
+    );
+
+    cache_db.toggle_bookmark_favorite(id)
+}
+
+#[tauri::command]
+pub async fn get_frequent_bookmarks(
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<Bookmark>, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during get_frequent_bookmarks"
+    );
+
+    cache_db.get_frequent_bookmarks(limit)
+}
+
 #[tauri::command]
 pub async fn update_bookmark(
     id: i32,
@@ -1499,7 +4570,11 @@ pub async fn get_all_bookmark_domains(state: State<'_, AppState>) -> Result<Vec<
 }
 
 #[tauri::command]
-pub async fn open_url_external(url: String, window: WebviewWindow) -> Result<(), String> {
+pub async fn open_url_external(
+    url: String,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     use tauri_plugin_opener::OpenerExt;
 
     // Validate URL format (basic check)
@@ -1507,6 +4582,16 @@ pub async fn open_url_external(url: String, window: WebviewWindow) -> Result<(),
         return Err("Invalid URL: must start with http:// or https://".to_string());
     }
 
+    // Visit tracking is best effort; it must never stop the URL opening
+    match state.cache_db.lock() {
+        Ok(cache_db) => {
+            if let Err(e) = cache_db.record_bookmark_visit(&url) {
+                eprintln!("Warning: {e}");
+            }
+        }
+        Err(_) => eprintln!("Warning: Cache database mutex was poisoned during open_url_external"),
+    }
+
     // Open the URL with the default browser
     window
         .opener()
@@ -1669,8 +4754,64 @@ fn is_window_position_visible(
     false
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheOptimizeResult {
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+#[tauri::command]
+pub async fn optimize_cache(state: State<'_, AppState>) -> Result<CacheOptimizeResult, String> {
+    optimize_cache_db(&state)
+}
+
+/// Compact the cache database. Refused while a sync or rebuild is running,
+/// since those let go of the cache lock between notes.
+pub(crate) fn optimize_cache_db(state: &AppState) -> Result<CacheOptimizeResult, String> {
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache DB mutex was poisoned during optimize_cache"
+    );
+    if state.cache_job_running() {
+        return Err("Cannot optimize the cache while it is being synced or rebuilt".to_string());
+    }
+    let (size_before, size_after) = cache_db.optimize()?;
+    Ok(CacheOptimizeResult {
+        size_before,
+        size_after,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheRebuildResult {
+    /// Notes reindexed; fewer than `total` when the rebuild was cancelled
+    pub processed: usize,
+    pub total: usize,
+    pub cancelled: bool,
+}
+
+/// Reindex every note, emitting `cache-rebuild-progress` as it goes. A
+/// cancelled rebuild keeps the notes it got through; the rest are picked up
+/// by the next sync.
+#[tauri::command]
+pub async fn force_rebuild_cache(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CacheRebuildResult, String> {
+    use tauri::Emitter;
+
+    crate::force_rebuild_cache(&state, |done, total| {
+        let progress = crate::CacheRebuildProgress { done, total };
+        if let Err(e) = app.emit("cache-rebuild-progress", progress) {
+            eprintln!("Warning: Failed to emit cache-rebuild-progress event: {e}");
+        }
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Ask a running `force_rebuild_cache` to stop after the current note
 #[tauri::command]
-pub async fn force_rebuild_cache(state: State<'_, AppState>) -> Result<String, String> {
-    crate::force_rebuild_cache(&state).map_err(|e| e.to_string())?;
-    Ok("Cache rebuilt successfully".to_string())
+pub async fn cancel_rebuild(state: State<'_, AppState>) -> Result<(), String> {
+    state.rebuild_cancelled.store(true, Ordering::SeqCst);
+    Ok(())
 }
@@ -7,6 +7,8 @@ pub enum AppError {
     NotFound(String),
     InvalidInput(String),
     LockPoisoned(String),
+    /// The file changed on disk since the caller last read it
+    Conflict(String),
 }
 
 impl fmt::Display for AppError {
@@ -17,6 +19,7 @@ impl fmt::Display for AppError {
             AppError::NotFound(e) => write!(f, "Not found: {e}"),
             AppError::InvalidInput(e) => write!(f, "Invalid input: {e}"),
             AppError::LockPoisoned(e) => write!(f, "Lock poisoned: {e}"),
+            AppError::Conflict(e) => write!(f, "Conflict: {e}"),
         }
     }
 }
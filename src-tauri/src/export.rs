@@ -0,0 +1,493 @@
+use crate::cache::{Bookmark, CacheDb};
+use crate::commands::GraphData;
+use crate::note_manager::{self, read_file_with_encoding};
+use crate::utils::safe_write_file;
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Asset folders copied alongside the exported notes
+const ASSET_FOLDERS: [&str; 2] = ["images", "attachments"];
+
+pub struct RenderedNote {
+    pub html: String,
+    /// Relative image paths referenced by the note, as written in the markdown
+    pub images: Vec<String>,
+}
+
+/// Render a note to a standalone HTML page. `link_href` maps a wikilink
+/// target (without any `#heading` part) to the href it should point at.
+pub fn render_note_html(
+    title: &str,
+    markdown: &str,
+    link_href: impl Fn(&str) -> String,
+) -> RenderedNote {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_WIKILINKS;
+
+    let mut images = Vec::new();
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Start(Tag::Link {
+            link_type: link_type @ LinkType::WikiLink { .. },
+            dest_url,
+            title,
+            id,
+        }) => {
+            let target = dest_url.split('#').next().unwrap_or(&dest_url).trim();
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::from(link_href(target)),
+                title,
+                id,
+            })
+        }
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            if is_local_relative_path(&dest_url) {
+                images.push(dest_url.to_string());
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            })
+        }
+        other => other,
+    });
+
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    );
+
+    RenderedNote { html, images }
+}
+
+/// Export a single note to `out_path`. Wikilinks point at sibling `.html`
+/// files and referenced images are copied next to the output so relative
+/// `images/...` paths keep working.
+pub fn export_note(note_path: &str, out_path: &str) -> Result<String, String> {
+    let content =
+        read_file_with_encoding(note_path).map_err(|e| format!("Failed to read note: {e}"))?;
+    let note_path = Path::new(note_path);
+    let title = note_path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Untitled");
+
+    let rendered = render_note_html(title, &content, |target| {
+        let name = target.strip_suffix(".md").unwrap_or(target);
+        format!("{name}.html")
+    });
+
+    let out_path = Path::new(out_path);
+    safe_write_file(out_path, &rendered.html).map_err(|e| e.to_string())?;
+
+    let note_dir = note_path.parent().unwrap_or(Path::new(""));
+    let out_dir = out_path.parent().unwrap_or(Path::new(""));
+    for image in &rendered.images {
+        let source = note_dir.join(image);
+        if !source.is_file() {
+            continue;
+        }
+        let destination = out_dir.join(image);
+        if destination == source {
+            continue;
+        }
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create image directory: {e}"))?;
+        }
+        std::fs::copy(&source, &destination)
+            .map_err(|e| format!("Failed to copy image '{image}': {e}"))?;
+    }
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Export every note under `notes_dir` into `out_dir` as a static site,
+/// mirroring the folder structure and adding an `index.html`. Returns the
+/// number of notes exported.
+pub fn export_vault(notes_dir: &str, out_dir: &str, cache_db: &CacheDb) -> Result<usize, String> {
+    let notes_root = Path::new(notes_dir);
+    let out_root = Path::new(out_dir);
+
+    // Exporting into the vault would feed the export back into itself
+    let canonical_notes = notes_root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve notes directory: {e}"))?;
+    let canonical_out = out_root
+        .canonicalize()
+        .unwrap_or_else(|_| out_root.to_path_buf());
+    if canonical_out.starts_with(&canonical_notes) || out_root.starts_with(notes_root) {
+        return Err("Export directory must be outside the notes directory".to_string());
+    }
+
+    // list_notes already leaves out .plainflux, .git and images
+    let mut notes = note_manager::list_notes(notes_dir)?;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let relative_html = |note_path: &str| -> PathBuf {
+        Path::new(note_path)
+            .strip_prefix(notes_root)
+            .unwrap_or(Path::new(note_path))
+            .with_extension("html")
+    };
+
+    let mut by_stem: HashMap<String, &str> = HashMap::new();
+    let mut by_relative_path: HashMap<String, &str> = HashMap::new();
+    for note in &notes {
+        by_stem
+            .entry(note.title.to_lowercase())
+            .or_insert(note.path.as_str());
+        let relative = note
+            .relative_path
+            .trim_end_matches(".md")
+            .replace('\\', "/");
+        by_relative_path.insert(relative.to_lowercase(), note.path.as_str());
+    }
+
+    let resolve = |target: &str| -> Option<String> {
+        let name = target.trim_end_matches(".md").to_lowercase();
+        if let Some(path) = by_relative_path.get(&name).or_else(|| by_stem.get(&name)) {
+            return Some(path.to_string());
+        }
+        cache_db.find_note_by_alias(target).ok().flatten()
+    };
+
+    let mut exported = 0;
+    for note in &notes {
+        let content = match read_file_with_encoding(&note.path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Skipping '{}' during export: {e}", note.path);
+                continue;
+            }
+        };
+
+        let html_path = relative_html(&note.path);
+        let rendered = render_note_html(&note.title, &content, |target| match resolve(target) {
+            Some(path) => relative_href(&html_path, &relative_html(&path)),
+            None => "#".to_string(),
+        });
+
+        safe_write_file(out_root.join(&html_path), &rendered.html).map_err(|e| e.to_string())?;
+        exported += 1;
+    }
+
+    let mut list = String::new();
+    for note in &notes {
+        let href = relative_href(Path::new("index.html"), &relative_html(&note.path));
+        list.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            escape_html(&href.replace(' ', "%20")),
+            escape_html(
+                &note
+                    .relative_path
+                    .trim_end_matches(".md")
+                    .replace('\\', "/")
+            )
+        ));
+    }
+    let index = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Notes</title>\n</head>\n<body>\n<h1>Notes</h1>\n<ul>\n{list}</ul>\n</body>\n</html>\n"
+    );
+    safe_write_file(out_root.join("index.html"), &index).map_err(|e| e.to_string())?;
+
+    copy_asset_folders(notes_root, out_root)?;
+
+    Ok(exported)
+}
+
+/// Folders bookmarks are filed under in an exported bookmark file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookmarkGrouping {
+    #[default]
+    Domain,
+    /// The note each bookmark was found in; manual ones go under "Unfiled"
+    Note,
+}
+
+/// Write all bookmarks to `out_path` as a Netscape bookmark file, which
+/// browsers can import. Returns the number of bookmarks written.
+pub fn export_bookmarks(
+    cache_db: &CacheDb,
+    notes_dir: &str,
+    out_path: &str,
+    grouping: BookmarkGrouping,
+) -> Result<usize, String> {
+    let bookmarks = cache_db.get_all_bookmarks()?;
+    let (html, count) = render_bookmarks_html(&bookmarks, notes_dir, grouping);
+    safe_write_file(out_path, &html).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Render bookmarks in the Netscape format, one folder per group. A URL
+/// listed more than once in a folder is written once. Returns the document
+/// and the number of bookmarks in it.
+pub fn render_bookmarks_html(
+    bookmarks: &[Bookmark],
+    notes_dir: &str,
+    grouping: BookmarkGrouping,
+) -> (String, usize) {
+    let mut folders: BTreeMap<String, Vec<&Bookmark>> = BTreeMap::new();
+    for bookmark in bookmarks {
+        let folder = match grouping {
+            BookmarkGrouping::Domain => bookmark.domain.clone(),
+            BookmarkGrouping::Note => match &bookmark.note_path {
+                Some(note_path) => Path::new(note_path)
+                    .strip_prefix(notes_dir)
+                    .unwrap_or(Path::new(note_path))
+                    .with_extension("")
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                None => "Unfiled".to_string(),
+            },
+        };
+        folders.entry(folder).or_default().push(bookmark);
+    }
+
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     It will be read and overwritten.\n     DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n<H1>Bookmarks</H1>\n<DL><p>\n",
+    );
+    let mut count = 0;
+    for (folder, entries) in folders {
+        html.push_str(&format!(
+            "    <DT><H3>{}</H3>\n    <DL><p>\n",
+            escape_html(&folder)
+        ));
+        let mut seen = HashSet::new();
+        for bookmark in entries {
+            if !seen.insert(bookmark.url.as_str()) {
+                continue;
+            }
+            let title = bookmark
+                .title
+                .as_deref()
+                .filter(|title| !title.trim().is_empty())
+                .unwrap_or(&bookmark.url);
+            let mut attributes = format!("HREF=\"{}\"", escape_html(&bookmark.url));
+            if let Ok(created) = chrono::DateTime::parse_from_rfc3339(&bookmark.created_at) {
+                attributes.push_str(&format!(" ADD_DATE=\"{}\"", created.timestamp()));
+            }
+            if let Some(tags) = bookmark.tags.as_deref().filter(|tags| !tags.is_empty()) {
+                attributes.push_str(&format!(" TAGS=\"{}\"", escape_html(tags)));
+            }
+            html.push_str(&format!(
+                "        <DT><A {attributes}>{}</A>\n",
+                escape_html(title)
+            ));
+            if let Some(description) = bookmark.description.as_deref().filter(|d| !d.is_empty()) {
+                html.push_str(&format!("        <DD>{}\n", escape_html(description)));
+            }
+            count += 1;
+        }
+        html.push_str("    </DL><p>\n");
+    }
+    html.push_str("</DL><p>\n");
+
+    (html, count)
+}
+
+/// File formats the note graph can be exported to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// Graphviz
+    Dot,
+    /// Gephi, yEd, Cytoscape and most graph libraries
+    Graphml,
+}
+
+/// Write `graph` to `out_path`. Nodes are identified by their path relative
+/// to `notes_dir`, labelled with the note title and carry its tags. Returns
+/// the number of nodes written.
+pub fn export_graph(
+    graph: &GraphData,
+    notes_dir: &str,
+    out_path: &str,
+    format: GraphFormat,
+) -> Result<usize, String> {
+    let document = match format {
+        GraphFormat::Dot => render_graph_dot(graph, notes_dir),
+        GraphFormat::Graphml => render_graph_graphml(graph, notes_dir),
+    };
+    safe_write_file(out_path, &document).map_err(|e| e.to_string())?;
+    Ok(graph.nodes.len())
+}
+
+pub fn render_graph_dot(graph: &GraphData, notes_dir: &str) -> String {
+    let quote = |text: &str| {
+        let mut quoted = String::with_capacity(text.len() + 2);
+        quoted.push('"');
+        for c in text.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                c if c.is_control() => {}
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    };
+
+    let mut dot = String::from("digraph notes {\n");
+    for node in &graph.nodes {
+        dot.push_str(&format!(
+            "  {} [label={}, tags={}];\n",
+            quote(&graph_node_id(&node.id, notes_dir)),
+            quote(&node.label),
+            quote(&node.tags.join(";"))
+        ));
+    }
+    for edge in &graph.edges {
+        dot.push_str(&format!(
+            "  {} -> {};\n",
+            quote(&graph_node_id(&edge.from, notes_dir)),
+            quote(&graph_node_id(&edge.to, notes_dir))
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn render_graph_graphml(graph: &GraphData, notes_dir: &str) -> String {
+    // Characters XML 1.0 can't hold at all are dropped rather than escaped
+    let escape = |text: &str| {
+        let text: String = text
+            .chars()
+            .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+            .collect();
+        escape_html(&text).replace('\'', "&apos;")
+    };
+
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+        "  <graph id=\"notes\" edgedefault=\"directed\">\n",
+    ));
+    for node in &graph.nodes {
+        xml.push_str(&format!(
+            "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"tags\">{}</data>\n    </node>\n",
+            escape(&graph_node_id(&node.id, notes_dir)),
+            escape(&node.label),
+            escape(&node.tags.join(";"))
+        ));
+    }
+    for edge in &graph.edges {
+        xml.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\"/>\n",
+            escape(&graph_node_id(&edge.from, notes_dir)),
+            escape(&graph_node_id(&edge.to, notes_dir))
+        ));
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// Vault-relative path with `/` separators, so exports don't leak the
+/// location of the vault
+fn graph_node_id(note_path: &str, notes_dir: &str) -> String {
+    Path::new(note_path)
+        .strip_prefix(notes_dir)
+        .unwrap_or(Path::new(note_path))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Copy every file inside an `images` or `attachments` folder, keeping its
+/// path relative to the notes directory
+fn copy_asset_folders(notes_root: &Path, out_root: &Path) -> Result<(), String> {
+    for entry in WalkDir::new(notes_root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = match entry.path().strip_prefix(notes_root) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+
+        let mut in_asset_folder = false;
+        let mut in_internal_folder = false;
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                if let Component::Normal(name) = component {
+                    let name = name.to_string_lossy();
+                    in_asset_folder |= ASSET_FOLDERS.contains(&name.as_ref());
+                    in_internal_folder |= name == ".plainflux" || name == ".git";
+                }
+            }
+        }
+        if !in_asset_folder || in_internal_folder {
+            continue;
+        }
+
+        let destination = out_root.join(relative);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create asset directory: {e}"))?;
+        }
+        std::fs::copy(entry.path(), &destination)
+            .map_err(|e| format!("Failed to copy '{}': {e}", relative.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Href from the page at `from` to the page at `to`, both relative to the
+/// export root
+fn relative_href(from: &Path, to: &Path) -> String {
+    let depth = from
+        .parent()
+        .map(|parent| parent.components().count())
+        .unwrap_or(0);
+    let target = to
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{}{}", "../".repeat(depth), target)
+}
+
+/// True for paths like `images/foo.png`; false for URLs, absolute paths and
+/// anything that climbs out of the note's folder
+fn is_local_relative_path(path: &str) -> bool {
+    if path.is_empty() || path.contains(':') {
+        return false;
+    }
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
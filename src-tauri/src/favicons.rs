@@ -0,0 +1,160 @@
+use crate::utils::ensure_dir_exists;
+use crate::web;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+
+/// Site icons cached per domain, e.g. `rust-lang.org.png`
+const FAVICON_DIR: &str = ".plainflux/favicons";
+/// Marks a domain without an icon so it isn't refetched on every request
+const MISSING_EXTENSION: &str = "missing";
+const MAX_FAVICON_BYTES: usize = 256 * 1024;
+
+fn favicon_dir(notes_dir: &str) -> PathBuf {
+    Path::new(notes_dir).join(FAVICON_DIR)
+}
+
+/// Accept only plain host names so a domain can't escape the cache folder
+pub fn validate_domain(domain: &str) -> Result<String, String> {
+    let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    let valid = !domain.is_empty()
+        && domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid {
+        Ok(domain)
+    } else {
+        Err(format!("Invalid domain: {domain}"))
+    }
+}
+
+/// The cached file for `domain` (an icon or a missing marker) and whether
+/// it is older than `max_age_days`
+pub fn cached_favicon(notes_dir: &str, domain: &str, max_age_days: u32) -> Option<(PathBuf, bool)> {
+    let prefix = format!("{domain}.");
+    let path = fs::read_dir(favicon_dir(notes_dir))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&prefix))
+                .is_some_and(|extension| !extension.contains('.'))
+        })?;
+
+    let max_age = Duration::from_secs(u64::from(max_age_days) * 24 * 60 * 60);
+    let expired = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_none_or(|age| age > max_age);
+    Some((path, expired))
+}
+
+/// Replace whatever is cached for `domain` with `icon` (bytes and file
+/// extension), or with a missing marker when there is no icon
+pub fn store_favicon(
+    notes_dir: &str,
+    domain: &str,
+    icon: Option<(Vec<u8>, &str)>,
+) -> Result<Option<PathBuf>, String> {
+    let dir = favicon_dir(notes_dir);
+    ensure_dir_exists(&dir).map_err(|e| format!("Failed to create favicon folder: {e}"))?;
+    if let Some((old, _)) = cached_favicon(notes_dir, domain, u32::MAX) {
+        fs::remove_file(old).map_err(|e| format!("Failed to replace cached favicon: {e}"))?;
+    }
+
+    let (bytes, extension) = icon.unwrap_or((Vec::new(), MISSING_EXTENSION));
+    let path = dir.join(format!("{domain}.{extension}"));
+    fs::write(&path, bytes).map_err(|e| format!("Failed to cache favicon: {e}"))?;
+    Ok((extension != MISSING_EXTENSION).then_some(path))
+}
+
+pub fn is_missing_marker(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == MISSING_EXTENSION)
+}
+
+/// Download the icon for `domain`: the one the home page links to, or
+/// `/favicon.ico`. Returns `None` when the site has no usable icon, and an
+/// error when the site couldn't be reached at all.
+pub async fn fetch_favicon(domain: &str) -> Result<Option<(Vec<u8>, &'static str)>, String> {
+    let home = web::validate_web_url(&format!("https://{domain}/"))?;
+
+    let mut candidates = Vec::new();
+    let home_page = web::fetch_limited(&home, MAX_FAVICON_BYTES).await;
+    if let Ok((_, body)) = &home_page {
+        if let Some(href) = find_icon_link(&String::from_utf8_lossy(body)) {
+            if let Ok(url) = home.join(&href) {
+                candidates.push(url);
+            }
+        }
+    }
+    candidates.push(home.join("/favicon.ico").map_err(|e| e.to_string())?);
+
+    for url in candidates {
+        if !matches!(url.scheme(), "http" | "https") {
+            continue;
+        }
+        let (content_type, bytes) = match web::fetch_limited(&url, MAX_FAVICON_BYTES).await {
+            Ok(response) => response,
+            // Neither the home page nor the icon answered: don't cache a miss
+            Err(e) if home_page.is_err() => return Err(e),
+            Err(_) => continue,
+        };
+        if let Some(extension) = image_extension(content_type.as_deref(), &url) {
+            if !bytes.is_empty() {
+                return Ok(Some((bytes, extension)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// `href` of the first `<link rel="icon">` (or `shortcut icon`) in a page
+pub fn find_icon_link(html: &str) -> Option<String> {
+    static LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?is)<link\s[^>]*>").unwrap());
+    static REL_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"(?is)\brel\s*=\s*["']?([^"'>]*)"#).unwrap());
+    static HREF_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"(?is)\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+    });
+
+    LINK_REGEX.find_iter(html).find_map(|tag| {
+        let tag = tag.as_str();
+        let rel = REL_REGEX.captures(tag)?[1].to_ascii_lowercase();
+        if !rel.split_whitespace().any(|part| part == "icon") {
+            return None;
+        }
+        let caps = HREF_REGEX.captures(tag)?;
+        let href = caps.get(1).or(caps.get(2)).or(caps.get(3))?.as_str().trim();
+        (!href.is_empty()).then(|| href.to_string())
+    })
+}
+
+/// File extension for an icon response, or `None` if it isn't an image
+/// (servers often answer a missing favicon with an HTML page)
+fn image_extension(content_type: Option<&str>, url: &url::Url) -> Option<&'static str> {
+    let content_type = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|content_type| content_type.trim().to_ascii_lowercase());
+    match content_type.as_deref() {
+        Some("image/png") => Some("png"),
+        Some("image/x-icon" | "image/vnd.microsoft.icon") => Some("ico"),
+        Some("image/svg+xml") => Some("svg"),
+        Some("image/gif") => Some("gif"),
+        Some("image/jpeg") => Some("jpg"),
+        Some("image/webp") => Some("webp"),
+        // Some servers send icons untyped
+        None | Some("application/octet-stream") if url.path().ends_with(".ico") => Some("ico"),
+        _ => None,
+    }
+}
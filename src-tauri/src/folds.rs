@@ -0,0 +1,112 @@
+use crate::error::AppError;
+use crate::utils::{safe_read_file, safe_write_file};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Collapsed heading lines per note, keyed by vault-relative path
+const FOLDS_FILE: &str = ".plainflux/folds.json";
+
+/// Fold state is a UI nicety; don't let a huge list bloat the file
+const MAX_FOLDED_LINES: usize = 1000;
+
+type FoldStates = BTreeMap<String, Vec<usize>>;
+
+fn folds_file(notes_dir: &str) -> PathBuf {
+    Path::new(notes_dir).join(FOLDS_FILE)
+}
+
+/// Key for `path`: relative to the vault with `/` separators, so the state
+/// survives the vault itself moving
+fn fold_key(path: &str, notes_dir: &str) -> Result<String, String> {
+    let relative = Path::new(path)
+        .strip_prefix(notes_dir)
+        .map_err(|_| format!("'{path}' is outside the notes directory"))?;
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn read_fold_states(notes_dir: &str) -> Result<FoldStates, String> {
+    match safe_read_file(folds_file(notes_dir)) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse fold state: {e}"))
+        }
+        Err(AppError::NotFound(_)) => Ok(FoldStates::new()),
+        Err(e) => Err(format!("Failed to read fold state: {e}")),
+    }
+}
+
+fn write_fold_states(notes_dir: &str, states: &FoldStates) -> Result<(), String> {
+    let json = serde_json::to_string(states)
+        .map_err(|e| format!("Failed to serialize fold state: {e}"))?;
+    safe_write_file(folds_file(notes_dir), &json)
+        .map_err(|e| format!("Failed to save fold state: {e}"))
+}
+
+/// Load the fold states, dropping entries for notes that no longer exist
+fn load_fold_states(notes_dir: &str) -> Result<FoldStates, String> {
+    let mut states = read_fold_states(notes_dir)?;
+    let before = states.len();
+    states.retain(|key, _| Path::new(notes_dir).join(key).is_file());
+    if states.len() != before {
+        write_fold_states(notes_dir, &states)?;
+    }
+    Ok(states)
+}
+
+/// Collapsed lines saved for the note at `path`; empty if none
+pub fn get_fold_state(path: &str, notes_dir: &str) -> Result<Vec<usize>, String> {
+    let key = fold_key(path, notes_dir)?;
+    Ok(load_fold_states(notes_dir)?
+        .remove(&key)
+        .unwrap_or_default())
+}
+
+/// Remember which lines of `path` are collapsed. An empty list forgets the
+/// note entirely.
+pub fn save_fold_state(
+    path: &str,
+    collapsed_lines: Vec<usize>,
+    notes_dir: &str,
+) -> Result<(), String> {
+    let key = fold_key(path, notes_dir)?;
+    let mut lines = collapsed_lines;
+    lines.sort_unstable();
+    lines.dedup();
+    lines.truncate(MAX_FOLDED_LINES);
+
+    let mut states = load_fold_states(notes_dir)?;
+    if lines.is_empty() {
+        if states.remove(&key).is_none() {
+            return Ok(());
+        }
+    } else {
+        states.insert(key, lines);
+    }
+    write_fold_states(notes_dir, &states)
+}
+
+/// Carry fold state over after a note or folder moves from `old_path` to
+/// `new_path`. For a folder, every note below it is re-keyed.
+pub fn move_fold_state(old_path: &str, new_path: &str, notes_dir: &str) -> Result<(), String> {
+    let old_key = fold_key(old_path, notes_dir)?;
+    let new_key = fold_key(new_path, notes_dir)?;
+    let mut states = read_fold_states(notes_dir)?;
+
+    let moved: Vec<String> = states
+        .keys()
+        .filter(|key| **key == old_key || key.starts_with(&format!("{old_key}/")))
+        .cloned()
+        .collect();
+    if moved.is_empty() {
+        return Ok(());
+    }
+    for key in moved {
+        if let Some(lines) = states.remove(&key) {
+            states.insert(format!("{new_key}{}", &key[old_key.len()..]), lines);
+        }
+    }
+    write_fold_states(notes_dir, &states)
+}
@@ -1,8 +1,11 @@
+use crate::commands::AppState;
 use chrono::Local;
-use git2::{IndexAddOption, Repository, Signature};
-use std::path::Path;
+use git2::{DiffFormat, DiffOptions, IndexAddOption, Repository, Signature, Status, StatusOptions};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex as StdMutex};
 use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::time::sleep;
 
 pub struct GitManager {
@@ -10,8 +13,51 @@ pub struct GitManager {
     notes_dir: String,
     last_change: Arc<StdMutex<Option<Instant>>>,
     commit_task_running: Arc<StdMutex<bool>>,
+    // None when auto-commit is disabled
+    commit_delay: Arc<StdMutex<Option<Duration>>>,
+    commit_settings: Arc<StdMutex<CommitSettings>>,
+    blame_cache: StdMutex<BlameCache>,
 }
 
+/// User preferences applied to every commit, manual or automatic
+#[derive(Debug, Clone, Default)]
+struct CommitSettings {
+    include_attachments: bool,
+    author_name: Option<String>,
+    author_email: Option<String>,
+}
+
+/// Blame of committed files, most recently used last. Entries are keyed by
+/// `(file_path, HEAD commit id)`, so a new commit invalidates them all.
+#[derive(Default)]
+struct BlameCache {
+    entries: VecDeque<((String, String), Vec<GitBlameInfo>)>,
+    misses: usize,
+}
+
+/// Number of files whose blame is kept in memory
+const BLAME_CACHE_CAPACITY: usize = 32;
+
+/// Auto-commit delay used until settings say otherwise
+const DEFAULT_COMMIT_DELAY: Duration = Duration::from_secs(5 * 60); // 5 minutes
+
+/// How often the debounce task checks whether edits have settled
+const AUTO_COMMIT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Files that are always committed
+const NOTE_PATTERNS: [&str; 1] = ["*.md"];
+
+/// Image and attachment folders, at the root or next to a note in any folder
+const ATTACHMENT_PATTERNS: [&str; 4] =
+    ["images/*", "*/images/*", "attachments/*", "*/attachments/*"];
+
+/// Identity used for commits unless the user configured their own
+const DEFAULT_AUTHOR_NAME: &str = "PlainFlux Auto-commit";
+const DEFAULT_AUTHOR_EMAIL: &str = "auto@plainflux.local";
+
+/// Entries written to a new repository's .gitignore
+const GITIGNORE_ENTRIES: [&str; 2] = ["notes_cache.db", ".plainflux/"];
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct GitBlameInfo {
     pub line_number: usize,
@@ -21,6 +67,22 @@ pub struct GitBlameInfo {
     pub summary: String,
 }
 
+/// Payload of the `git-auto-commit` event emitted after each auto-commit attempt
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AutoCommitEvent {
+    pub success: bool,
+    pub message: String,
+    pub timestamp: i64, // seconds since the epoch
+}
+
+/// Uncommitted note changes relative to HEAD, as repository-relative paths
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct GitStatus {
+    pub modified: Vec<String>,
+    pub added: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
 impl GitManager {
     pub fn new(notes_dir: &str) -> Self {
         let repo = Repository::discover(notes_dir).ok();
@@ -29,6 +91,46 @@ impl GitManager {
             notes_dir: notes_dir.to_string(),
             last_change: Arc::new(StdMutex::new(None)),
             commit_task_running: Arc::new(StdMutex::new(false)),
+            commit_delay: Arc::new(StdMutex::new(Some(DEFAULT_COMMIT_DELAY))),
+            commit_settings: Arc::new(StdMutex::new(CommitSettings::default())),
+            blame_cache: StdMutex::new(BlameCache::default()),
+        }
+    }
+
+    /// Whether commits also stage `images/` and `attachments/` folders
+    pub fn set_include_attachments(&self, include: bool) {
+        if let Ok(mut commit_settings) = self.commit_settings.lock() {
+            commit_settings.include_attachments = include;
+        }
+    }
+
+    /// Commit as the given author; blank values fall back to the
+    /// PlainFlux defaults
+    pub fn set_author(&self, name: Option<&str>, email: Option<&str>) -> Result<(), String> {
+        validate_author(name, email)?;
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+        let email = email.map(str::trim).filter(|email| !email.is_empty());
+
+        if let Ok(mut commit_settings) = self.commit_settings.lock() {
+            commit_settings.author_name = name.map(str::to_string);
+            commit_settings.author_email = email.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    /// Set how long edits must settle before auto-committing.
+    /// `None` or `Some(0)` disables auto-commit.
+    pub fn set_autocommit_minutes(&self, minutes: Option<u64>) {
+        let delay = minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes.saturating_mul(60)));
+        self.set_commit_delay(delay);
+    }
+
+    /// Set the auto-commit delay directly; `None` disables auto-commit
+    pub(crate) fn set_commit_delay(&self, delay: Option<Duration>) {
+        if let Ok(mut commit_delay) = self.commit_delay.lock() {
+            *commit_delay = delay;
         }
     }
 
@@ -44,12 +146,39 @@ impl GitManager {
         match Repository::init(&self.notes_dir) {
             Ok(repo) => {
                 self.repo = Some(repo);
-                Ok(())
+                self.write_gitignore()
             }
             Err(e) => Err(format!("Failed to initialize git repository: {}", e)),
         }
     }
 
+    /// Make sure the cache database and app settings never get committed,
+    /// keeping whatever the user already has in .gitignore
+    fn write_gitignore(&self) -> Result<(), String> {
+        let gitignore_path = Path::new(&self.notes_dir).join(".gitignore");
+        let mut content = std::fs::read_to_string(&gitignore_path).unwrap_or_default();
+
+        let missing: Vec<&str> = GITIGNORE_ENTRIES
+            .iter()
+            .copied()
+            .filter(|entry| !content.lines().any(|line| line.trim() == *entry))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        for entry in missing {
+            content.push_str(entry);
+            content.push('\n');
+        }
+
+        std::fs::write(&gitignore_path, content)
+            .map_err(|e| format!("Failed to write .gitignore: {}", e))
+    }
+
     pub fn commit_changes(&self, message: Option<&str>) -> Result<(), String> {
         let repo = match &self.repo {
             Some(repo) => repo,
@@ -61,9 +190,19 @@ impl GitManager {
             .index()
             .map_err(|e| format!("Failed to get index: {}", e))?;
 
-        // Add all .md files
+        let commit_settings = self
+            .commit_settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default();
+
+        // Add all .md files, plus images and attachments when enabled
+        let mut patterns = NOTE_PATTERNS.to_vec();
+        if commit_settings.include_attachments {
+            patterns.extend(ATTACHMENT_PATTERNS);
+        }
         index
-            .add_all(["*.md"].iter(), IndexAddOption::DEFAULT, None)
+            .add_all(patterns.iter(), IndexAddOption::DEFAULT, None)
             .map_err(|e| format!("Failed to add files: {}", e))?;
 
         // Write the index
@@ -103,8 +242,17 @@ impl GitManager {
         }
 
         // Create signature
-        let signature = Signature::now("PlainFlux Auto-commit", "auto@plainflux.local")
-            .map_err(|e| format!("Failed to create signature: {}", e))?;
+        let signature = Signature::now(
+            commit_settings
+                .author_name
+                .as_deref()
+                .unwrap_or(DEFAULT_AUTHOR_NAME),
+            commit_settings
+                .author_email
+                .as_deref()
+                .unwrap_or(DEFAULT_AUTHOR_EMAIL),
+        )
+        .map_err(|e| format!("Failed to create signature: {}", e))?;
 
         // Create commit message
         let default_message = format!("Auto-commit: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
@@ -126,12 +274,67 @@ impl GitManager {
         Ok(())
     }
 
+    /// Per-line blame for the lines of `file_path` that exist on disk.
+    /// Served from the blame cache until HEAD moves.
     pub fn get_blame_info(&self, file_path: &str) -> Result<Vec<GitBlameInfo>, String> {
         let repo = match &self.repo {
             Some(repo) => repo,
             None => return Err("No git repository available".to_string()),
         };
 
+        // Only lines still present in the working file are reported
+        let file_line_count = if let Ok(content) = std::fs::read_to_string(file_path) {
+            content.lines().count()
+        } else {
+            0
+        };
+        let visible = |blame: &[GitBlameInfo]| {
+            blame
+                .iter()
+                .take_while(|info| info.line_number <= file_line_count)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .map(|oid| oid.to_string());
+        let Some(head) = head else {
+            return Ok(visible(&Self::compute_blame(repo, file_path)?));
+        };
+        let key = (file_path.to_string(), head);
+
+        let mut cache = lock_mutex!(self.blame_cache);
+        if let Some(index) = cache.entries.iter().position(|(k, _)| *k == key) {
+            // Move to the back as the most recently used entry
+            if let Some(entry) = cache.entries.remove(index) {
+                let blame = visible(&entry.1);
+                cache.entries.push_back(entry);
+                return Ok(blame);
+            }
+        }
+
+        cache.misses += 1;
+        let blame = Self::compute_blame(repo, file_path)?;
+        let result = visible(&blame);
+        cache.entries.retain(|((path, _), _)| *path != key.0);
+        if cache.entries.len() >= BLAME_CACHE_CAPACITY {
+            cache.entries.pop_front();
+        }
+        cache.entries.push_back((key, blame));
+        Ok(result)
+    }
+
+    /// Number of blame lookups that missed the cache
+    #[cfg(test)]
+    pub(crate) fn blame_cache_misses(&self) -> usize {
+        lock_mutex!(self.blame_cache).misses
+    }
+
+    /// Blame every committed line of `file_path`
+    fn compute_blame(repo: &Repository, file_path: &str) -> Result<Vec<GitBlameInfo>, String> {
         // Convert absolute path to relative path from repo root
         let repo_path = repo
             .workdir()
@@ -148,37 +351,179 @@ impl GitManager {
 
         let mut blame_info = Vec::new();
 
-        // Get the actual number of lines in the file
-        let file_line_count = if let Ok(content) = std::fs::read_to_string(file_path) {
-            content.lines().count()
-        } else {
-            0
+        // Iterate through each committed line to get blame info
+        let mut line_num = 1;
+        while let Some(hunk) = blame.get_line(line_num) {
+            let commit_oid = hunk.final_commit_id();
+            if let Ok(commit) = repo.find_commit(commit_oid) {
+                let author = commit.author();
+                let timestamp = author.when().seconds();
+                let summary = commit.summary().unwrap_or("").to_string();
+
+                blame_info.push(GitBlameInfo {
+                    line_number: line_num,
+                    commit_hash: commit_oid.to_string()[..8].to_string(), // Short hash
+                    author: author.name().unwrap_or("Unknown").to_string(),
+                    timestamp,
+                    summary,
+                });
+            }
+            line_num += 1;
+        }
+
+        Ok(blame_info)
+    }
+
+    /// Notes changed since the last commit, including untracked new notes.
+    /// Empty when there is no repository.
+    pub fn get_git_status(&self) -> Result<GitStatus, String> {
+        let repo = match &self.repo {
+            Some(repo) => repo,
+            None => return Ok(GitStatus::default()),
         };
 
-        // Iterate through each line of the file to get blame info
-        for line_num in 1..=file_line_count {
-            if let Some(hunk) = blame.get_line(line_num) {
-                let commit_oid = hunk.final_commit_id();
-                if let Ok(commit) = repo.find_commit(commit_oid) {
-                    let author = commit.author();
-                    let timestamp = author.when().seconds();
-                    let summary = commit.summary().unwrap_or("").to_string();
-
-                    blame_info.push(GitBlameInfo {
-                        line_number: line_num,
-                        commit_hash: commit_oid.to_string()[..8].to_string(), // Short hash
-                        author: author.name().unwrap_or("Unknown").to_string(),
-                        timestamp,
-                        summary,
-                    });
-                }
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| format!("Failed to get git status: {}", e))?;
+
+        let mut status = GitStatus::default();
+        for entry in statuses.iter() {
+            let path = match entry.path() {
+                Some(path) if path.ends_with(".md") => path.to_string(),
+                _ => continue,
+            };
+
+            let flags = entry.status();
+            if flags.intersects(Status::INDEX_NEW | Status::WT_NEW) {
+                status.added.push(path);
+            } else if flags.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+                status.deleted.push(path);
+            } else if flags.intersects(
+                Status::INDEX_MODIFIED
+                    | Status::WT_MODIFIED
+                    | Status::INDEX_RENAMED
+                    | Status::WT_RENAMED
+                    | Status::INDEX_TYPECHANGE
+                    | Status::WT_TYPECHANGE,
+            ) {
+                status.modified.push(path);
             }
         }
 
-        Ok(blame_info)
+        Ok(status)
+    }
+
+    /// Content of `file_path` as it was in `commit`
+    pub fn get_note_at_commit(&self, file_path: &str, commit: &str) -> Result<String, String> {
+        let repo = match &self.repo {
+            Some(repo) => repo,
+            None => return Err("No git repository available".to_string()),
+        };
+
+        let relative_path = relative_to_workdir(repo, file_path)?;
+        let tree = repo
+            .revparse_single(commit)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| format!("Failed to find commit '{}': {}", commit, e))?;
+        let entry = tree
+            .get_path(&relative_path)
+            .map_err(|_| format!("Note does not exist in commit '{}'", commit))?;
+        let blob = entry
+            .to_object(repo)
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|e| format!("Failed to read note from commit: {}", e))?;
+
+        String::from_utf8(blob.content().to_vec())
+            .map_err(|_| "Note content at that commit is not valid UTF-8".to_string())
+    }
+
+    /// Unified diff of `file_path` between two commits. With no `new_commit`
+    /// the old commit is compared against the file on disk. A file that only
+    /// exists on one side shows up as fully added or deleted.
+    pub fn get_note_diff(
+        &self,
+        file_path: &str,
+        old_commit: &str,
+        new_commit: Option<&str>,
+    ) -> Result<String, String> {
+        let repo = match &self.repo {
+            Some(repo) => repo,
+            None => return Err("No git repository available".to_string()),
+        };
+
+        let relative_path = relative_to_workdir(repo, file_path)?;
+
+        let tree_for = |rev: &str| {
+            repo.revparse_single(rev)
+                .and_then(|object| object.peel_to_tree())
+                .map_err(|e| format!("Failed to find commit '{}': {}", rev, e))
+        };
+        let old_tree = tree_for(old_commit)?;
+
+        let mut options = DiffOptions::new();
+        options
+            .pathspec(&relative_path)
+            .disable_pathspec_match(true)
+            .include_untracked(true)
+            .show_untracked_content(true);
+
+        let diff = match new_commit {
+            Some(new_commit) => {
+                let new_tree = tree_for(new_commit)?;
+                repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut options))
+            }
+            None => repo.diff_tree_to_workdir(Some(&old_tree), Some(&mut options)),
+        }
+        .map_err(|e| format!("Failed to diff note: {}", e))?;
+
+        let mut patch = String::new();
+        diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })
+        .map_err(|e| format!("Failed to format diff: {}", e))?;
+
+        Ok(patch)
     }
 
-    pub fn schedule_auto_commit(&self) {
+    /// Commit pending changes once the configured delay passes without
+    /// further edits. `app` is used to reach the live git manager and to
+    /// report the outcome as a `git-auto-commit` event.
+    pub fn schedule_auto_commit(&self, app: AppHandle) {
+        let notes_dir = self.notes_dir.clone();
+        self.schedule_commit(AUTO_COMMIT_CHECK_INTERVAL, move || {
+            Self::auto_commit_and_notify(&app, &notes_dir)
+        });
+    }
+
+    /// Record a change and run `commit` once no further change has been
+    /// recorded for the commit delay. Changes made while a commit is pending
+    /// share that single commit.
+    ///
+    /// The task never holds a `Repository` of its own: `git2::Repository` is
+    /// not `Sync`, so `commit` is expected to go through the `GitManager` that
+    /// already owns the discovered repo, behind its mutex.
+    pub(crate) fn schedule_commit<F>(&self, check_interval: Duration, commit: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let enabled = self
+            .commit_delay
+            .lock()
+            .map(|delay| delay.is_some())
+            .unwrap_or(false);
+        if !enabled {
+            return;
+        }
+
         // Update the last change timestamp
         if let Ok(mut last_change) = self.last_change.lock() {
             *last_change = Some(Instant::now());
@@ -187,7 +532,7 @@ impl GitManager {
         // Start the debounced commit task if it's not already running
         let task_running = self.commit_task_running.clone();
         let last_change = self.last_change.clone();
-        let notes_dir = self.notes_dir.clone();
+        let commit_delay = self.commit_delay.clone();
 
         let should_start_task = {
             if let Ok(mut running) = task_running.lock() {
@@ -205,25 +550,44 @@ impl GitManager {
         if should_start_task {
             // Spawn the debounced commit task
             tokio::spawn(async move {
-                Self::debounced_commit_task(last_change, notes_dir, task_running).await;
+                Self::debounced_commit_task(
+                    last_change,
+                    commit_delay,
+                    task_running,
+                    check_interval,
+                    commit,
+                )
+                .await;
             });
         }
     }
 
-    async fn debounced_commit_task(
+    async fn debounced_commit_task<F>(
         last_change: Arc<StdMutex<Option<Instant>>>,
-        notes_dir: String,
+        commit_delay: Arc<StdMutex<Option<Duration>>>,
         task_running: Arc<StdMutex<bool>>,
-    ) {
-        const COMMIT_DELAY: Duration = Duration::from_secs(5 * 60); // 5 minutes
-
+        check_interval: Duration,
+        commit: F,
+    ) where
+        F: FnOnce() + Send + 'static,
+    {
         loop {
-            sleep(Duration::from_secs(30)).await; // Check every 30 seconds
+            sleep(check_interval).await;
+
+            // Re-read the delay so settings changes apply to a pending commit
+            let delay = commit_delay.lock().ok().and_then(|delay| *delay);
+            let Some(delay) = delay else {
+                // Auto-commit was disabled while waiting
+                if let Ok(mut running) = task_running.lock() {
+                    *running = false;
+                }
+                break;
+            };
 
             let should_commit = {
                 if let Ok(last_change_guard) = last_change.lock() {
                     if let Some(last_time) = *last_change_guard {
-                        last_time.elapsed() >= COMMIT_DELAY
+                        last_time.elapsed() >= delay
                     } else {
                         false
                     }
@@ -238,18 +602,7 @@ impl GitManager {
                     *last_change_guard = None;
                 }
 
-                // Perform the commit
-                let temp_manager = GitManager::new(&notes_dir);
-                if temp_manager.is_git_repo() {
-                    if let Err(e) = temp_manager.commit_changes(None) {
-                        eprintln!("Auto-commit failed: {}", e);
-                    } else {
-                        println!(
-                            "Auto-commit completed at {}",
-                            Local::now().format("%Y-%m-%d %H:%M:%S")
-                        );
-                    }
-                }
+                commit();
 
                 // Mark task as not running and exit
                 if let Ok(mut running) = task_running.lock() {
@@ -259,4 +612,90 @@ impl GitManager {
             }
         }
     }
+
+    /// Auto-commit through the app's git manager and report the outcome as a
+    /// `git-auto-commit` event
+    fn auto_commit_and_notify(app: &AppHandle, notes_dir: &str) {
+        let Some(result) = Self::auto_commit(app, notes_dir) else {
+            return;
+        };
+        let message = match &result {
+            Ok(()) => format!(
+                "Auto-commit completed at {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            ),
+            Err(e) => format!("Auto-commit failed: {}", e),
+        };
+        if result.is_ok() {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+
+        let event = AutoCommitEvent {
+            success: result.is_ok(),
+            message,
+            timestamp: Local::now().timestamp(),
+        };
+        if let Err(e) = app.emit("git-auto-commit", &event) {
+            eprintln!("Warning: Failed to emit git-auto-commit event: {}", e);
+        }
+    }
+
+    /// Commit through the app's git manager. `None` when there was nothing
+    /// to try: the vault has no repository or was switched while waiting.
+    fn auto_commit(app: &AppHandle, notes_dir: &str) -> Option<Result<(), String>> {
+        let state = app.state::<AppState>();
+        let git_manager = lock_mutex!(state.git_manager);
+        if git_manager.notes_dir != notes_dir || !git_manager.is_git_repo() {
+            return None;
+        }
+        Some(git_manager.commit_changes(None))
+    }
+}
+
+/// Path of `file_path` relative to the repository's working directory
+fn relative_to_workdir(repo: &Repository, file_path: &str) -> Result<PathBuf, String> {
+    let repo_path = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?;
+    Path::new(file_path)
+        .strip_prefix(repo_path)
+        .map(Path::to_path_buf)
+        .map_err(|_| "File is not in repository".to_string())
+}
+
+/// Check an author name and email the way `set_author` will, so settings
+/// can be rejected before they are saved. Blank values are allowed.
+pub(crate) fn validate_author(name: Option<&str>, email: Option<&str>) -> Result<(), String> {
+    if let Some(email) = email.map(str::trim).filter(|email| !email.is_empty()) {
+        validate_email(email)?;
+    }
+    // git2 rejects names containing angle brackets
+    if name.is_some_and(|name| name.contains(['<', '>'])) {
+        return Err("Git author name must not contain '<' or '>'".to_string());
+    }
+    Ok(())
+}
+
+/// Loose sanity check so obviously broken emails fail with a clear message
+/// instead of an opaque git error
+fn validate_email(email: &str) -> Result<(), String> {
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !email.contains(|c: char| c.is_whitespace() || c == '<' || c == '>')
+        }
+        None => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid git author email: {}", email))
+    }
 }
@@ -0,0 +1,120 @@
+use crate::cache::CacheDb;
+use crate::links::{self, LinkStyle};
+use crate::note_manager::{self, read_file_with_encoding};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub files_imported: usize,
+    pub links_converted: usize,
+    /// Vault-relative paths that already exist and were left untouched
+    pub conflicts: Vec<String>,
+}
+
+/// Copy a folder of markdown (and the files next to it) into the vault,
+/// turning relative `[text](note.md)` links into `[[note]]` wikilinks
+/// when the target exists, then index the imported notes. Existing files
+/// are never overwritten; they are reported as conflicts instead.
+pub fn import_markdown_dir(
+    source: &str,
+    notes_dir: &str,
+    cache_db: &CacheDb,
+) -> Result<ImportSummary, String> {
+    let source_root = Path::new(source)
+        .canonicalize()
+        .map_err(|e| format!("Failed to open import folder: {e}"))?;
+    if !source_root.is_dir() {
+        return Err("Import source is not a folder".to_string());
+    }
+    let vault_root = Path::new(notes_dir)
+        .canonicalize()
+        .map_err(|e| format!("Failed to open notes directory: {e}"))?;
+    if vault_root.starts_with(&source_root) || source_root.starts_with(&vault_root) {
+        return Err("Import folder must not overlap the notes directory".to_string());
+    }
+
+    let mut summary = ImportSummary::default();
+    let mut imported_notes = Vec::new();
+
+    let walker = WalkDir::new(&source_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            // Skip other apps' settings and trash (.obsidian, .git, .trash)
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = match entry.path().strip_prefix(&source_root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => continue,
+        };
+        let destination = Path::new(notes_dir).join(&relative);
+
+        let exists = destination.exists()
+            || note_manager::find_case_insensitive_match(&destination).is_some();
+        if exists {
+            summary
+                .conflicts
+                .push(relative.to_string_lossy().to_string());
+            continue;
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {e}"))?;
+        }
+
+        if is_markdown(entry.path()) {
+            let content = read_file_with_encoding(&entry.path().to_string_lossy())?;
+            let (converted, links) = convert_relative_links(&content, entry.path(), &source_root);
+            note_manager::write_note(&destination.to_string_lossy(), &converted)?;
+            summary.links_converted += links;
+            imported_notes.push((destination, converted));
+        } else {
+            fs::copy(entry.path(), &destination)
+                .map_err(|e| format!("Failed to copy '{}': {e}", relative.display()))?;
+        }
+        summary.files_imported += 1;
+    }
+
+    // Index once everything is on disk so links between imported notes resolve
+    for (path, content) in &imported_notes {
+        let path = path.to_string_lossy();
+        let title = Path::new(path.as_ref())
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Untitled");
+        cache_db.update_note_cache_with_fts(&path, title, content, notes_dir)?;
+        if let Ok((secs, nanos)) = crate::commands::get_file_mtime(&path) {
+            cache_db.set_cached_mtime(&path, secs, nanos)?;
+        }
+    }
+
+    summary.conflicts.sort();
+    Ok(summary)
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+/// Rewrite `[text](other.md)` links whose target exists in the import
+/// folder, the same way converting a note's links to wikilinks does. Image
+/// embeds, external URLs and code are left alone. Returns the new content
+/// and the number of links converted.
+fn convert_relative_links(content: &str, note_path: &Path, source_root: &Path) -> (String, usize) {
+    let (updated, changes) = links::convert_links(
+        content,
+        &note_path.to_string_lossy(),
+        &source_root.to_string_lossy(),
+        LinkStyle::Wikilink,
+        // Only used when converting to markdown links
+        |_| None,
+    );
+    (updated, changes.len())
+}
@@ -127,6 +127,20 @@ fn folder_delete_rejects_root_and_traversal_paths() {
         note_manager::delete_folder_confirmed(absolute_folder_str, ws.notes_dir_str()).is_err(),
         "absolute folder paths must be rejected"
     );
+
+    #[cfg(unix)]
+    {
+        let outside = ws.root.join("outside");
+        fs::create_dir_all(&outside).expect("failed to create outside folder");
+        fs::write(outside.join("Secret.md"), "# Secret\n").unwrap();
+        std::os::unix::fs::symlink(&outside, ws.notes_dir.join("Linked"))
+            .expect("failed to create symlink");
+        assert!(
+            note_manager::delete_folder_confirmed("Linked", ws.notes_dir_str()).is_err(),
+            "symlinked folders outside the notes directory must be rejected"
+        );
+        assert!(outside.join("Secret.md").exists());
+    }
 }
 
 #[test]
@@ -258,7 +272,7 @@ fn enhanced_search_reflects_content_updates_and_deletions() {
         .expect("failed to set initial mtime");
 
     let banana_results =
-        note_manager::search_notes_enhanced(ws.notes_dir_str(), "banana", &cache_db)
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "banana", None, &cache_db)
             .expect("banana search should succeed");
     assert!(
         banana_results
@@ -278,7 +292,7 @@ fn enhanced_search_reflects_content_updates_and_deletions() {
         .expect("failed to set updated mtime");
 
     let banana_after_update =
-        note_manager::search_notes_enhanced(ws.notes_dir_str(), "banana", &cache_db)
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "banana", None, &cache_db)
             .expect("banana search after update should succeed");
     assert!(
         banana_after_update.is_empty(),
@@ -286,7 +300,7 @@ fn enhanced_search_reflects_content_updates_and_deletions() {
     );
 
     let carrot_results =
-        note_manager::search_notes_enhanced(ws.notes_dir_str(), "carrot", &cache_db)
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "carrot", None, &cache_db)
             .expect("carrot search should succeed");
     assert!(
         carrot_results
@@ -301,10 +315,3716 @@ fn enhanced_search_reflects_content_updates_and_deletions() {
         .expect("failed to remove stale deleted note");
 
     let carrot_after_delete =
-        note_manager::search_notes_enhanced(ws.notes_dir_str(), "carrot", &cache_db)
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "carrot", None, &cache_db)
             .expect("carrot search after delete should succeed");
     assert!(
         carrot_after_delete.is_empty(),
         "deleted note should not appear in enhanced search results"
     );
 }
+
+#[test]
+fn links_resolve_through_frontmatter_aliases() {
+    let ws = TestWorkspace::new("alias-links");
+    let cache_db = ws.create_cache();
+
+    let alpha_path = ws.write_note("Alpha.md", "---\naliases: [Gamma]\n---\n# Alpha\n");
+    let beta_path = ws.write_note(
+        "Projects/Beta.md",
+        "---\naliases:\n  - Alpha\n---\n# Beta\n",
+    );
+    let source_path = ws.write_note("Source.md", "See [[Gamma]] and [[Alpha]].\n");
+
+    for path in [&alpha_path, &beta_path, &source_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    assert_eq!(
+        cache_db
+            .resolve_note_link("gamma", ws.notes_dir_str(), None)
+            .expect("alias should resolve"),
+        alpha_path,
+        "alias lookup should be case-insensitive"
+    );
+    assert_eq!(
+        cache_db
+            .resolve_note_link("Alpha", ws.notes_dir_str(), None)
+            .expect("stem should resolve"),
+        alpha_path,
+        "a filename match must win over another note's alias"
+    );
+    assert!(cache_db
+        .resolve_note_link("Missing", ws.notes_dir_str(), None)
+        .is_err());
+
+    let backlinks = cache_db
+        .get_backlinks(&alpha_path)
+        .expect("failed to query backlinks");
+    assert_eq!(backlinks, vec![source_path]);
+}
+
+#[test]
+fn enhanced_search_snippets_use_char_offsets_for_accented_text() {
+    let ws = TestWorkspace::new("search-accents");
+    let cache_db = ws.create_cache();
+
+    let note_path = ws.write_note("Français.md", "café résumé naïve\n");
+    let content = note_manager::read_file_with_encoding(&note_path).expect("failed to read note");
+    cache_db
+        .update_note_cache_with_fts(&note_path, "Français", &content, ws.notes_dir_str())
+        .expect("failed to index note");
+
+    let results =
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "Résumé", None, &cache_db)
+            .expect("accented search should not fail");
+    let result = results
+        .iter()
+        .find(|result| result.note.path == note_path)
+        .expect("accented note should match");
+
+    assert_eq!(result.match_count, 1);
+    let snippet = &result.snippets[0];
+    assert_eq!(snippet.match_start, 5, "match start should be in chars");
+    assert_eq!(snippet.match_length, 6, "match length should be in chars");
+
+    let highlighted: String = snippet
+        .text
+        .chars()
+        .skip(snippet.match_start)
+        .take(snippet.match_length)
+        .collect();
+    assert_eq!(highlighted, "résumé");
+}
+
+#[test]
+fn enhanced_search_ranks_by_bm25_and_keeps_stemmed_matches() {
+    let ws = TestWorkspace::new("search-ranking");
+    let cache_db = ws.create_cache();
+
+    let body_path = ws.write_note("Weekend.md", "Some notes about compost and gardening.\n");
+    let title_path = ws.write_note("Gardening.md", "Seeds to buy this spring.\n");
+    let stemmed_path = ws.write_note("Exercise.md", "I was running late again.\n");
+
+    for path in [&body_path, &title_path, &stemmed_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let results =
+        note_manager::search_notes_enhanced(ws.notes_dir_str(), "gardening", None, &cache_db)
+            .expect("ranked search should succeed");
+    let paths: Vec<&str> = results.iter().map(|r| r.note.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![title_path.as_str(), body_path.as_str()],
+        "title matches should outrank content matches"
+    );
+    assert!(results[0].score >= results[1].score);
+
+    let stemmed = note_manager::search_notes_enhanced(ws.notes_dir_str(), "runs", None, &cache_db)
+        .expect("stemmed search should succeed");
+    let stemmed_result = stemmed
+        .iter()
+        .find(|r| r.note.path == stemmed_path)
+        .expect("stemmed FTS match should not be dropped");
+    assert_eq!(stemmed_result.match_count, 0);
+}
+
+#[test]
+fn search_query_parser_translates_operators() {
+    let parsed = note_manager::parse_search_query(r#"tag:work deadline "exact phrase" a OR b"#);
+    assert_eq!(parsed.tags, vec!["work"]);
+    assert_eq!(
+        parsed.fts_expression.as_deref(),
+        Some(r#""deadline" AND "exact phrase" AND ("a" OR "b")"#)
+    );
+    assert_eq!(parsed.terms, vec!["deadline", "exact phrase", "a", "b"]);
+
+    let tag_only = note_manager::parse_search_query("tag:#work");
+    assert_eq!(tag_only.tags, vec!["work"]);
+    assert!(tag_only.fts_expression.is_none());
+
+    let unbalanced = note_manager::parse_search_query(r#"say "hello tag:x"#);
+    assert!(
+        unbalanced.tags.is_empty(),
+        "operators are literal when quotes are unbalanced"
+    );
+    assert_eq!(
+        unbalanced.fts_expression.as_deref(),
+        Some(r#""say" AND "hello" AND "tag:x""#)
+    );
+}
+
+#[test]
+fn enhanced_search_supports_tag_and_phrase_operators() {
+    let ws = TestWorkspace::new("search-operators");
+    let cache_db = ws.create_cache();
+
+    let work_path = ws.write_note("Work.md", "#work\n\nThe project deadline is Friday.\n");
+    let home_path = ws.write_note("Home.md", "#home\n\nThe deadline for taxes.\n");
+
+    for path in [&work_path, &home_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let tagged = note_manager::search_notes_enhanced(
+        ws.notes_dir_str(),
+        "tag:work deadline",
+        None,
+        &cache_db,
+    )
+    .expect("tag search should succeed");
+    let tagged_paths: Vec<&str> = tagged.iter().map(|r| r.note.path.as_str()).collect();
+    assert_eq!(tagged_paths, vec![work_path.as_str()]);
+
+    let phrase = note_manager::search_notes_enhanced(
+        ws.notes_dir_str(),
+        r#""deadline for taxes""#,
+        None,
+        &cache_db,
+    )
+    .expect("phrase search should succeed");
+    let phrase_paths: Vec<&str> = phrase.iter().map(|r| r.note.path.as_str()).collect();
+    assert_eq!(phrase_paths, vec![home_path.as_str()]);
+    assert_eq!(phrase[0].match_count, 1);
+
+    let malformed = note_manager::search_notes_enhanced(
+        ws.notes_dir_str(),
+        r#""project (deadline"#,
+        None,
+        &cache_db,
+    )
+    .expect("unbalanced quotes must not produce an FTS syntax error");
+    assert_eq!(malformed.len(), 1);
+}
+
+#[test]
+fn completing_recurring_todo_keeps_recurrence_on_new_instance() {
+    let ws = TestWorkspace::new("recurring-todo");
+    let cache_db = std::sync::Mutex::new(ws.create_cache());
+
+    let project_path = ws.write_note(
+        "Project.md",
+        "- [ ] Water the plants #home @repeat(daily)\n",
+    );
+    let content =
+        note_manager::read_file_with_encoding(&project_path).expect("failed to read note");
+    cache_db
+        .lock()
+        .unwrap()
+        .update_note_cache_with_fts(
+            &project_path,
+            &title_from_path(&project_path),
+            &content,
+            ws.notes_dir_str(),
+        )
+        .expect("failed to index note");
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let daily_path = ws
+        .notes_dir
+        .join("Daily Notes")
+        .join(format!("{today}.md"))
+        .to_string_lossy()
+        .to_string();
+
+    let open_daily_todo = |cache_db: &CacheDb| {
+        cache_db
+            .get_incomplete_todos()
+            .expect("failed to load todos")
+            .into_iter()
+            .find(|todo| todo.note_path == daily_path)
+            .expect("daily note should contain a new open todo")
+    };
+
+    // First completion happens in the original note
+    crate::commands::toggle_todo_in_note(&project_path, 1, false, ws.notes_dir_str(), &cache_db)
+        .expect("toggle should succeed");
+    let first = open_daily_todo(&cache_db.lock().unwrap());
+    assert_eq!(first.recurrence_pattern.as_deref(), Some("daily"));
+    assert!(first.content.contains("#home"));
+
+    // Second completion happens in the daily note the instance was appended to
+    let returned = crate::commands::toggle_todo_in_note(
+        &daily_path,
+        first.line_number,
+        false,
+        ws.notes_dir_str(),
+        &cache_db,
+    )
+    .expect("toggle should succeed");
+    let second = open_daily_todo(&cache_db.lock().unwrap());
+    assert_ne!(second.line_number, first.line_number);
+    assert_eq!(second.recurrence_pattern.as_deref(), Some("daily"));
+    assert!(second.content.contains("#home"));
+
+    let daily_content = fs::read_to_string(&daily_path).expect("failed to read daily note");
+    assert_eq!(returned, daily_content);
+    assert_eq!(daily_content.matches("@repeat(daily)").count(), 2);
+    assert_eq!(daily_content.matches("- [ ]").count(), 1);
+}
+
+#[test]
+fn retoggling_recurring_todo_does_not_duplicate_next_instance() {
+    let ws = TestWorkspace::new("recurring-todo-dedupe");
+    let cache_db = std::sync::Mutex::new(ws.create_cache());
+
+    let project_path = ws.write_note("Project.md", "- [ ] Stretch @repeat(weekly)\n");
+    let content =
+        note_manager::read_file_with_encoding(&project_path).expect("failed to read note");
+    cache_db
+        .lock()
+        .unwrap()
+        .update_note_cache_with_fts(
+            &project_path,
+            &title_from_path(&project_path),
+            &content,
+            ws.notes_dir_str(),
+        )
+        .expect("failed to index note");
+
+    // complete -> incomplete -> complete
+    for _ in 0..3 {
+        crate::commands::toggle_todo_in_note(
+            &project_path,
+            1,
+            false,
+            ws.notes_dir_str(),
+            &cache_db,
+        )
+        .expect("toggle should succeed");
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let daily_path = ws.notes_dir.join("Daily Notes").join(format!("{today}.md"));
+    let daily_content = fs::read_to_string(&daily_path).expect("failed to read daily note");
+    assert_eq!(daily_content.matches("Stretch").count(), 1);
+
+    let daily_path = daily_path.to_string_lossy().to_string();
+    let instances = cache_db
+        .lock()
+        .unwrap()
+        .get_incomplete_todos()
+        .expect("failed to load todos")
+        .into_iter()
+        .filter(|todo| todo.note_path == daily_path)
+        .count();
+    assert_eq!(instances, 1);
+}
+
+#[test]
+fn todo_links_are_resolved_to_note_paths() {
+    let ws = TestWorkspace::new("todo-links");
+    let db = ws.create_cache();
+    let design = ws.write_note("Design Doc.md", "# Design");
+    let spec = ws.write_note("Spec.md", "# Spec");
+
+    let content = "- [ ] Review [[Design Doc]]\n\
+                   - [ ] Compare [[Design Doc#Goals]] with [[Spec|the spec]] and [[Missing]]\n\
+                   - [ ] No links here\n";
+    db.update_note_cache("/notes/a.md", content, ws.notes_dir_str())
+        .unwrap();
+
+    let todos = db.get_incomplete_todos().unwrap();
+    assert_eq!(todos[0].links, std::slice::from_ref(&design));
+    assert_eq!(todos[1].links, [design, spec]);
+    assert!(todos[2].links.is_empty());
+}
+
+#[test]
+fn update_todo_rewrites_line_and_rejects_non_todo_lines() {
+    let ws = TestWorkspace::new("update-todo");
+    let cache_db = ws.create_cache();
+
+    let path = ws.write_note(
+        "Tasks.md",
+        "# Tasks\n  - [x] Old text !low due:2025-01-01\nplain line\n",
+    );
+    let content = note_manager::read_file_with_encoding(&path).expect("failed to read note");
+    cache_db
+        .update_note_cache_with_fts(&path, &title_from_path(&path), &content, ws.notes_dir_str())
+        .expect("failed to index note");
+
+    let updated = crate::commands::update_todo_in_note(
+        &path,
+        2,
+        "New text #work p:3",
+        Some("high"),
+        Some("2025-02-01"),
+        ws.notes_dir_str(),
+        &cache_db,
+    )
+    .expect("update should succeed");
+    assert_eq!(
+        updated,
+        "# Tasks\n  - [x] New text #work !high @due(2025-02-01)\nplain line\n"
+    );
+
+    let todo = cache_db
+        .get_todo(&path, 2)
+        .expect("todo should be re-indexed");
+    assert_eq!(todo.priority.as_deref(), Some("high"));
+    assert_eq!(todo.due_date.as_deref(), Some("2025-02-01"));
+    assert!(todo.is_completed);
+
+    let err = crate::commands::update_todo_in_note(
+        &path,
+        3,
+        "Should not land",
+        None,
+        None,
+        ws.notes_dir_str(),
+        &cache_db,
+    );
+    assert!(err.is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), updated);
+}
+
+#[test]
+fn cascading_toggle_completes_nested_todos_only() {
+    let ws = TestWorkspace::new("todo-cascade");
+    let cache_db = std::sync::Mutex::new(ws.create_cache());
+
+    let path = ws.write_note(
+        "Plan.md",
+        "- [ ] Parent\n  - [ ] Child\n\n    - [ ] Grandchild\n- [ ] Sibling\n",
+    );
+    let content = note_manager::read_file_with_encoding(&path).expect("failed to read note");
+    cache_db
+        .lock()
+        .unwrap()
+        .update_note_cache_with_fts(&path, &title_from_path(&path), &content, ws.notes_dir_str())
+        .expect("failed to index note");
+
+    let completed =
+        crate::commands::toggle_todo_in_note(&path, 1, true, ws.notes_dir_str(), &cache_db)
+            .expect("toggle should succeed");
+    assert_eq!(
+        completed,
+        "- [x] Parent\n  - [x] Child\n\n    - [x] Grandchild\n- [ ] Sibling\n"
+    );
+
+    let open: Vec<i32> = cache_db
+        .lock()
+        .unwrap()
+        .get_incomplete_todos()
+        .expect("failed to load todos")
+        .iter()
+        .map(|todo| todo.line_number)
+        .collect();
+    assert_eq!(open, vec![5]);
+
+    let reopened =
+        crate::commands::toggle_todo_in_note(&path, 2, true, ws.notes_dir_str(), &cache_db)
+            .expect("toggle should succeed");
+    assert_eq!(
+        reopened,
+        "- [x] Parent\n  - [ ] Child\n\n    - [ ] Grandchild\n- [ ] Sibling\n"
+    );
+}
+
+#[test]
+fn rename_tag_respects_word_boundaries_and_nesting() {
+    let ws = TestWorkspace::new("rename-tag");
+    let cache_db = ws.create_cache();
+
+    let a_path = ws.write_note("A.md", "#todo #todoist #project/alpha\n");
+    let b_path = ws.write_note("B.md", "Nothing tagged here\n");
+    let c_path = ws.write_note("C.md", "#project and #project/beta/gamma\n");
+    for path in [&a_path, &b_path, &c_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let renamed =
+        crate::commands::rename_tag_in_notes("#todo", "task", false, ws.notes_dir_str(), &cache_db)
+            .expect("rename should succeed");
+    assert_eq!(renamed, 1);
+    assert_eq!(
+        fs::read_to_string(&a_path).unwrap(),
+        "#task #todoist #project/alpha\n"
+    );
+    assert!(cache_db.get_notes_by_tag("todo").unwrap().is_empty());
+    assert_eq!(
+        cache_db.get_notes_by_tag("task").unwrap(),
+        vec![a_path.clone()]
+    );
+
+    let renamed = crate::commands::rename_tag_in_notes(
+        "project",
+        "work",
+        false,
+        ws.notes_dir_str(),
+        &cache_db,
+    )
+    .expect("rename should succeed");
+    assert_eq!(renamed, 1);
+    assert_eq!(
+        fs::read_to_string(&c_path).unwrap(),
+        "#work and #project/beta/gamma\n"
+    );
+
+    let renamed = crate::commands::rename_tag_in_notes(
+        "project",
+        "work",
+        true,
+        ws.notes_dir_str(),
+        &cache_db,
+    )
+    .expect("rename should succeed");
+    assert_eq!(renamed, 2);
+    assert_eq!(
+        fs::read_to_string(&a_path).unwrap(),
+        "#task #todoist #work/alpha\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&c_path).unwrap(),
+        "#work and #work/beta/gamma\n"
+    );
+
+    let d_path = ws.write_note(
+        "D.md",
+        "#work here\n```\n#work in a fence\n```\nInline `#work` and https://example.com/page#work\n",
+    );
+    let content = note_manager::read_file_with_encoding(&d_path).expect("failed to read note");
+    cache_db
+        .update_note_cache_with_fts(
+            &d_path,
+            &title_from_path(&d_path),
+            &content,
+            ws.notes_dir_str(),
+        )
+        .expect("failed to index note");
+    let renamed =
+        crate::commands::rename_tag_in_notes("work", "job", false, ws.notes_dir_str(), &cache_db)
+            .expect("rename should succeed");
+    assert_eq!(renamed, 2);
+    assert_eq!(
+        fs::read_to_string(&d_path).unwrap(),
+        "#job here\n```\n#work in a fence\n```\nInline `#work` and https://example.com/page#work\n"
+    );
+    // The index skips the same code and URLs, so nothing under the old tag is left
+    assert_eq!(cache_db.get_tags_for_note(&d_path).unwrap(), ["job"]);
+    assert!(!cache_db.get_notes_by_tag("work").unwrap().contains(&d_path));
+
+    assert!(crate::commands::rename_tag_in_notes(
+        "todo",
+        "bad tag",
+        false,
+        ws.notes_dir_str(),
+        &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn export_note_html_renders_links_images_and_tasks() {
+    let ws = TestWorkspace::new("export-note");
+
+    let note_path = ws.write_note(
+        "Journal/Trip.md",
+        "# Trip\n\nSee [[Packing List|the list]] and [[Budget#Food]].\n\n\
+         ![map](images/map.png)\n\n\
+         - [ ] Book hotel\n- [x] Buy tickets\n\n\
+         ```\n[[not a link]] - [ ] not a task\n```\n",
+    );
+    ws.write_note("Journal/images/map.png", "fake png");
+
+    let out_path = ws.root.join("export").join("Trip.html");
+    let written = crate::export::export_note(&note_path, out_path.to_str().expect("utf-8 path"))
+        .expect("export should succeed");
+    assert_eq!(written, out_path.to_string_lossy());
+
+    let html = fs::read_to_string(&out_path).expect("failed to read export");
+    assert!(html.contains("<title>Trip</title>"));
+    assert!(html.contains(r#"<a href="Packing%20List.html">the list</a>"#));
+    assert!(html.contains(r#"<a href="Budget.html">Budget#Food</a>"#));
+    assert!(html.contains(r#"<img src="images/map.png" alt="map" />"#));
+    assert!(html.contains(r#"<input disabled="" type="checkbox"/>"#));
+    assert!(html.contains(r#"<input disabled="" type="checkbox" checked=""/>"#));
+    assert!(html.contains("<pre><code>[[not a link]] - [ ] not a task\n</code></pre>"));
+
+    assert!(ws
+        .root
+        .join("export")
+        .join("images")
+        .join("map.png")
+        .is_file());
+}
+
+#[test]
+fn export_vault_html_mirrors_folders_and_rewrites_links() {
+    let ws = TestWorkspace::new("export-vault");
+    let cache_db = ws.create_cache();
+
+    ws.write_note("Home.md", "Start at [[Project Plan]] or [[Missing]].\n");
+    ws.write_note(
+        "Work/Project Plan.md",
+        "Back to [[home]].\n\n![chart](images/chart.png)\n",
+    );
+    ws.write_note("Work/images/chart.png", "fake png");
+    ws.write_note(".plainflux/settings.md", "internal");
+    ws.write_note(".git/notes.md", "internal");
+
+    let out_dir = ws.root.join("site");
+    let exported = crate::export::export_vault(
+        ws.notes_dir_str(),
+        out_dir.to_str().expect("utf-8 path"),
+        &cache_db,
+    )
+    .expect("export should succeed");
+    assert_eq!(exported, 2);
+
+    let home = fs::read_to_string(out_dir.join("Home.html")).expect("home should be exported");
+    assert!(home.contains(r#"<a href="Work/Project%20Plan.html">Project Plan</a>"#));
+    assert!(home.contains(r##"<a href="#">Missing</a>"##));
+
+    let plan = fs::read_to_string(out_dir.join("Work").join("Project Plan.html"))
+        .expect("nested note should be exported");
+    assert!(plan.contains(r#"<a href="../Home.html">home</a>"#));
+    assert!(out_dir
+        .join("Work")
+        .join("images")
+        .join("chart.png")
+        .is_file());
+
+    let index = fs::read_to_string(out_dir.join("index.html")).expect("index should exist");
+    assert!(index.contains(r#"<a href="Home.html">Home</a>"#));
+    assert!(index.contains(r#"<a href="Work/Project%20Plan.html">Work/Project Plan</a>"#));
+    assert!(!out_dir.join(".plainflux").exists());
+    assert!(!out_dir.join(".git").exists());
+
+    let inside = ws.notes_dir.join("site");
+    assert!(crate::export::export_vault(
+        ws.notes_dir_str(),
+        inside.to_str().expect("utf-8 path"),
+        &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn unlinked_mentions_skip_links_substrings_and_self() {
+    let ws = TestWorkspace::new("unlinked-mentions");
+
+    let target = ws.write_note("Rust.md", "Rust is mentioned in its own note.\n");
+    let other = ws.write_note(
+        "Journal.md",
+        "Learning rust today.\n\
+         Already linked: [[Rust]].\n\
+         Rustacean is not a mention.\n\
+         Crab language (ferris) counts too.\n\
+         Both [[Rust|the language]] and Rust here.\n",
+    );
+
+    let names = vec!["Rust".to_string(), "Crab language".to_string()];
+    let mentions = note_manager::find_unlinked_mentions(ws.notes_dir_str(), &target, &names)
+        .expect("mention scan should succeed");
+
+    let found: Vec<(&str, usize)> = mentions
+        .iter()
+        .map(|m| (m.note_path.as_str(), m.line_number))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (other.as_str(), 1),
+            (other.as_str(), 4),
+            (other.as_str(), 5)
+        ]
+    );
+    assert_eq!(mentions[0].snippet, "Learning rust today.");
+}
+
+#[test]
+fn global_graph_dedupes_mutual_links_and_optionally_includes_orphans() {
+    let ws = TestWorkspace::new("graph-orphans");
+    let cache_db = ws.create_cache();
+
+    let a_path = ws.write_note("A.md", "Links to [[B]]\n");
+    let b_path = ws.write_note("B.md", "Links back to [[A]]\n");
+    let c_path = ws.write_note("C.md", "No links here\n");
+    for path in [&a_path, &b_path, &c_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let graph = crate::commands::build_global_graph(&cache_db, ws.notes_dir_str(), false)
+        .expect("graph should build");
+    assert_eq!(graph.edges.len(), 1);
+    let mut ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec![a_path.as_str(), b_path.as_str()]);
+    assert!(graph.nodes.iter().all(|n| n.connection_count == 1));
+
+    let graph = crate::commands::build_global_graph(&cache_db, ws.notes_dir_str(), true)
+        .expect("graph should build");
+    assert_eq!(graph.nodes.len(), 3);
+    let orphan = graph
+        .nodes
+        .iter()
+        .find(|n| n.id == c_path)
+        .expect("orphan should be included");
+    assert_eq!(orphan.connection_count, 0);
+}
+
+#[test]
+fn graph_nodes_report_degree_and_tags() {
+    let ws = TestWorkspace::new("graph-degree");
+    let cache_db = ws.create_cache();
+
+    let hub_path = ws.write_note("Hub.md", "#index #map\n\n[[One]] [[Two]]\n");
+    // A link back to the hub doesn't add a second edge
+    let one_path = ws.write_note("One.md", "#leaf\n\n[[Hub]]\n");
+    let two_path = ws.write_note("Two.md", "Plain\n");
+    let three_path = ws.write_note("Three.md", "Points at [[Hub]]\n");
+    for path in [&hub_path, &one_path, &two_path, &three_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let global = crate::commands::build_global_graph(&cache_db, ws.notes_dir_str(), false)
+        .expect("graph should build");
+    let local = crate::commands::build_local_graph(&cache_db, ws.notes_dir_str(), &hub_path, 1)
+        .expect("graph should build");
+
+    for graph in [&global, &local] {
+        let hub = graph
+            .nodes
+            .iter()
+            .find(|n| n.id == hub_path)
+            .expect("hub should be in the graph");
+        assert_eq!(hub.degree, 3);
+        assert_eq!(hub.tags, vec!["index".to_string(), "map".to_string()]);
+
+        let one = graph
+            .nodes
+            .iter()
+            .find(|n| n.id == one_path)
+            .expect("neighbor should be in the graph");
+        assert_eq!(one.degree, 1);
+        assert_eq!(one.tags, vec!["leaf".to_string()]);
+    }
+}
+
+#[test]
+fn local_graph_expands_to_requested_depth_and_caps_size() {
+    let ws = TestWorkspace::new("graph-depth");
+    let cache_db = ws.create_cache();
+
+    let a_path = ws.write_note("A.md", "[[B]]\n");
+    let b_path = ws.write_note("B.md", "[[C]]\n");
+    let c_path = ws.write_note("C.md", "[[D]]\n");
+    let d_path = ws.write_note("D.md", "End of the chain\n");
+    for path in [&a_path, &b_path, &c_path, &d_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let node_ids = |depth: u32| {
+        let graph =
+            crate::commands::build_local_graph(&cache_db, ws.notes_dir_str(), &a_path, depth)
+                .expect("graph should build");
+        assert!(!graph.truncated);
+        let mut ids: Vec<String> = graph.nodes.into_iter().map(|n| n.id).collect();
+        ids.sort();
+        (ids, graph.edges.len())
+    };
+
+    assert_eq!(node_ids(0), (vec![a_path.clone()], 0));
+    assert_eq!(node_ids(1), (vec![a_path.clone(), b_path.clone()], 1));
+    assert_eq!(
+        node_ids(2),
+        (vec![a_path.clone(), b_path.clone(), c_path.clone()], 2)
+    );
+
+    for i in 0..600 {
+        cache_db
+            .add_link(&b_path, &format!("/elsewhere/{i}.md"))
+            .expect("failed to add link");
+    }
+    let graph = crate::commands::build_local_graph(&cache_db, ws.notes_dir_str(), &a_path, 3)
+        .expect("graph should build");
+    assert!(graph.truncated);
+}
+
+#[test]
+fn note_diff_covers_commits_worktree_and_added_files() {
+    let ws = TestWorkspace::new("note-diff");
+    let notes_dir = ws
+        .notes_dir
+        .canonicalize()
+        .expect("notes dir should exist")
+        .to_string_lossy()
+        .to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+    let head = || {
+        git2::Repository::open(&notes_dir)
+            .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id().to_string()))
+            .expect("HEAD should point at a commit")
+    };
+
+    let note_path = format!("{notes_dir}/Note.md");
+    fs::write(&note_path, "first line\nsecond line\n").unwrap();
+    fs::write(format!("{notes_dir}/Other.md"), "unrelated\n").unwrap();
+    git_manager
+        .commit_changes(Some("initial"))
+        .expect("commit should succeed");
+    let first = head();
+
+    fs::write(&note_path, "first line\nchanged line\n").unwrap();
+    fs::write(format!("{notes_dir}/Other.md"), "also changed\n").unwrap();
+    git_manager
+        .commit_changes(Some("edit"))
+        .expect("commit should succeed");
+    let second = head();
+
+    let diff = git_manager
+        .get_note_diff(&note_path, &first, Some(&second))
+        .expect("diff should succeed");
+    assert!(diff.contains("-second line\n"));
+    assert!(diff.contains("+changed line\n"));
+    assert!(!diff.contains("Other.md"));
+
+    fs::write(&note_path, "first line\nchanged line\nthird line\n").unwrap();
+    let diff = git_manager
+        .get_note_diff(&note_path, &second, None)
+        .expect("worktree diff should succeed");
+    assert!(diff.contains("+third line\n"));
+    assert!(!diff.contains("-first line"));
+
+    let new_path = format!("{notes_dir}/New.md");
+    fs::write(&new_path, "brand new\n").unwrap();
+    let diff = git_manager
+        .get_note_diff(&new_path, &second, None)
+        .expect("diff of an untracked note should succeed");
+    assert!(diff.contains("+brand new\n"));
+}
+
+#[test]
+fn git_commits_attachments_only_when_enabled_and_ignores_cache() {
+    let ws = TestWorkspace::new("git-attachments");
+    let notes_dir = ws.notes_dir_str().to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+
+    let gitignore = fs::read_to_string(ws.notes_dir.join(".gitignore")).unwrap();
+    assert!(gitignore.lines().any(|line| line == "notes_cache.db"));
+    assert!(gitignore.lines().any(|line| line == ".plainflux/"));
+
+    ws.write_note("Note.md", "text\n");
+    ws.write_note("images/root.png", "png");
+    ws.write_note("Work/attachments/spec.pdf", "pdf");
+    ws.write_note(".plainflux/settings.json", "{}");
+    ws.write_note("notes_cache.db", "db");
+
+    let committed_paths = || {
+        let repo = git2::Repository::open(&notes_dir).unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let mut paths = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                paths.push(format!("{dir}{}", entry.name().unwrap()));
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .unwrap();
+        paths.sort();
+        paths
+    };
+
+    git_manager
+        .commit_changes(None)
+        .expect("commit should succeed");
+    assert_eq!(committed_paths(), vec!["Note.md".to_string()]);
+
+    git_manager.set_include_attachments(true);
+    git_manager
+        .commit_changes(None)
+        .expect("commit should succeed");
+    assert_eq!(
+        committed_paths(),
+        vec![
+            "Note.md".to_string(),
+            "Work/attachments/spec.pdf".to_string(),
+            "images/root.png".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn restore_note_version_writes_reindexes_and_commits() {
+    let ws = TestWorkspace::new("restore-note");
+    let notes_dir = ws.notes_dir_str().to_string();
+    let cache_db = ws.create_cache();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+    let head = || {
+        git2::Repository::open(&notes_dir)
+            .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id().to_string()))
+            .expect("HEAD should point at a commit")
+    };
+
+    let note_path = ws.write_note("Note.md", "original #draft\n");
+    git_manager
+        .commit_changes(Some("initial"))
+        .expect("commit should succeed");
+    let original = head();
+
+    fs::write(&note_path, "rewritten #final\n").unwrap();
+    git_manager
+        .commit_changes(Some("rewrite"))
+        .expect("commit should succeed");
+
+    let restored = crate::commands::restore_note_from_commit(
+        &git_manager,
+        &cache_db,
+        &notes_dir,
+        &note_path,
+        &original,
+    )
+    .expect("restore should succeed");
+    assert_eq!(restored, "original #draft\n");
+    assert_eq!(fs::read_to_string(&note_path).unwrap(), restored);
+    assert_eq!(
+        cache_db.get_notes_by_tag("draft").unwrap(),
+        vec![note_path.clone()]
+    );
+
+    let repo = git2::Repository::open(&notes_dir).unwrap();
+    let message = repo.head().unwrap().peel_to_commit().unwrap();
+    assert!(message.message().unwrap().starts_with("Restore Note to "));
+
+    // A commit that predates the note can't be restored from
+    let later_path = ws.write_note("Later.md", "new\n");
+    assert!(crate::commands::restore_note_from_commit(
+        &git_manager,
+        &cache_db,
+        &notes_dir,
+        &later_path,
+        &original,
+    )
+    .is_err());
+    assert_eq!(fs::read_to_string(&later_path).unwrap(), "new\n");
+}
+
+#[test]
+fn git_status_reports_added_modified_and_deleted_notes() {
+    let ws = TestWorkspace::new("git-status");
+    let notes_dir = ws.notes_dir_str().to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    let empty = git_manager.get_git_status().expect("status without repo");
+    assert!(empty.added.is_empty() && empty.modified.is_empty() && empty.deleted.is_empty());
+
+    git_manager.init_repo().expect("failed to init repo");
+    let keep_path = ws.write_note("Keep.md", "keep\n");
+    let gone_path = ws.write_note("Gone.md", "gone\n");
+    git_manager
+        .commit_changes(Some("initial"))
+        .expect("commit should succeed");
+
+    fs::write(&keep_path, "edited\n").unwrap();
+    fs::remove_file(&gone_path).unwrap();
+    ws.write_note("Folder/New.md", "new\n");
+    ws.write_note("images/pic.png", "not a note");
+
+    let status = git_manager.get_git_status().expect("status should succeed");
+    assert_eq!(status.modified, vec!["Keep.md".to_string()]);
+    assert_eq!(status.deleted, vec!["Gone.md".to_string()]);
+    assert_eq!(status.added, vec!["Folder/New.md".to_string()]);
+}
+
+#[test]
+fn git_commits_use_configured_author() {
+    let ws = TestWorkspace::new("git-author");
+    let notes_dir = ws.notes_dir_str().to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+
+    assert!(git_manager
+        .set_author(Some("Ada"), Some("not-an-email"))
+        .is_err());
+    assert!(git_manager
+        .set_author(Some("Ada"), Some("ada@localhost"))
+        .is_err());
+
+    let head_author = || {
+        let repo = git2::Repository::open(&notes_dir).unwrap();
+        let commit = repo.head().unwrap().peel_to_commit().unwrap();
+        let author = commit.author();
+        (
+            author.name().unwrap().to_string(),
+            author.email().unwrap().to_string(),
+        )
+    };
+
+    ws.write_note("First.md", "one\n");
+    git_manager
+        .commit_changes(None)
+        .expect("commit should succeed");
+    assert_eq!(
+        head_author(),
+        (
+            "PlainFlux Auto-commit".to_string(),
+            "auto@plainflux.local".to_string()
+        )
+    );
+
+    git_manager
+        .set_author(Some("Ada Lovelace"), Some("ada@example.com"))
+        .expect("valid author should be accepted");
+    ws.write_note("Second.md", "two\n");
+    git_manager
+        .commit_changes(None)
+        .expect("commit should succeed");
+    assert_eq!(
+        head_author(),
+        ("Ada Lovelace".to_string(), "ada@example.com".to_string())
+    );
+}
+
+#[test]
+fn templates_are_listed_and_rendered_by_name() {
+    let ws = TestWorkspace::new("note_templates");
+    let notes_dir = ws.notes_dir_str();
+
+    assert!(note_manager::list_templates(notes_dir).unwrap().is_empty());
+
+    ws.write_note(
+        ".plainflux/templates/Meeting.md",
+        "# {{title}}\n\nDate: {{date}}\n",
+    );
+    ws.write_note(".plainflux/templates/Book.md", "# {{title}}\n");
+    ws.write_note(".plainflux/templates/notes.txt", "not a template");
+
+    assert_eq!(
+        note_manager::list_templates(notes_dir).unwrap(),
+        vec!["Book".to_string(), "Meeting".to_string()]
+    );
+
+    let rendered = note_manager::render_template(notes_dir, "Meeting", "Standup").unwrap();
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    assert_eq!(rendered, format!("# Standup\n\nDate: {today}\n"));
+
+    assert!(note_manager::render_template(notes_dir, "Missing", "x").is_err());
+    assert!(note_manager::render_template(notes_dir, "../Meeting", "x").is_err());
+}
+
+#[test]
+fn template_date_variables_accept_formats_and_relative_days() {
+    let now = chrono::NaiveDate::from_ymd_opt(2024, 2, 29)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap();
+    let render = |template: &str| note_manager::render_template_variables(template, "Note", now);
+
+    assert_eq!(render("{{date:%A}}"), "Thursday");
+    assert_eq!(render("{{date:%Y/%m}}"), "2024/02");
+    assert_eq!(render("{{tomorrow}}"), "2024-03-01");
+    assert_eq!(render("{{yesterday}}"), "2024-02-28");
+    assert_eq!(render("{{date}} {{time}}"), "2024-02-29 09:30");
+
+    // An invalid format is left untouched instead of panicking
+    assert_eq!(render("{{date:%Q}}"), "{{date:%Q}}");
+}
+
+#[test]
+fn periodic_notes_use_iso_weeks_and_are_never_overwritten() {
+    let ws = TestWorkspace::new("periodic_notes");
+    let notes_dir = ws.notes_dir_str();
+    let now = chrono::NaiveDate::from_ymd_opt(2021, 1, 3)
+        .unwrap()
+        .and_hms_opt(8, 0, 0)
+        .unwrap();
+
+    // Jan 3rd 2021 still belongs to ISO week 53 of 2020
+    let weekly = note_manager::create_periodic_note_at(
+        notes_dir,
+        "Daily Notes",
+        "weekly",
+        Some("# {{title}}\nWeek {{week}}\n"),
+        now,
+    )
+    .unwrap();
+    assert!(weekly.ends_with("2020-W53.md"));
+    assert!(Path::new(&weekly).starts_with(ws.notes_dir.join("Daily Notes").join("Weekly")));
+    assert_eq!(
+        fs::read_to_string(&weekly).unwrap(),
+        "# 2020-W53\nWeek 53\n"
+    );
+
+    let monthly =
+        note_manager::create_periodic_note_at(notes_dir, "Daily Notes", "monthly", None, now)
+            .unwrap();
+    assert!(Path::new(&monthly).ends_with("Daily Notes/Monthly/2021-01.md"));
+    assert_eq!(fs::read_to_string(&monthly).unwrap(), "# 2021-01\n\n");
+
+    // Existing notes are not overwritten
+    fs::write(&monthly, "edited").unwrap();
+    note_manager::create_periodic_note_at(notes_dir, "Daily Notes", "monthly", None, now).unwrap();
+    assert_eq!(fs::read_to_string(&monthly).unwrap(), "edited");
+
+    assert!(
+        note_manager::create_periodic_note_at(notes_dir, "Daily Notes", "yearly", None, now)
+            .is_err()
+    );
+
+    let folders = note_manager::get_all_folders(notes_dir, "Daily Notes").unwrap();
+    assert!(folders
+        .iter()
+        .all(|folder| !folder.contains("Weekly") && !folder.contains("Monthly")));
+}
+
+#[test]
+fn daily_note_for_date_renders_the_template_and_rejects_bad_dates() {
+    let ws = TestWorkspace::new("daily_note_for_date");
+    let notes_dir = ws.notes_dir_str();
+
+    let path = note_manager::create_daily_note_for_date(
+        notes_dir,
+        "Daily Notes",
+        "2023-07-04",
+        Some("# {{date}}\n{{weekday}} after {{yesterday}}\n"),
+    )
+    .unwrap();
+    assert!(Path::new(&path).ends_with("Daily Notes/2023-07-04.md"));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# 2023-07-04\nTuesday after 2023-07-03\n"
+    );
+
+    for bad in ["2023-7-4", "2023-02-30", "04/07/2023", "", "2023-07-04x"] {
+        let err = note_manager::create_daily_note_for_date(notes_dir, "Daily Notes", bad, None)
+            .unwrap_err();
+        assert!(err.contains("expected YYYY-MM-DD"), "{bad}: {err}");
+    }
+}
+
+#[test]
+fn listing_daily_notes_reports_incomplete_todos_on_request() {
+    let ws = TestWorkspace::new("list_daily_notes");
+    let notes_dir = ws.notes_dir_str();
+    let cache_db = ws.create_cache();
+
+    // No Daily Notes directory yet
+    assert!(
+        crate::commands::build_daily_note_entries(notes_dir, Some(&cache_db))
+            .unwrap()
+            .is_empty()
+    );
+
+    let open_path = ws.write_note("Daily Notes/2024-05-02.md", "- [ ] call back\n");
+    let done_path = ws.write_note("Daily Notes/2024-05-01.md", "- [x] done\n");
+    ws.write_note("Daily Notes/Ideas.md", "not a daily note\n");
+    ws.write_note("Daily Notes/Weekly/2024-W18.md", "- [ ] weekly\n");
+    for path in [&open_path, &done_path] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+
+    let entries = crate::commands::build_daily_note_entries(notes_dir, Some(&cache_db)).unwrap();
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.date.as_str(), entry.has_incomplete_todos))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("2024-05-01", Some(false)), ("2024-05-02", Some(true))]
+    );
+
+    let without_todos = crate::commands::build_daily_note_entries(notes_dir, None).unwrap();
+    assert_eq!(without_todos.len(), 2);
+    assert!(without_todos
+        .iter()
+        .all(|entry| entry.has_incomplete_todos.is_none()));
+}
+
+#[test]
+fn saving_over_an_externally_modified_note_is_a_conflict() {
+    let ws = TestWorkspace::new("save_conflict");
+    let path = ws.write_note("Draft.md", "original\n");
+
+    let set_modified = |time: SystemTime| {
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    };
+    let opened_at = UNIX_EPOCH + std::time::Duration::new(1_700_000_000, 100_000_000);
+    set_modified(opened_at);
+    let opened = note_manager::read_note(&path).unwrap();
+    let opened_mtime = (opened.last_modified, opened.last_modified_nanos);
+    assert_eq!(opened_mtime, (1_700_000_000, 100_000_000));
+    crate::commands::check_save_conflict(&path, opened_mtime)
+        .expect("unchanged file should not conflict");
+
+    // Another program rewrites the note within the same second
+    fs::write(&path, "external edit\n").unwrap();
+    set_modified(opened_at + std::time::Duration::from_millis(500));
+
+    let err = crate::commands::check_save_conflict(&path, opened_mtime).unwrap_err();
+    assert!(matches!(err, crate::error::AppError::Conflict(_)));
+    assert!(String::from(err).starts_with("Conflict:"));
+
+    // A note that does not exist yet can always be saved
+    let missing = ws.notes_dir.join("New.md");
+    assert!(crate::commands::check_save_conflict(&missing.to_string_lossy(), (0, 0)).is_ok());
+}
+
+#[test]
+fn vault_health_reports_orphans_dead_ends_and_hubs() {
+    let ws = TestWorkspace::new("vault-health");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let hub = ws.write_note("Hub.md", "[[Leaf]] [[Middle]] [[Hub]]\n");
+    let middle = ws.write_note("Middle.md", "[[Leaf]]\n");
+    let leaf = ws.write_note("Leaf.md", "Nothing further\n");
+    let lonely = ws.write_note("Lonely.md", "On my own\n");
+    let daily = ws.write_note("Daily Notes/2024-01-01.md", "Quiet day\n");
+    for path in [&hub, &middle, &leaf, &lonely, &daily] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+
+    let paths = |notes: Vec<note_manager::NoteMetadata>| -> Vec<String> {
+        notes.into_iter().map(|note| note.path).collect()
+    };
+
+    assert_eq!(
+        paths(crate::commands::find_orphan_notes(&cache_db, notes_dir, false).unwrap()),
+        vec![daily.clone(), lonely.clone()]
+    );
+    assert_eq!(
+        paths(crate::commands::find_orphan_notes(&cache_db, notes_dir, true).unwrap()),
+        vec![lonely.clone()]
+    );
+    assert_eq!(
+        paths(crate::commands::find_dead_end_notes(&cache_db, notes_dir, false).unwrap()),
+        vec![leaf.clone()]
+    );
+    // The self-link on Hub does not count towards its outgoing links
+    assert_eq!(
+        paths(crate::commands::find_hub_notes(&cache_db, notes_dir, 1, false).unwrap()),
+        vec![hub.clone(), middle.clone()]
+    );
+    assert_eq!(
+        paths(crate::commands::find_hub_notes(&cache_db, notes_dir, 2, false).unwrap()),
+        vec![hub.clone()]
+    );
+}
+
+#[test]
+fn move_notes_reports_each_file_and_updates_cache() {
+    let ws = TestWorkspace::new("move-notes");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let alpha = ws.write_note("Alpha.md", "alpha #moved\n");
+    let beta = ws.write_note("Beta.md", "beta\n");
+    let existing = ws.write_note("Archive/Beta.md", "already archived\n");
+    for path in [&alpha, &beta, &existing] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+    let missing = ws
+        .notes_dir
+        .join("Missing.md")
+        .to_string_lossy()
+        .to_string();
+
+    let results = crate::commands::move_notes_to_folder(
+        &[alpha.clone(), beta.clone(), missing.clone()],
+        "Archive",
+        notes_dir,
+        &cache_db,
+    )
+    .unwrap();
+    assert_eq!(results.len(), 3);
+
+    let moved_alpha = ws.notes_dir.join("Archive").join("Alpha.md");
+    assert_eq!(results[0].new_path, moved_alpha.to_string_lossy());
+    assert!(results[0].error.is_none());
+    assert!(moved_alpha.exists() && !Path::new(&alpha).exists());
+
+    // A clash in the destination leaves both notes untouched
+    assert_eq!(results[1].new_path, beta);
+    assert!(results[1]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("already exists"));
+    assert_eq!(fs::read_to_string(&existing).unwrap(), "already archived\n");
+    assert!(Path::new(&beta).exists());
+
+    assert_eq!(results[2].new_path, missing);
+    assert!(results[2].error.is_some());
+
+    let cached = cache_db.get_all_cached_paths().unwrap();
+    assert!(cached.contains(&moved_alpha.to_string_lossy().to_string()));
+    assert!(!cached.contains(&alpha));
+    assert_eq!(
+        cache_db.get_notes_by_tag("moved").unwrap(),
+        vec![moved_alpha.to_string_lossy().to_string()]
+    );
+
+    assert!(crate::commands::move_notes_to_folder(
+        std::slice::from_ref(&beta),
+        "../outside",
+        notes_dir,
+        &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn store_image_reuses_identical_images() {
+    let ws = TestWorkspace::new("image-dedup");
+    let images_dir = ws.notes_dir.join("images");
+    fs::create_dir_all(&images_dir).unwrap();
+
+    let screenshot = b"\x89PNG fake screenshot bytes".to_vec();
+    let first = crate::commands::store_image(&images_dir, "paste.png", &screenshot).unwrap();
+    assert_eq!(first, "paste.png");
+
+    // Pasting the same screenshot again, even under another name, reuses it
+    let again = crate::commands::store_image(&images_dir, "paste.png", &screenshot).unwrap();
+    assert_eq!(again, "paste.png");
+    let renamed = crate::commands::store_image(&images_dir, "other.png", &screenshot).unwrap();
+    assert_eq!(renamed, "paste.png");
+
+    // A different image with a clashing name still gets a unique name
+    let different = crate::commands::store_image(&images_dir, "paste.png", b"other bytes").unwrap();
+    assert_eq!(different, "paste-1.png");
+
+    assert_eq!(fs::read_dir(&images_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn attachment_validation_enforces_settings() {
+    use crate::attachments::validate_attachment;
+    use crate::commands::AppSettings;
+
+    let pdf = b"%PDF-1.7 minimal".to_vec();
+    let exe = b"MZ\x90\x00 windows program".to_vec();
+
+    let defaults = AppSettings::default();
+    assert_eq!(
+        validate_attachment("report.pdf", &pdf, &defaults),
+        Ok("application/pdf")
+    );
+    assert_eq!(
+        validate_attachment("notes.txt", b"plain words", &defaults),
+        Ok("text/plain")
+    );
+    // Without safe attachments executables are allowed through
+    assert!(validate_attachment("setup.exe", &exe, &defaults).is_ok());
+
+    let strict = AppSettings {
+        max_attachment_mb: Some(1),
+        allowed_attachment_extensions: Some(vec![".pdf".to_string(), "EXE".to_string()]),
+        safe_attachments: true,
+        ..AppSettings::default()
+    };
+    assert_eq!(
+        validate_attachment("Report.PDF", &pdf, &strict),
+        Ok("application/pdf")
+    );
+    assert!(validate_attachment("notes.txt", b"plain words", &strict)
+        .unwrap_err()
+        .contains("not allowed"));
+    assert!(validate_attachment("setup.exe", &exe, &strict)
+        .unwrap_err()
+        .contains("executable"));
+
+    let too_big = vec![0u8; 1024 * 1024 + 1];
+    assert!(validate_attachment("big.pdf", &too_big, &strict)
+        .unwrap_err()
+        .contains("exceeds the 1 MB limit"));
+}
+
+#[test]
+fn unreferenced_assets_are_found_and_deleted() {
+    use crate::attachments::{delete_assets, find_unreferenced_assets};
+
+    let ws = TestWorkspace::new("unreferenced-assets");
+    let notes_dir = ws.notes_dir_str();
+
+    ws.write_note(
+        "Projects/Plan.md",
+        "![diagram](images/My%20Diagram.png)\n[spec](<attachments/spec v2.pdf>)\n![up](../images/shared.png)\n",
+    );
+    ws.write_note("Root.md", "<img src=\"images/inline.jpg\">\n");
+    let used = [
+        ws.write_note("Projects/images/My Diagram.png", "png"),
+        ws.write_note("Projects/attachments/spec v2.pdf", "pdf"),
+        ws.write_note("images/shared.png", "png"),
+        ws.write_note("images/inline.jpg", "jpg"),
+    ];
+    let unused_old = ws.write_note("Projects/images/old.png", "png");
+    // Same file name as a referenced asset, but in a folder nothing points at
+    let unused_copy = ws.write_note("Archive/images/shared.png", "png");
+    ws.write_note(".plainflux/images/internal.png", "png");
+
+    let unreferenced = find_unreferenced_assets(notes_dir).unwrap();
+    let mut expected = vec![unused_copy.clone(), unused_old.clone()];
+    expected.sort();
+    assert_eq!(unreferenced, expected);
+
+    // Notes and paths escaping an asset folder are refused before anything is deleted
+    let note = ws.notes_dir.join("Root.md").to_string_lossy().to_string();
+    let sneaky = ws
+        .notes_dir
+        .join("images/../Root.md")
+        .to_string_lossy()
+        .to_string();
+    assert!(delete_assets(&[unused_old.clone(), note], notes_dir).is_err());
+    assert!(delete_assets(&[sneaky], notes_dir).is_err());
+    assert!(Path::new(&unused_old).exists());
+
+    assert_eq!(delete_assets(&unreferenced, notes_dir).unwrap(), 2);
+    assert!(find_unreferenced_assets(notes_dir).unwrap().is_empty());
+    assert!(used.iter().all(|path| Path::new(path).exists()));
+}
+
+#[test]
+fn transclusion_expands_nested_embeds_and_stops_at_loops() {
+    let ws = TestWorkspace::new("transclusion-loop");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    ws.write_note("A.md", "A start\n![[B]]\nA end");
+    ws.write_note("B.md", "B body\n![[A]]");
+    ws.write_note("Outer.md", "Outer\n![[Inner]]\n![[missing]]");
+    ws.write_note("Inner.md", "Inner text");
+
+    let expanded = crate::commands::expand_transclusion("Outer", notes_dir, &cache_db).unwrap();
+    assert_eq!(expanded, "Outer\nInner text\n![[missing]]");
+
+    let expanded = crate::commands::expand_transclusion("A", notes_dir, &cache_db).unwrap();
+    assert_eq!(
+        expanded,
+        "A start\nB body\n[Transclusion loop: A → B → A]\nA end"
+    );
+
+    assert!(crate::commands::expand_transclusion("Nope", notes_dir, &cache_db).is_err());
+}
+
+#[test]
+fn transclusion_of_caret_block_returns_only_the_paragraph() {
+    let ws = TestWorkspace::new("caret-blocks");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let path = ws.write_note(
+        "Source.md",
+        "# Section\n\nIntro paragraph.\n\nThe key idea\nspans two lines ^key-idea\n\nMore text.\n",
+    );
+    let content = fs::read_to_string(&path).unwrap();
+    cache_db
+        .update_note_cache_with_fts(&path, "Source", &content, notes_dir)
+        .unwrap();
+
+    assert_eq!(
+        crate::commands::expand_transclusion("Source#^key-idea", notes_dir, &cache_db).unwrap(),
+        "The key idea\nspans two lines"
+    );
+    // Heading blocks still span to the next heading
+    assert!(
+        crate::commands::expand_transclusion("Source#section", notes_dir, &cache_db)
+            .unwrap()
+            .contains("More text.")
+    );
+    assert!(crate::commands::expand_transclusion("Source#^missing", notes_dir, &cache_db).is_err());
+}
+
+#[test]
+fn blocks_report_heading_levels_and_migrate_old_rows() {
+    let ws = TestWorkspace::new("block-levels");
+
+    // A cache created before blocks had a level column
+    {
+        let conn = rusqlite::Connection::open(&ws.db_path).unwrap();
+        conn.execute(
+            "CREATE TABLE blocks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                block_id TEXT NOT NULL,
+                note_path TEXT NOT NULL,
+                line_number INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                UNIQUE(note_path, block_id)
+            )",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO blocks (block_id, note_path, line_number, content) VALUES ('old', 'Old.md', 1, 'Old')",
+            [],
+        )
+        .unwrap();
+    }
+
+    let cache_db = ws.create_cache();
+    assert_eq!(
+        cache_db.get_blocks_for_note("Old.md").unwrap(),
+        vec![("old".to_string(), 1, "Old".to_string(), 1)]
+    );
+
+    let path = ws.write_note(
+        "Outline.md",
+        "# Top\n## Child\n### Grandchild\n## Sibling\n",
+    );
+    let content = fs::read_to_string(&path).unwrap();
+    cache_db
+        .update_note_cache_with_fts(&path, "Outline", &content, ws.notes_dir_str())
+        .unwrap();
+
+    let levels: Vec<(String, i32)> = cache_db
+        .get_blocks_for_note(&path)
+        .unwrap()
+        .into_iter()
+        .map(|(block_id, _, _, level)| (block_id, level))
+        .collect();
+    assert_eq!(
+        levels,
+        vec![
+            ("top".to_string(), 1),
+            ("child".to_string(), 2),
+            ("grandchild".to_string(), 3),
+            ("sibling".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn switching_vaults_keeps_caches_isolated_and_persists() {
+    use crate::commands::{load_app_config, save_app_config, switch_notes_directory, AppConfig};
+
+    let first = TestWorkspace::new("vault-first");
+    let second = TestWorkspace::new("vault-second");
+    first.write_note("Old.md", "#old [[Elsewhere]]\n");
+    second.write_note("New.md", "#new\n- [ ] fresh task\n");
+
+    let mut config = AppConfig::default();
+    let first_vault = config.vault_for_path(first.notes_dir_str());
+    let second_vault = config.add_vault("Work", second.notes_dir_str()).unwrap();
+    assert_eq!(first_vault.cache_db, "notes_cache.db");
+    assert_eq!(second_vault.cache_db, "notes_cache-work.db");
+    assert!(config.add_vault("Work", first.notes_dir_str()).is_err());
+    assert!(config.add_vault("Other", second.notes_dir_str()).is_err());
+    assert_eq!(
+        config.vault_for_path(first.notes_dir_str()).name,
+        first_vault.name
+    );
+
+    let open_cache = |vault: &crate::commands::Vault| {
+        CacheDb::new(&first.root.join(&vault.cache_db).to_string_lossy()).unwrap()
+    };
+
+    let state = crate::commands::AppState::new(
+        open_cache(&first_vault),
+        crate::git_manager::GitManager::new(first.notes_dir_str()),
+        first.notes_dir_str().to_string(),
+    );
+    crate::sync_cache(&state).unwrap();
+    assert_eq!(
+        lock_mutex!(state.cache_db).get_all_tags().unwrap(),
+        vec!["old".to_string()]
+    );
+
+    switch_notes_directory(&state, second.notes_dir_str(), open_cache(&second_vault)).unwrap();
+    assert_eq!(state.notes_dir(), second.notes_dir_str());
+    let cache_db = lock_mutex!(state.cache_db);
+    assert_eq!(cache_db.get_all_tags().unwrap(), vec!["new".to_string()]);
+    assert!(cache_db.get_all_links().unwrap().is_empty());
+    assert_eq!(cache_db.get_incomplete_todos().unwrap().len(), 1);
+    drop(cache_db);
+
+    // Switching back finds the first vault's cache untouched
+    switch_notes_directory(&state, first.notes_dir_str(), open_cache(&first_vault)).unwrap();
+    assert_eq!(
+        lock_mutex!(state.cache_db).get_all_tags().unwrap(),
+        vec!["old".to_string()]
+    );
+
+    // The vault list and active vault survive a restart through the config file
+    let app_data_dir = first.root.join("app-data");
+    assert!(load_app_config(&app_data_dir).notes_dir.is_none());
+    config.notes_dir = Some(second.notes_dir_str().to_string());
+    save_app_config(&app_data_dir, &config).unwrap();
+    let reloaded = load_app_config(&app_data_dir);
+    assert_eq!(reloaded.notes_dir.as_deref(), Some(second.notes_dir_str()));
+    assert_eq!(reloaded.vaults.len(), 2);
+}
+
+#[test]
+fn read_file_with_encoding_handles_unicode_bom_and_legacy_files() {
+    let ws = TestWorkspace::new("encoding");
+
+    // Emoji and CJK text survive a read/write round trip
+    let content = "- [ ] 提交报告 📅 2024-06-01 @repeat(weekly)\n";
+    let path = ws.write_note("Unicode.md", content);
+    let read = note_manager::read_file_with_encoding(&path).unwrap();
+    assert_eq!(read, content);
+    note_manager::write_note(&path, &read).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+    // A UTF-8 byte order mark is not part of the text
+    let bom_path = ws.notes_dir.join("Bom.md");
+    fs::write(&bom_path, b"\xEF\xBB\xBF# Caf\xC3\xA9").unwrap();
+    assert_eq!(
+        note_manager::read_file_with_encoding(&bom_path.to_string_lossy()).unwrap(),
+        "# Café"
+    );
+
+    // UTF-16 files with a BOM are decoded too
+    let utf16_path = ws.notes_dir.join("Utf16.md");
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend("日本 📅".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    fs::write(&utf16_path, utf16).unwrap();
+    assert_eq!(
+        note_manager::read_file_with_encoding(&utf16_path.to_string_lossy()).unwrap(),
+        "日本 📅"
+    );
+
+    // Invalid UTF-8 falls back to Windows-1252
+    let legacy_path = ws.notes_dir.join("Legacy.md");
+    fs::write(&legacy_path, b"\x93Caf\xE9\x94 costs \x805").unwrap();
+    assert_eq!(
+        note_manager::read_file_with_encoding(&legacy_path.to_string_lossy()).unwrap(),
+        "“Café” costs €5"
+    );
+}
+
+#[test]
+fn toggling_a_todo_keeps_crlf_line_endings() {
+    let ws = TestWorkspace::new("todo-crlf");
+    let cache_db = std::sync::Mutex::new(ws.create_cache());
+
+    let path = ws.write_note("Windows.md", "# Tasks\r\n- [ ] First\r\n- [ ] Second\r\n");
+    let content = note_manager::read_file_with_encoding(&path).expect("failed to read note");
+    cache_db
+        .lock()
+        .unwrap()
+        .update_note_cache_with_fts(&path, &title_from_path(&path), &content, ws.notes_dir_str())
+        .expect("failed to index note");
+
+    crate::commands::toggle_todo_in_note(&path, 2, false, ws.notes_dir_str(), &cache_db)
+        .expect("toggle should succeed");
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Tasks\r\n- [x] First\r\n- [ ] Second\r\n"
+    );
+
+    assert_eq!(note_manager::line_ending("a\nb\r\nc\n"), "\n");
+    assert_eq!(
+        note_manager::with_line_ending("a\nb\r\nc\n", "\r\n"),
+        "a\r\nb\r\nc\r\n"
+    );
+}
+
+#[test]
+fn deleted_notes_go_to_trash_and_can_be_restored() {
+    let ws = TestWorkspace::new("trash");
+    let cache_db = ws.create_cache();
+
+    let path = ws.write_note("Projects/Plan.md", "# Plan\n#work");
+    cache_db
+        .update_note_cache_with_fts(&path, "Plan", "# Plan\n#work", ws.notes_dir_str())
+        .expect("failed to index note");
+
+    let trash_path = crate::commands::trash_note(&path, ws.notes_dir_str(), &cache_db)
+        .expect("trash should succeed");
+    assert!(!Path::new(&path).exists());
+    assert_eq!(
+        Path::new(&trash_path),
+        ws.notes_dir.join(".plainflux/trash/Projects/Plan.md")
+    );
+    assert!(!cache_db.get_all_cached_paths().unwrap().contains(&path));
+    assert!(note_manager::list_notes(ws.notes_dir_str())
+        .unwrap()
+        .is_empty());
+
+    // A second note with the same path gets a timestamped name in the trash
+    ws.write_note("Projects/Plan.md", "# Plan v2");
+    let second_trash_path = crate::commands::trash_note(&path, ws.notes_dir_str(), &cache_db)
+        .expect("second trash should succeed");
+    assert_ne!(second_trash_path, trash_path);
+    assert!(second_trash_path.contains("Plan (deleted "));
+
+    let entries = crate::trash::list_trash(ws.notes_dir_str()).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| entry.original_path == path));
+    assert!(entries.iter().all(|entry| entry.title == "Plan"));
+
+    // Restoring puts the note back and indexes it again
+    let restored =
+        crate::commands::restore_trashed_note(&trash_path, ws.notes_dir_str(), &cache_db)
+            .expect("restore should succeed");
+    assert_eq!(restored, path);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "# Plan\n#work");
+    assert!(cache_db.get_all_cached_paths().unwrap().contains(&path));
+
+    // The other copy can't overwrite the restored note
+    let err =
+        crate::commands::restore_trashed_note(&second_trash_path, ws.notes_dir_str(), &cache_db)
+            .unwrap_err();
+    assert!(err.contains("already exists"), "{err}");
+
+    // Paths outside the trash are rejected
+    assert!(crate::trash::restore_from_trash(&path, ws.notes_dir_str()).is_err());
+
+    assert_eq!(crate::trash::empty_trash(ws.notes_dir_str()).unwrap(), 1);
+    assert!(crate::trash::list_trash(ws.notes_dir_str())
+        .unwrap()
+        .is_empty());
+    assert!(Path::new(&path).exists());
+}
+
+#[test]
+fn deleting_a_folder_clears_only_its_notes_from_the_cache() {
+    let ws = TestWorkspace::new("folder-delete-cache");
+    let cache_db = ws.create_cache();
+
+    let notes = [
+        ws.write_note("Archive/Old.md", "# Old\n#archive [[Keep]]"),
+        ws.write_note("Archive/Nested/Older.md", "# Older\n- [ ] Sort #archive"),
+        ws.write_note("Keep.md", "# Keep\n#active [[Old]]\n- [ ] Still here"),
+    ];
+    for path in &notes {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+        let (secs, nanos) = crate::commands::get_file_mtime(path).unwrap();
+        cache_db.set_cached_mtime(path, secs, nanos).unwrap();
+    }
+    let keep = &notes[2];
+    let keep_mtime = cache_db.get_cached_mtime(keep).unwrap();
+    let outgoing_links = |cache_db: &CacheDb| {
+        cache_db
+            .get_links_for_note(keep)
+            .unwrap()
+            .into_iter()
+            .filter(|link| &link.from_note == keep)
+            .map(|link| link.to_note)
+            .collect::<Vec<_>>()
+    };
+    let keep_links = outgoing_links(&cache_db);
+    assert_eq!(keep_links.len(), 1);
+
+    crate::commands::delete_folder_and_cache("Archive", ws.notes_dir_str(), &cache_db)
+        .expect("folder delete should succeed");
+
+    assert!(!ws.notes_dir.join("Archive").exists());
+    assert_eq!(cache_db.get_all_cached_paths().unwrap(), vec![keep.clone()]);
+    assert!(cache_db.get_notes_by_tag("archive").unwrap().is_empty());
+    assert!(cache_db.search_notes_fts("Older").unwrap().is_empty());
+
+    // The unrelated note is left exactly as it was
+    assert_eq!(cache_db.get_cached_mtime(keep).unwrap(), keep_mtime);
+    assert_eq!(cache_db.get_tags_for_note(keep).unwrap(), vec!["active"]);
+    assert_eq!(outgoing_links(&cache_db), keep_links);
+    assert_eq!(
+        cache_db.search_notes_fts("Still").unwrap(),
+        vec![keep.clone()]
+    );
+    assert_eq!(cache_db.get_incomplete_todos().unwrap().len(), 1);
+}
+
+#[test]
+fn note_names_that_differ_only_in_case_collide() {
+    let ws = TestWorkspace::new("case-collide");
+    let state = crate::commands::AppState::new(
+        ws.create_cache(),
+        crate::git_manager::GitManager::new(ws.notes_dir_str()),
+        ws.notes_dir_str().to_string(),
+    );
+    let empty_note = |title: &str| Ok(format!("# {title}\n"));
+
+    let foo = crate::commands::create_note_with_content("Foo", &state, empty_note)
+        .expect("create should succeed");
+    let lower = crate::commands::create_note_with_content("foo", &state, empty_note)
+        .expect("create should return the existing note");
+    assert_eq!(lower, foo);
+    assert_eq!(
+        note_manager::list_notes(ws.notes_dir_str()).unwrap().len(),
+        1
+    );
+    assert_eq!(fs::read_to_string(&foo).unwrap(), "# Foo\n");
+
+    // Renaming onto a differently-cased existing note is refused
+    let bar = ws.write_note("Bar.md", "# Bar\n");
+    let err = note_manager::rename_note(&bar, "FOO").unwrap_err();
+    assert!(err.contains("already exists"), "{err}");
+    assert!(Path::new(&bar).exists());
+
+    // ...but changing the case of the note itself is allowed
+    let renamed = note_manager::rename_note(&foo, "FOO").expect("case-only rename");
+    assert!(renamed.ends_with("FOO.md"));
+    assert_eq!(
+        note_manager::list_notes(ws.notes_dir_str()).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn note_toc_lists_headings_with_unique_block_slugs() {
+    let ws = TestWorkspace::new("note-toc");
+    let cache_db = ws.create_cache();
+    let content = "# Meeting\n## Notes\nfirst\n## Actions\n## Notes\nsecond\n";
+    let path = ws.write_note("Meeting.md", content);
+    cache_db
+        .update_note_cache_with_fts(&path, "Meeting", content, ws.notes_dir_str())
+        .expect("failed to index note");
+
+    let toc = note_manager::extract_toc(content);
+    let summary: Vec<(i32, &str, &str, i32)> = toc
+        .iter()
+        .map(|entry| {
+            (
+                entry.level,
+                entry.text.as_str(),
+                entry.slug.as_str(),
+                entry.line_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (1, "Meeting", "meeting", 1),
+            (2, "Notes", "notes", 2),
+            (2, "Actions", "actions", 4),
+            (2, "Notes", "notes-2", 5),
+        ]
+    );
+
+    // Every slug is a stored block, so the repeated heading is reachable too
+    let stored: Vec<String> = cache_db
+        .get_blocks_for_note(&path)
+        .unwrap()
+        .into_iter()
+        .map(|(block_id, ..)| block_id)
+        .collect();
+    for entry in &toc {
+        assert!(stored.contains(&entry.slug), "missing block {}", entry.slug);
+    }
+    assert_eq!(
+        cache_db.get_block(&path, "notes-2").unwrap(),
+        Some((5, "Notes".to_string()))
+    );
+}
+
+#[test]
+fn repeated_headings_are_stored_as_distinct_blocks() {
+    let ws = TestWorkspace::new("repeated-headings");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+    let content = "## Notes\nfirst\n## Notes\nsecond\n## Notes\nthird\n";
+    let path = ws.write_note("Log.md", content);
+    cache_db
+        .update_note_cache_with_fts(&path, "Log", content, notes_dir)
+        .unwrap();
+
+    let blocks: Vec<(String, i32)> = cache_db
+        .get_blocks_for_note(&path)
+        .unwrap()
+        .into_iter()
+        .map(|(block_id, line_number, ..)| (block_id, line_number))
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![
+            ("notes".to_string(), 1),
+            ("notes-2".to_string(), 3),
+            ("notes-3".to_string(), 5),
+        ]
+    );
+
+    // Transclusion finds each one by slug or by heading text
+    assert_eq!(
+        crate::commands::expand_transclusion("Log#notes-3", notes_dir, &cache_db).unwrap(),
+        "## Notes\nthird"
+    );
+    assert_eq!(
+        crate::commands::expand_transclusion("Log#Notes 2", notes_dir, &cache_db).unwrap(),
+        "## Notes\nsecond"
+    );
+    assert_eq!(
+        crate::commands::expand_transclusion("Log#Notes", notes_dir, &cache_db).unwrap(),
+        "## Notes\nfirst"
+    );
+}
+
+#[test]
+fn search_can_be_scoped_to_a_folder_or_tag() {
+    let ws = TestWorkspace::new("search-scope");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let project = ws.write_note("Projects/Launch.md", "# Launch\nbudget review #work");
+    let nested = ws.write_note("Projects/Old/Retro.md", "# Retro\nbudget overrun");
+    let personal = ws.write_note("Home.md", "# Home\nbudget for groceries #personal");
+    ws.write_note("ProjectsArchive/Draft.md", "# Draft\nbudget draft #work");
+    for path in note_manager::list_notes(notes_dir).unwrap() {
+        let content = fs::read_to_string(&path.path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(&path.path, &path.title, &content, notes_dir)
+            .unwrap();
+    }
+
+    let search = |scope: note_manager::SearchScope| {
+        let mut paths: Vec<String> =
+            note_manager::search_notes_enhanced(notes_dir, "budget", Some(&scope), &cache_db)
+                .expect("scoped search should succeed")
+                .into_iter()
+                .map(|result| result.note.path)
+                .collect();
+        paths.sort();
+        paths
+    };
+
+    // Folder scope includes subfolders but not folders sharing a prefix
+    let mut in_projects = vec![project.clone(), nested];
+    in_projects.sort();
+    assert_eq!(
+        search(note_manager::SearchScope::Folder("Projects".to_string())),
+        in_projects
+    );
+    assert_eq!(
+        search(note_manager::SearchScope::Tag("#personal".to_string())),
+        vec![personal]
+    );
+
+    // An empty intersection finds nothing rather than everything
+    assert!(search(note_manager::SearchScope::Tag("missing".to_string())).is_empty());
+    assert!(search(note_manager::SearchScope::Folder("Nowhere".to_string())).is_empty());
+    assert!(note_manager::search_notes_enhanced(
+        notes_dir,
+        "budget",
+        Some(&note_manager::SearchScope::Folder("../outside".to_string())),
+        &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn replace_in_notes_previews_and_rewrites_matches() {
+    let ws = TestWorkspace::new("replace-in-notes");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let first = ws.write_note(
+        "First.md",
+        "Acme ships Acme Pro\n```\nlet name = \"Acme\";\n```\nAcme again\n",
+    );
+    let second = ws.write_note("Second.md", "No match here\n");
+    let outside = ws.root.join("Outside.md").to_string_lossy().to_string();
+    fs::write(&outside, "Acme").unwrap();
+    let paths = vec![first.clone(), second.clone(), outside.clone()];
+
+    let preview = crate::commands::replace_in_note_files(
+        "Acme", "Globex", &paths, true, true, notes_dir, &cache_db,
+    )
+    .expect("dry run should succeed");
+    let counts: Vec<(usize, bool)> = preview
+        .iter()
+        .map(|result| (result.replacements, result.error.is_some()))
+        .collect();
+    assert_eq!(counts, vec![(3, false), (0, false), (0, true)]);
+    assert!(fs::read_to_string(&first)
+        .unwrap()
+        .starts_with("Acme ships"));
+
+    let applied = crate::commands::replace_in_note_files(
+        "Acme", "Globex", &paths, false, true, notes_dir, &cache_db,
+    )
+    .expect("replace should succeed");
+    assert_eq!(applied[0].replacements, 3);
+    assert_eq!(
+        fs::read_to_string(&first).unwrap(),
+        "Globex ships Globex Pro\n```\nlet name = \"Acme\";\n```\nGlobex again\n"
+    );
+    assert_eq!(fs::read_to_string(&outside).unwrap(), "Acme");
+    assert_eq!(
+        cache_db.search_notes_fts("Globex").unwrap(),
+        vec![first.clone()]
+    );
+
+    // Without the flag code blocks are replaced too
+    let in_code = crate::commands::replace_in_note_files(
+        "Acme",
+        "Initech",
+        &paths[..1],
+        false,
+        false,
+        notes_dir,
+        &cache_db,
+    )
+    .unwrap();
+    assert_eq!(in_code[0].replacements, 1);
+    assert!(fs::read_to_string(&first).unwrap().contains("\"Initech\""));
+
+    assert!(crate::commands::replace_in_note_files(
+        "", "x", &paths, true, false, notes_dir, &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn regex_search_reports_matches_captures_and_files_scanned() {
+    let ws = TestWorkspace::new("regex-search");
+    let notes_dir = ws.notes_dir_str();
+    let invoices = ws.write_note(
+        "Invoices.md",
+        "# Invoices\nINV-2041 paid\nCafé INV-77 due\n",
+    );
+    ws.write_note("Other.md", "Nothing to see\n");
+    ws.write_note(".plainflux/templates/Invoice.md", "INV-0000\n");
+
+    let found = note_manager::search_notes_regex(notes_dir, r"INV-(\d+)(x)?")
+        .expect("regex search should succeed");
+    assert_eq!(found.files_scanned, 2);
+    assert_eq!(found.results.len(), 1);
+    assert_eq!(found.results[0].note.path, invoices);
+
+    let matches = &found.results[0].matches;
+    assert_eq!(matches.len(), 2);
+    assert_eq!(
+        (
+            matches[0].line_number,
+            matches[0].match_start,
+            matches[0].match_length
+        ),
+        (2, 0, 8)
+    );
+    // Offsets count chars, so the accented "é" is one position
+    assert_eq!(
+        (
+            matches[1].line_number,
+            matches[1].match_start,
+            matches[1].match_length
+        ),
+        (3, 5, 6)
+    );
+    assert_eq!(
+        matches[1].captures,
+        vec![
+            Some(note_manager::CaptureSpan {
+                start: 9,
+                length: 2
+            }),
+            None
+        ]
+    );
+
+    let err = note_manager::search_notes_regex(notes_dir, "INV-(").unwrap_err();
+    assert!(err.starts_with("Invalid regex"), "{err}");
+}
+
+#[test]
+fn recent_notes_can_be_filtered_by_a_custom_range() {
+    let ws = TestWorkspace::new("recent-custom-range");
+    let notes_dir = ws.notes_dir_str();
+
+    let set_mtime = |path: &str, secs: u64| {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+            .unwrap();
+    };
+    let early = ws.write_note("Early.md", "# Early");
+    let middle = ws.write_note("Middle.md", "# Middle");
+    let late = ws.write_note("Late.md", "# Late");
+    set_mtime(&early, 1_700_000_000);
+    set_mtime(&middle, 1_700_050_000);
+    set_mtime(&late, 1_700_100_000);
+
+    let titles = |filter| -> Vec<String> {
+        crate::commands::list_recent_notes(notes_dir, filter)
+            .expect("recent notes should load")
+            .into_iter()
+            .map(|note| note.title)
+            .collect()
+    };
+    use crate::commands::RecentNotesFilter::Custom;
+
+    // Both ends are inclusive, newest first
+    assert_eq!(
+        titles(Custom {
+            since: 1_700_000_000,
+            until: Some(1_700_050_000)
+        }),
+        vec!["Middle", "Early"]
+    );
+    assert_eq!(
+        titles(Custom {
+            since: 1_700_050_001,
+            until: None
+        }),
+        vec!["Late"]
+    );
+
+    let err = crate::commands::list_recent_notes(
+        notes_dir,
+        Custom {
+            since: 1_700_100_000,
+            until: Some(1_700_000_000),
+        },
+    )
+    .unwrap_err();
+    assert!(err.contains("Invalid date range"), "{err}");
+}
+
+#[test]
+fn importing_a_markdown_folder_converts_links_and_reports_conflicts() {
+    let ws = TestWorkspace::new("import-markdown");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+    ws.write_note("Existing.md", "# Already here\n");
+
+    let source = ws.root.join("export");
+    let write_source = |relative: &str, content: &str| {
+        let path = source.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write_source(
+        "Index.md",
+        "See [the plan](Projects/Plan%20A.md#goals), [missing](Gone.md), \
+         [site](https://example.com/page.md) and ![diagram](images/flow.png)\n",
+    );
+    write_source(
+        "Projects/Plan A.md",
+        "Back to [the index](../Index.md) and [Index](../Index.md)\n",
+    );
+    write_source("Journal.md", "Worked on [the plan](Projects/Plan%20A.md)\n");
+    write_source("images/flow.png", "png");
+    write_source("Existing.md", "# Imported copy\n");
+    write_source(".obsidian/app.json", "{}");
+
+    let summary =
+        crate::import::import_markdown_dir(&source.to_string_lossy(), notes_dir, &cache_db)
+            .expect("import should succeed");
+    assert_eq!(summary.files_imported, 4);
+    assert_eq!(summary.links_converted, 4);
+    assert_eq!(summary.conflicts, vec!["Existing.md".to_string()]);
+
+    assert_eq!(
+        fs::read_to_string(ws.notes_dir.join("Index.md")).unwrap(),
+        "See [[Plan A#goals|the plan]], [missing](Gone.md), \
+         [site](https://example.com/page.md) and ![diagram](images/flow.png)\n"
+    );
+    assert_eq!(
+        fs::read_to_string(ws.notes_dir.join("Projects/Plan A.md")).unwrap(),
+        "Back to [[Index|the index]] and [[Index]]\n"
+    );
+    assert_eq!(
+        fs::read_to_string(ws.notes_dir.join("Journal.md")).unwrap(),
+        "Worked on [[Plan A|the plan]]\n"
+    );
+    assert!(ws.notes_dir.join("images/flow.png").exists());
+    assert!(!ws.notes_dir.join(".obsidian").exists());
+    assert_eq!(
+        fs::read_to_string(ws.notes_dir.join("Existing.md")).unwrap(),
+        "# Already here\n"
+    );
+
+    // Converted links, aliased ones included, are indexed between the
+    // imported notes
+    let plan = ws
+        .notes_dir
+        .join("Projects/Plan A.md")
+        .to_string_lossy()
+        .to_string();
+    let index = ws.notes_dir.join("Index.md").to_string_lossy().to_string();
+    let journal = ws
+        .notes_dir
+        .join("Journal.md")
+        .to_string_lossy()
+        .to_string();
+    let mut backlinks = cache_db.get_backlinks(&plan).unwrap();
+    backlinks.sort();
+    assert_eq!(backlinks, vec![index, journal]);
+
+    assert!(crate::import::import_markdown_dir(notes_dir, notes_dir, &cache_db).is_err());
+}
+
+#[test]
+fn saved_searches_persist_with_unique_sanitized_names() {
+    let ws = TestWorkspace::new("saved-searches");
+    let notes_dir = ws.notes_dir_str();
+
+    let saved =
+        crate::commands::add_saved_search(notes_dir, "  Work\t deadlines ", "tag:work deadline")
+            .expect("save should succeed");
+    assert_eq!(saved.name, "Work deadlines");
+    crate::commands::add_saved_search(notes_dir, "Taxes", r#""deadline for taxes""#).unwrap();
+
+    let err = crate::commands::add_saved_search(notes_dir, "work DEADLINES", "other").unwrap_err();
+    assert!(err.contains("already exists"), "{err}");
+    assert!(crate::commands::add_saved_search(notes_dir, "   ", "query").is_err());
+    assert!(crate::commands::add_saved_search(notes_dir, "Bad\u{7}name", "query").is_err());
+    assert!(crate::commands::add_saved_search(notes_dir, "Empty", " ").is_err());
+
+    let stored = crate::commands::load_saved_searches(notes_dir).unwrap();
+    assert_eq!(
+        stored.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("Taxes".to_string(), r#""deadline for taxes""#.to_string()),
+            (
+                "Work deadlines".to_string(),
+                "tag:work deadline".to_string()
+            ),
+        ]
+    );
+
+    crate::commands::remove_saved_search(notes_dir, " Taxes ").unwrap();
+    assert!(crate::commands::remove_saved_search(notes_dir, "Taxes").is_err());
+    assert_eq!(
+        crate::commands::load_saved_searches(notes_dir)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn rollover_moves_incomplete_todos_to_the_next_daily_note() {
+    let ws = TestWorkspace::new("todo-rollover");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let from = ws.write_note(
+        "Daily Notes/2026-03-09.md",
+        "# 2026-03-09\n\n## Tasks\n\
+         - [ ] Call bank !high @due(2026-03-12)\n\
+         - [x] Ship release\n\
+         \x20 - [ ] Write changelog\n\
+         - [ ] Plan trip\n\
+         \x20 - [ ] Book flights\n\
+         \x20 - [x] Pick dates\n\
+         \n## Notes\nA quiet day\n",
+    );
+    let template = "# {{date}}\n\n## Tasks\n- [ ] Morning review\n\n## Notes\n\n";
+
+    let moved =
+        crate::commands::rollover_todos(notes_dir, "2026-03-09", "2026-03-10", template, &cache_db)
+            .expect("rollover should succeed");
+    assert_eq!(moved, 3);
+
+    assert_eq!(
+        fs::read_to_string(&from).unwrap(),
+        "# 2026-03-09\n\n## Tasks\n\
+         - [x] Ship release\n\
+         \x20 - [ ] Write changelog\n\
+         \n## Notes\nA quiet day\n"
+    );
+    let to = ws.notes_dir.join("Daily Notes/2026-03-10.md");
+    assert_eq!(
+        fs::read_to_string(&to).unwrap(),
+        "# 2026-03-10\n\n## Tasks\n- [ ] Morning review\n\
+         - [ ] Call bank !high @due(2026-03-12)\n\
+         - [ ] Plan trip\n\
+         \x20 - [ ] Book flights\n\
+         \x20 - [x] Pick dates\n\
+         \n## Notes\n\n"
+    );
+
+    // Both notes are re-indexed
+    let to = to.to_string_lossy().to_string();
+    let open_todos: Vec<(String, String)> = cache_db
+        .get_incomplete_todos()
+        .unwrap()
+        .into_iter()
+        .map(|todo| (todo.note_path, todo.content))
+        .collect();
+    assert!(open_todos.contains(&(to.clone(), "Book flights".to_string())));
+    assert!(open_todos.contains(&(from.clone(), "Write changelog".to_string())));
+    assert!(!open_todos
+        .iter()
+        .any(|(path, content)| path == &from && content == "Plan trip"));
+
+    assert_eq!(
+        crate::commands::rollover_todos(notes_dir, "2026-03-09", "2026-03-10", template, &cache_db)
+            .unwrap(),
+        0
+    );
+    assert!(crate::commands::rollover_todos(
+        notes_dir,
+        "2026-03-01",
+        "2026-03-10",
+        template,
+        &cache_db
+    )
+    .is_err());
+}
+
+#[test]
+fn rollover_moves_nested_notes_and_handles_wide_indents() {
+    let content = "- [ ] Draft post\n  Outline in the doc\n  - [x] Pick topic\n\
+                   - [x] Done\n  - [ ] Left with its parent\n";
+    let (remaining, moved, todo_count) = note_manager::take_incomplete_todos(content);
+    assert_eq!(remaining, "- [x] Done\n  - [ ] Left with its parent\n");
+    assert_eq!(
+        moved,
+        vec![
+            "- [ ] Draft post",
+            "  Outline in the doc",
+            "  - [x] Pick topic"
+        ]
+    );
+    assert_eq!(todo_count, 1);
+
+    // Dedenting counts characters, not bytes
+    let content = "## Tasks\n\u{3000}- [ ] Wide indent\n\u{3000}\u{3000}detail\n";
+    let (remaining, moved, _) = note_manager::take_incomplete_todos(content);
+    assert_eq!(remaining, "## Tasks\n");
+    assert_eq!(moved, vec!["- [ ] Wide indent", "\u{3000}detail"]);
+}
+
+#[test]
+fn backlinks_with_context_show_linking_lines_including_aliases() {
+    let ws = TestWorkspace::new("backlink-context");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let target = ws.write_note(
+        "Project Atlas.md",
+        "---\naliases: [Atlas]\n---\n# Project Atlas\n",
+    );
+    let by_title = ws.write_note(
+        "Standup.md",
+        "# Standup\nDiscussed [[project atlas#Risks]] today.\nUnrelated line\n",
+    );
+    let by_alias = ws.write_note("Roadmap.md", "Q3 goals: ship [[Atlas]] and [[Other]]\n");
+    // Index the target first so the alias resolves for the linking notes
+    for path in [&target, &by_title, &by_alias] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+
+    let backlinks =
+        crate::commands::backlinks_with_context(&target, &cache_db).expect("backlinks should load");
+    let summary: Vec<(&str, usize, &str, usize, usize)> = backlinks
+        .iter()
+        .flat_map(|backlink| {
+            backlink.snippets.iter().map(|s| {
+                (
+                    backlink.source_title.as_str(),
+                    s.line_number,
+                    s.text.as_str(),
+                    s.match_start,
+                    s.match_length,
+                )
+            })
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "Roadmap",
+                1,
+                "Q3 goals: ship [[Atlas]] and [[Other]]",
+                15,
+                9
+            ),
+            (
+                "Standup",
+                2,
+                "Discussed [[project atlas#Risks]] today.",
+                10,
+                23
+            ),
+        ]
+    );
+}
+
+#[test]
+fn folder_tree_counts_notes_and_keeps_empty_branches() {
+    let ws = TestWorkspace::new("folder-tree");
+    ws.write_note("Inbox.md", "");
+    ws.write_note("Projects/Plan.md", "");
+    ws.write_note("Projects/Launch/Checklist.md", "");
+    ws.write_note("Projects/Launch/Budget.md", "");
+    ws.write_note("Areas/Health/Sleep/Log.md", "");
+    ws.write_note(".plainflux/templates/Meeting.md", "");
+    ws.write_note("images/sketch.md", "");
+    ws.write_note("Daily Notes/2026-01-05.md", "");
+    ws.write_note("Daily Notes/Archive/2025-12-31.md", "");
+    fs::create_dir_all(ws.notes_dir.join("Empty")).unwrap();
+
+    let tree =
+        note_manager::get_folder_tree(ws.notes_dir_str(), note_manager::DEFAULT_DAILY_NOTES_FOLDER)
+            .expect("tree should build");
+
+    fn flatten(node: &note_manager::FolderNode, out: &mut Vec<(String, usize, usize)>) {
+        out.push((
+            node.path.replace('\\', "/"),
+            node.note_count,
+            node.total_count,
+        ));
+        for child in &node.children {
+            flatten(child, out);
+        }
+    }
+    let mut nodes = Vec::new();
+    flatten(&tree, &mut nodes);
+    assert_eq!(
+        nodes,
+        vec![
+            (String::new(), 1, 5),
+            ("Areas".to_string(), 0, 1),
+            ("Areas/Health".to_string(), 0, 1),
+            ("Areas/Health/Sleep".to_string(), 1, 1),
+            ("Empty".to_string(), 0, 0),
+            ("Projects".to_string(), 1, 3),
+            ("Projects/Launch".to_string(), 2, 2),
+        ]
+    );
+    assert_eq!(tree.children[2].name, "Projects");
+}
+
+#[test]
+fn fast_search_returns_escaped_highlighted_snippets_from_the_index() {
+    let ws = TestWorkspace::new("fast-search");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let recipe = ws.write_note("Recipe.md", "Bake the <bread> & serve #food");
+    let other = ws.write_note("Other.md", "No baking here");
+    for path in [&recipe, &other] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+    // Results come from the index alone, even once the file is gone
+    fs::remove_file(&recipe).unwrap();
+
+    let results = note_manager::search_notes_fast("bread", &cache_db).expect("search should work");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, recipe);
+    assert_eq!(results[0].title, "Recipe");
+    assert_eq!(
+        results[0].snippet,
+        "Bake the &lt;<mark>bread</mark>&gt; &amp; serve #food"
+    );
+
+    let tagged = note_manager::search_notes_fast("tag:food", &cache_db).unwrap();
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].snippet, "");
+    assert!(note_manager::search_notes_fast("  ", &cache_db)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn link_suggestions_rank_recent_notes_first_and_include_aliases() {
+    let ws = TestWorkspace::new("link-suggestions");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let project = ws.write_note("Project Atlas.md", "---\naliases: [Atlas, Map]\n---\n");
+    let planning = ws.write_note("Planning.md", "");
+    let plants = ws.write_note("Garden/Plants.md", "");
+    ws.write_note("Zebra.md", "");
+    for path in note_manager::list_notes(notes_dir).unwrap() {
+        let content = fs::read_to_string(&path.path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(&path.path, &path.title, &content, notes_dir)
+            .unwrap();
+        let (secs, nanos) = crate::commands::get_file_mtime(&path.path).unwrap();
+        cache_db.set_cached_mtime(&path.path, secs, nanos).unwrap();
+    }
+
+    let suggest =
+        |prefix: &str, limit: usize, recent: &[String]| -> Vec<(String, Option<String>)> {
+            crate::commands::link_suggestions(prefix, limit, recent, &cache_db)
+                .expect("suggestions should load")
+                .into_iter()
+                .map(|s| (s.title, s.alias))
+                .collect()
+        };
+
+    assert_eq!(
+        suggest("pla", 10, &[]),
+        vec![("Planning".to_string(), None), ("Plants".to_string(), None)]
+    );
+    // Recently opened notes come first
+    assert_eq!(
+        suggest("PLA", 10, std::slice::from_ref(&plants)),
+        vec![("Plants".to_string(), None), ("Planning".to_string(), None)]
+    );
+    assert_eq!(
+        suggest("at", 10, &[]),
+        vec![("Project Atlas".to_string(), Some("Atlas".to_string()))]
+    );
+    assert_eq!(suggest("", 2, &[planning.clone(), project]).len(), 2);
+    assert_eq!(
+        suggest("", 2, std::slice::from_ref(&planning))[0].0,
+        "Planning"
+    );
+}
+
+#[test]
+fn configured_daily_notes_folder_is_used_everywhere() {
+    let ws = TestWorkspace::new("daily-notes-folder");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+    let settings = crate::commands::AppSettings {
+        daily_notes_folder: "Journal".to_string(),
+        ..Default::default()
+    };
+    ws.write_note(
+        ".plainflux/settings.json",
+        &serde_json::to_string(&settings).unwrap(),
+    );
+    assert_eq!(
+        crate::commands::daily_notes_folder(notes_dir).unwrap(),
+        "Journal"
+    );
+
+    let from = ws.write_note("Journal/2026-03-09.md", "## Tasks\n- [ ] carry me\n");
+    let lonely = ws.write_note("Projects/Lonely.md", "On my own\n");
+    ws.write_note("Daily Notes/Old.md", "no longer special\n");
+    for path in [&from, &lonely] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, notes_dir)
+            .unwrap();
+    }
+
+    let moved =
+        crate::commands::rollover_todos(notes_dir, "2026-03-09", "2026-03-10", "", &cache_db)
+            .unwrap();
+    assert_eq!(moved, 1);
+    assert!(ws.notes_dir.join("Journal/2026-03-10.md").exists());
+
+    let dates: Vec<_> = crate::commands::build_daily_note_entries(notes_dir, None)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.date)
+        .collect();
+    assert_eq!(dates, vec!["2026-03-09", "2026-03-10"]);
+
+    let weekly = note_manager::create_periodic_note(notes_dir, "Journal", "weekly", None).unwrap();
+    assert!(Path::new(&weekly).starts_with(ws.notes_dir.join("Journal/Weekly")));
+
+    // The configured folder is hidden from the folder list, the old one is not
+    assert_eq!(
+        note_manager::get_all_folders(notes_dir, "Journal").unwrap(),
+        vec!["Daily Notes", "Projects"]
+    );
+    let orphans: Vec<_> = crate::commands::find_orphan_notes(&cache_db, notes_dir, true)
+        .unwrap()
+        .into_iter()
+        .map(|note| note.title)
+        .collect();
+    assert_eq!(orphans, vec!["Old", "Lonely"]);
+
+    for bad in ["", "../Outside", "/abs", ".plainflux/daily", " Journal"] {
+        assert!(
+            crate::commands::validate_daily_notes_folder(bad).is_err(),
+            "{bad:?} should be rejected"
+        );
+    }
+    assert!(crate::commands::validate_daily_notes_folder("Journal/Daily").is_ok());
+
+    // A hand-edited settings file can't point daily notes outside the vault
+    let escaping = crate::commands::AppSettings {
+        daily_notes_folder: "../Outside".to_string(),
+        ..Default::default()
+    };
+    ws.write_note(
+        ".plainflux/settings.json",
+        &serde_json::to_string(&escaping).unwrap(),
+    );
+    assert_eq!(
+        crate::commands::daily_notes_folder(notes_dir).unwrap(),
+        note_manager::DEFAULT_DAILY_NOTES_FOLDER
+    );
+}
+
+#[test]
+fn convert_links_round_trips_aliases_and_block_refs() {
+    let ws = TestWorkspace::new("convert-links");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let road_map = ws.write_note(
+        "Projects/Road Map.md",
+        "# Road Map\n## Next Steps\nShip it ^ship\n",
+    );
+    ws.write_note("Inbox.md", "");
+    let original = "See [[Road Map]] and [[Road Map|the plan]].\n\
+                    Jump to [[Road Map#Next Steps]] or [[Road Map#^ship|shipping]].\n\
+                    Keep [[Missing]], ![[Road Map]] and `[[Inbox]]`.\n\
+                    ```\n[[Inbox]]\n```\n";
+    let note = ws.write_note("Daily/Today.md", original);
+
+    let preview = crate::commands::convert_note_links(
+        &note,
+        crate::links::LinkStyle::Markdown,
+        true,
+        notes_dir,
+        &cache_db,
+    )
+    .unwrap();
+    assert_eq!(preview.len(), 4);
+    assert_eq!(preview[0].line_number, 1);
+    assert_eq!(preview[0].before, "[[Road Map]]");
+    assert_eq!(preview[0].after, "[Road Map](../Projects/Road%20Map.md)");
+    assert_eq!(fs::read_to_string(&note).unwrap(), original, "dry run");
+
+    crate::commands::convert_note_links(
+        &note,
+        crate::links::LinkStyle::Markdown,
+        false,
+        notes_dir,
+        &cache_db,
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "See [Road Map](../Projects/Road%20Map.md) and [the plan](../Projects/Road%20Map.md).\n\
+         Jump to [Road Map#Next Steps](../Projects/Road%20Map.md#next-steps) or [shipping](../Projects/Road%20Map.md#^ship).\n\
+         Keep [[Missing]], ![[Road Map]] and `[[Inbox]]`.\n\
+         ```\n[[Inbox]]\n```\n"
+    );
+
+    let back = crate::commands::convert_note_links(
+        &note,
+        crate::links::LinkStyle::Wikilink,
+        false,
+        notes_dir,
+        &cache_db,
+    )
+    .unwrap();
+    assert_eq!(back.len(), 4);
+    assert_eq!(fs::read_to_string(&note).unwrap(), original);
+    // An aliased wikilink alone still counts as a link
+    cache_db
+        .update_note_cache_with_fts(&note, "Today", "[[Road Map|the plan]]\n", notes_dir)
+        .unwrap();
+    assert_eq!(cache_db.get_backlinks(&road_map).unwrap(), vec![note]);
+}
+
+#[test]
+fn broken_links_skip_aliases_code_and_name_variants() {
+    let ws = TestWorkspace::new("broken-links");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let target = ws.write_note("Projects/Road Map.md", "---\naliases: [Plan]\n---\n");
+    let content = note_manager::read_file_with_encoding(&target).unwrap();
+    cache_db
+        .update_note_cache_with_fts(&target, "Road Map", &content, notes_dir)
+        .unwrap();
+    let note = ws.write_note(
+        "Today.md",
+        "See [[Road Map|the plan]] and [[road map.md#Next]].\n\
+         Also [[Plan]].\n\
+         ```\n[[Inside Fence]]\n```\n\
+         But [[Nowhere]] is missing.\n",
+    );
+
+    let notes = note_manager::list_notes(notes_dir).unwrap();
+    let broken = crate::commands::find_broken_links(&notes, &cache_db).unwrap();
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0].from_note, note);
+    assert_eq!(broken[0].target, "Nowhere");
+    assert_eq!(broken[0].line_number, 6);
+}
+
+#[test]
+fn duplicate_titles_are_grouped_and_local_links_preferred() {
+    let ws = TestWorkspace::new("duplicate-titles");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let root = ws.write_note("Meeting.md", "");
+    let work = ws.write_note("Work/meeting.md", "");
+    let home = ws.write_note("Home/Meeting.md", "");
+    let agenda = ws.write_note("Home/Agenda.md", "[[Meeting]]\n");
+    ws.write_note("Unique.md", "");
+
+    let groups =
+        crate::commands::group_duplicate_titles(note_manager::list_notes(notes_dir).unwrap());
+    assert_eq!(groups.len(), 1);
+    assert!(groups[0].title.eq_ignore_ascii_case("meeting"));
+    let mut expected = vec![root, work, home.clone()];
+    expected.sort();
+    assert_eq!(groups[0].paths, expected);
+
+    assert_eq!(
+        cache_db
+            .resolve_note_link("meeting", notes_dir, Some(&agenda))
+            .unwrap(),
+        home
+    );
+    // No same-folder match: falls back to the usual lookup
+    let unique = cache_db
+        .resolve_note_link("Unique", notes_dir, Some(&agenda))
+        .unwrap();
+    assert!(unique.ends_with("Unique.md"));
+
+    // The index resolves links the same way navigation does
+    cache_db
+        .update_note_cache_with_fts(
+            &agenda,
+            "Agenda",
+            "[[Meeting]]\n- [ ] Prep [[Meeting]]\n",
+            notes_dir,
+        )
+        .expect("failed to index note");
+    assert_eq!(cache_db.get_backlinks(&home).unwrap(), vec![agenda.clone()]);
+    let todo = cache_db.get_todo(&agenda, 2).unwrap();
+    assert_eq!(todo.links, vec![home]);
+}
+
+#[test]
+fn page_metadata_is_parsed_from_html_head() {
+    let html = r#"<!doctype html><html><head>
+        <meta charset="utf-8">
+        <TITLE>
+          Rust &amp; Friends &#8211; Home
+        </TITLE>
+        <meta content='A language &quot;empowering&quot; everyone' name="Description">
+        <meta property="og:description" content="ignored">
+        </head><body><title>Not this one</title></body></html>"#;
+    let metadata = crate::web::parse_page_metadata("https://example.com", html);
+    assert_eq!(metadata.title.as_deref(), Some("Rust & Friends – Home"));
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("A language \"empowering\" everyone")
+    );
+
+    let og_only = crate::web::parse_page_metadata(
+        "https://example.com",
+        r#"<meta property="og:title" content="Shared"><meta property="og:description" content="Card">"#,
+    );
+    assert_eq!(og_only.title.as_deref(), Some("Shared"));
+    assert_eq!(og_only.description.as_deref(), Some("Card"));
+
+    assert_eq!(
+        crate::web::parse_page_metadata("https://example.com", "plain text"),
+        crate::web::PageMetadata {
+            url: "https://example.com".to_string(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn metadata_fetching_respects_network_setting_and_scheme() {
+    let ws = TestWorkspace::new("network-setting");
+    let notes_dir = ws.notes_dir_str();
+    assert!(crate::commands::ensure_network_allowed(notes_dir).is_ok());
+
+    let settings = crate::commands::AppSettings {
+        allow_network_requests: false,
+        ..Default::default()
+    };
+    ws.write_note(
+        ".plainflux/settings.json",
+        &serde_json::to_string(&settings).unwrap(),
+    );
+    assert!(crate::commands::ensure_network_allowed(notes_dir).is_err());
+
+    for bad in ["file:///etc/passwd", "javascript:alert(1)", "not a url"] {
+        assert!(crate::web::validate_web_url(bad).is_err(), "{bad}");
+    }
+    assert!(crate::web::validate_web_url("https://example.com/page").is_ok());
+}
+
+#[test]
+fn favicon_cache_stores_icons_and_misses_per_domain() {
+    let ws = TestWorkspace::new("favicons");
+    let notes_dir = ws.notes_dir_str();
+
+    assert_eq!(
+        crate::favicons::validate_domain(" Docs.RS. ").unwrap(),
+        "docs.rs"
+    );
+    for bad in ["", "../etc", "a/b", "a..b", "-bad.com", "exa mple.com"] {
+        assert!(crate::favicons::validate_domain(bad).is_err(), "{bad:?}");
+    }
+
+    assert!(crate::favicons::cached_favicon(notes_dir, "example.com", 30).is_none());
+    let stored = crate::favicons::store_favicon(notes_dir, "example.com", None).unwrap();
+    assert!(stored.is_none());
+    let (marker, expired) = crate::favicons::cached_favicon(notes_dir, "example.com", 30).unwrap();
+    assert!(crate::favicons::is_missing_marker(&marker));
+    assert!(!expired);
+    // A zero-day lifetime is already expired
+    assert!(crate::favicons::cached_favicon(notes_dir, "example.com", 0)
+        .is_some_and(|(_, expired)| expired));
+
+    // Storing an icon replaces the marker; other domains are unaffected
+    let icon =
+        crate::favicons::store_favicon(notes_dir, "example.com", Some((vec![1, 2, 3], "png")))
+            .unwrap()
+            .unwrap();
+    assert!(icon.ends_with("example.com.png"));
+    assert!(!marker.exists());
+    assert!(crate::favicons::cached_favicon(notes_dir, "example", 30).is_none());
+    assert!(crate::favicons::cached_favicon(notes_dir, "sub.example.com", 30).is_none());
+
+    assert_eq!(
+        crate::favicons::find_icon_link(
+            r#"<link rel="stylesheet" href="a.css"><LINK REL="shortcut icon" HREF='/static/fav.png'>"#
+        )
+        .as_deref(),
+        Some("/static/fav.png")
+    );
+    assert!(
+        crate::favicons::find_icon_link(r#"<link rel="apple-touch-icon" href="x.png">"#).is_none()
+    );
+}
+
+#[test]
+fn bookmarks_export_to_netscape_html() {
+    let ws = TestWorkspace::new("bookmark-export");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let reading = ws.write_note(
+        "Lists/Reading.md",
+        "[Rust & Co](https://www.rust-lang.org/) #lang #tools\nhttps://docs.rs/regex\n",
+    );
+    let content = fs::read_to_string(&reading).unwrap();
+    cache_db
+        .update_note_cache_with_fts(&reading, "Reading", &content, notes_dir)
+        .unwrap();
+    cache_db
+        .add_bookmark(
+            "https://docs.rs/regex",
+            None,
+            Some("Regex <docs>"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+    let out = ws.root.join("export/bookmarks.html");
+    let count = crate::export::export_bookmarks(
+        &cache_db,
+        notes_dir,
+        &out.to_string_lossy(),
+        crate::export::BookmarkGrouping::Note,
+    )
+    .unwrap();
+    assert_eq!(count, 3);
+    let html = fs::read_to_string(&out).unwrap();
+    assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+    assert!(html.contains("<DT><H3>Lists/Reading</H3>"));
+    assert!(html.contains("<DT><H3>Unfiled</H3>"));
+    assert!(html.contains(r#"HREF="https://www.rust-lang.org/""#));
+    assert!(html.contains(r#"TAGS="lang,tools">Rust &amp; Co</A>"#));
+    // Missing titles fall back to the URL
+    assert!(html.contains(r#">https://docs.rs/regex</A>"#));
+    assert!(html.contains("<DD>Regex &lt;docs&gt;"));
+
+    // By domain, the URL found in the note and added manually is listed once
+    let (by_domain, count) = crate::export::render_bookmarks_html(
+        &cache_db.get_all_bookmarks().unwrap(),
+        notes_dir,
+        crate::export::BookmarkGrouping::Domain,
+    );
+    assert_eq!(count, 2);
+    assert!(by_domain.contains("<DT><H3>docs.rs</H3>"));
+    assert!(by_domain.contains("<DT><H3>rust-lang.org</H3>"));
+}
+
+#[test]
+fn read_note_exposes_frontmatter_style() {
+    let ws = TestWorkspace::new("note-style");
+
+    let styled = ws.write_note(
+        "Wide.md",
+        "---\ncssclasses:\n  - wide-table\n  - \"dense\"\ncssclass: wide-table 9bad <script>\ntheme: Light\n---\n# Table\n",
+    );
+    assert_eq!(
+        note_manager::read_note(&styled).unwrap().style,
+        Some(note_manager::NoteStyle {
+            css_classes: vec!["wide-table".to_string(), "dense".to_string()],
+            theme: Some("light".to_string()),
+        })
+    );
+
+    let unknown_theme = ws.write_note("Odd.md", "---\ntheme: solarized\n---\nBody\n");
+    assert_eq!(note_manager::read_note(&unknown_theme).unwrap().style, None);
+
+    let plain = ws.write_note("Plain.md", "theme: dark\n");
+    assert_eq!(note_manager::read_note(&plain).unwrap().style, None);
+}
+
+#[test]
+fn settings_reject_bad_theme_colors_and_clamp_font_size() {
+    let theme = crate::commands::CustomTheme {
+        bg_primary: "#1e1e1e".to_string(),
+        bg_secondary: "#12345".to_string(),
+        text_primary: "rgb(212, 212, 212)".to_string(),
+        text_secondary: "rgba(0, 0, 0, 0.5)".to_string(),
+        border_color: "#ABC".to_string(),
+        accent_color: "RebeccaPurple".to_string(),
+        hover_color: "not-a-color".to_string(),
+        active_color: "rgb(300, 0, 0)".to_string(),
+    };
+    let settings = crate::commands::AppSettings {
+        custom_theme: Some(theme.clone()),
+        ..Default::default()
+    };
+    let err: String = crate::commands::normalize_app_settings(settings)
+        .unwrap_err()
+        .into();
+    assert_eq!(
+        err,
+        "Invalid input: Invalid custom theme colors: bg_secondary, hover_color, active_color"
+    );
+
+    let fixed = crate::commands::CustomTheme {
+        bg_secondary: "#123456".to_string(),
+        hover_color: "#2a2d2e80".to_string(),
+        active_color: "rgb(100%, 0%, 0%)".to_string(),
+        ..theme
+    };
+    for (font_size, expected) in [(4, 12), (14, 14), (99, 24)] {
+        let settings = crate::commands::AppSettings {
+            font_size,
+            custom_theme: Some(fixed.clone()),
+            ..Default::default()
+        };
+        let normalized = crate::commands::normalize_app_settings(settings).unwrap();
+        assert_eq!(normalized.font_size, expected);
+    }
+}
+
+#[test]
+fn settings_bundle_round_trips_and_merges_without_clobbering() {
+    let source = TestWorkspace::new("settings_bundle_source");
+    let source_dir = source.notes_dir_str();
+    let source_settings = crate::commands::AppSettings {
+        font_size: 16,
+        daily_notes_folder: "Journal".to_string(),
+        window_width: Some(800.0),
+        ..Default::default()
+    };
+    crate::commands::write_app_settings(source_dir, &source_settings).unwrap();
+    source.write_note(".plainflux/daily_note_template.md", "# {{date}}\n");
+    source.write_note(".plainflux/templates/Meeting.md", "## Attendees\n");
+    source.write_note(".plainflux/templates/Shared.md", "from source\n");
+    crate::commands::add_saved_search(source_dir, "Work", "tag:work").unwrap();
+    crate::commands::add_saved_search(source_dir, "Todo", "TODO").unwrap();
+
+    let bundle = crate::commands::build_settings_bundle(source_dir).unwrap();
+    assert_eq!(
+        bundle.templates.keys().collect::<Vec<_>>(),
+        [
+            "daily_note_template.md",
+            "templates/Meeting.md",
+            "templates/Shared.md"
+        ]
+    );
+    let json = serde_json::to_string(&bundle).unwrap();
+
+    let target = TestWorkspace::new("settings_bundle_target");
+    let target_dir = target.notes_dir_str();
+    crate::commands::write_app_settings(
+        target_dir,
+        &crate::commands::AppSettings {
+            window_width: Some(1440.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    target.write_note(".plainflux/templates/Shared.md", "kept\n");
+    crate::commands::add_saved_search(target_dir, "work", "tag:job").unwrap();
+
+    let parsed = crate::commands::parse_settings_bundle(&json).unwrap();
+    let summary = crate::commands::apply_settings_bundle(target_dir, parsed).unwrap();
+    assert!(summary.settings_applied);
+    assert_eq!(summary.templates_imported, 2);
+    assert_eq!(summary.saved_searches_imported, 1);
+    assert_eq!(
+        summary.conflicts,
+        ["Saved search: Work", "Template: templates/Shared.md"]
+    );
+
+    let settings = crate::commands::load_app_settings(target_dir).unwrap();
+    assert_eq!(settings.font_size, 16);
+    assert_eq!(settings.daily_notes_folder, "Journal");
+    assert_eq!(settings.window_width, Some(1440.0));
+    let config_dir = target.notes_dir.join(".plainflux");
+    assert_eq!(
+        fs::read_to_string(config_dir.join("templates/Shared.md")).unwrap(),
+        "kept\n"
+    );
+    assert_eq!(
+        fs::read_to_string(config_dir.join("templates/Meeting.md")).unwrap(),
+        "## Attendees\n"
+    );
+    let searches = crate::commands::load_saved_searches(target_dir).unwrap();
+    assert_eq!(searches.get("work").map(String::as_str), Some("tag:job"));
+    assert_eq!(searches.get("Todo").map(String::as_str), Some("TODO"));
+
+    for bad in [
+        r#"{"format":"something-else","version":1,"settings":null}"#,
+        r#"{"format":"plainflux-settings","version":99,"settings":null}"#,
+        r#"{"format":"plainflux-settings","version":1,"settings":null,"templates":{"../../escape.md":""}}"#,
+        r#"{"format":"plainflux-settings","version":1,"settings":null,"templates":{"settings.json":"{}"}}"#,
+        r#"{"format":"plainflux-settings","version":1,"settings":null,"saved_searches":{"  ":"q"}}"#,
+    ] {
+        assert!(
+            crate::commands::parse_settings_bundle(bad).is_err(),
+            "{bad}"
+        );
+    }
+
+    // Settings that would be rejected when applied are rejected before
+    // anything is written
+    let mut bad_author = crate::commands::build_settings_bundle(source_dir).unwrap();
+    bad_author.settings.as_mut().unwrap().git_author_email = Some("not an email".to_string());
+    let unwritten = TestWorkspace::new("settings_bundle_bad_author");
+    assert!(crate::commands::apply_settings_bundle(unwritten.notes_dir_str(), bad_author).is_err());
+    assert!(!unwritten.notes_dir.join(".plainflux").exists());
+    let bad_tokenizer = json.replace(
+        "\"search_tokenizer\":\"porter\"",
+        "\"search_tokenizer\":\"bogus\"",
+    );
+    assert_ne!(bad_tokenizer, json);
+    assert!(crate::commands::parse_settings_bundle(&bad_tokenizer).is_err());
+}
+
+#[test]
+fn graph_exports_escape_labels_for_dot_and_graphml() {
+    let workspace = TestWorkspace::new("graph_export");
+    let cache_db = workspace.create_cache();
+    let quoted = workspace.write_note(
+        "Say \"hi\" & <bye>.md",
+        "#greeting/casual links to [[Plain]]",
+    );
+    let plain = workspace.write_note("Folder/Plain.md", "No links back");
+    for path in [&quoted, &plain] {
+        let content = fs::read_to_string(path).unwrap();
+        cache_db
+            .update_note_cache(path, &content, workspace.notes_dir_str())
+            .unwrap();
+    }
+
+    let graph =
+        crate::commands::build_global_graph(&cache_db, workspace.notes_dir_str(), true).unwrap();
+    let notes_dir = workspace.notes_dir_str();
+
+    let dot = crate::export::render_graph_dot(&graph, notes_dir);
+    assert!(dot.starts_with("digraph notes {\n"));
+    assert!(dot.contains(
+        r#""Say \"hi\" & <bye>.md" [label="Say \"hi\" & <bye>", tags="greeting;greeting/casual"];"#
+    ));
+    assert!(dot.contains(r#""Say \"hi\" & <bye>.md" -> "Folder/Plain.md";"#));
+    assert!(!dot.contains(notes_dir));
+
+    let graphml = crate::export::render_graph_graphml(&graph, notes_dir);
+    assert!(graphml.contains(r#"<node id="Say &quot;hi&quot; &amp; &lt;bye&gt;.md">"#));
+    assert!(graphml.contains(r#"<data key="label">Say &quot;hi&quot; &amp; &lt;bye&gt;</data>"#));
+    assert!(graphml.contains(r#"<data key="tags">greeting;greeting/casual</data>"#));
+    assert!(graphml.contains(
+        r#"<edge source="Say &quot;hi&quot; &amp; &lt;bye&gt;.md" target="Folder/Plain.md"/>"#
+    ));
+
+    let out = workspace.root.join("graph.graphml");
+    let written = crate::export::export_graph(
+        &graph,
+        notes_dir,
+        out.to_str().unwrap(),
+        crate::export::GraphFormat::Graphml,
+    )
+    .unwrap();
+    assert_eq!(written, 2);
+    assert_eq!(fs::read_to_string(out).unwrap(), graphml);
+}
+
+#[test]
+fn connection_path_follows_links_in_either_direction() {
+    let ws = TestWorkspace::new("connection-path");
+    let cache_db = ws.create_cache();
+
+    let a_path = ws.write_note("A.md", "Links to [[B]]\n");
+    let b_path = ws.write_note("B.md", "Nothing outgoing\n");
+    let c_path = ws.write_note("C.md", "Links to [[B]]\n");
+    let d_path = ws.write_note("D.md", "Alone\n");
+    for path in [&a_path, &b_path, &c_path, &d_path] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let connection = crate::commands::find_connection_path(&cache_db, &a_path, &c_path).unwrap();
+    assert_eq!(connection.path, Some(vec![a_path.clone(), b_path, c_path]));
+    assert_eq!(connection.explored, 3);
+
+    let connection = crate::commands::find_connection_path(&cache_db, &a_path, &a_path).unwrap();
+    assert_eq!(connection.path, Some(vec![a_path.clone()]));
+
+    let connection = crate::commands::find_connection_path(&cache_db, &a_path, &d_path).unwrap();
+    assert_eq!(connection.path, None);
+    assert_eq!(connection.explored, 3);
+}
+
+#[test]
+fn note_link_stats_rank_by_incoming_or_outgoing_links() {
+    let ws = TestWorkspace::new("note-link-stats");
+    let cache_db = ws.create_cache();
+
+    let hub = ws.write_note("Hub.md", "[[Popular]] [[Other]] [[Hub]]\n");
+    let popular = ws.write_note("Popular.md", "Nothing outgoing\n");
+    let other = ws.write_note("Other.md", "[[Popular]]\n");
+    let lonely = ws.write_note("Lonely.md", "No links\n");
+    for path in [&hub, &popular, &other, &lonely] {
+        let content = note_manager::read_file_with_encoding(path).expect("failed to read note");
+        cache_db
+            .update_note_cache_with_fts(path, &title_from_path(path), &content, ws.notes_dir_str())
+            .expect("failed to index note");
+    }
+
+    let counts = |sort, include_unlinked| {
+        crate::commands::note_link_counts(&cache_db, ws.notes_dir_str(), sort, include_unlinked)
+            .unwrap()
+            .into_iter()
+            .map(|note| (note.title, note.incoming, note.outgoing))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        counts(crate::commands::LinkStatsSort::IncomingDesc, false),
+        [
+            ("Popular".to_string(), 2, 0),
+            ("Other".to_string(), 1, 1),
+            ("Hub".to_string(), 0, 2),
+        ]
+    );
+    assert_eq!(
+        counts(crate::commands::LinkStatsSort::OutgoingDesc, true),
+        [
+            ("Hub".to_string(), 0, 2),
+            ("Other".to_string(), 1, 1),
+            ("Popular".to_string(), 2, 0),
+            ("Lonely".to_string(), 0, 0),
+        ]
+    );
+}
+
+#[test]
+fn update_note_cache_inserts_repeated_tags_and_links_once() {
+    let ws = TestWorkspace::new("index-dedupe");
+    let db = ws.create_cache();
+    ws.write_note("Other.md", "");
+
+    // Count insert attempts, including ones OR IGNORE would drop
+    db.conn()
+        .execute_batch(
+            "CREATE TEMP TABLE insert_attempts (tbl TEXT);
+             CREATE TEMP TRIGGER count_tag_inserts BEFORE INSERT ON tags
+             BEGIN INSERT INTO insert_attempts VALUES ('tags'); END;
+             CREATE TEMP TRIGGER count_link_inserts BEFORE INSERT ON links
+             BEGIN INSERT INTO insert_attempts VALUES ('links'); END;",
+        )
+        .unwrap();
+    let attempts = |table: &str| -> i64 {
+        db.conn()
+            .query_row(
+                "SELECT COUNT(*) FROM insert_attempts WHERE tbl = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .unwrap()
+    };
+
+    let content = format!(
+        "{}\n[[Other]] [[Other#^block]] [[other]]\n",
+        "#work ".repeat(10)
+    );
+    db.update_note_cache("/notes/Note.md", &content, ws.notes_dir_str())
+        .unwrap();
+
+    assert_eq!(attempts("tags"), 1);
+    assert_eq!(db.get_all_tags().unwrap(), ["work"]);
+    assert_eq!(attempts("links"), 1);
+    assert_eq!(db.get_all_links().unwrap().len(), 1);
+}
+
+#[test]
+fn rebuild_all_indexes_the_same_as_per_note_updates() {
+    let per_note_ws = TestWorkspace::new("rebuild-per-note");
+    let bulk_ws = TestWorkspace::new("rebuild-bulk");
+
+    let notes_for = |ws: &TestWorkspace| -> Vec<(String, String, String)> {
+        (0..500)
+            .map(|i| {
+                let content = format!(
+                    "# Note {i}\n\nSee https://example.com/{i} #topic{}\n- [ ] task {i}\n",
+                    i % 10
+                );
+                let path = ws.write_note(&format!("Note {i}.md"), &content);
+                (path, format!("Note {i}"), content)
+            })
+            .collect()
+    };
+
+    let per_note_notes = notes_for(&per_note_ws);
+    let per_note_db = per_note_ws.create_cache();
+    for (path, title, content) in &per_note_notes {
+        per_note_db
+            .update_note_cache_with_fts(path, title, content, per_note_ws.notes_dir_str())
+            .unwrap();
+    }
+
+    let bulk_notes = notes_for(&bulk_ws);
+    let bulk_db = bulk_ws.create_cache();
+    let stale = bulk_ws.notes_dir.join("Deleted.md");
+    let stale = stale.to_str().unwrap();
+    bulk_db
+        .update_note_cache_with_fts(stale, "Deleted", "gone #topic0", bulk_ws.notes_dir_str())
+        .unwrap();
+    let indexed = bulk_db
+        .rebuild_all(&bulk_notes, bulk_ws.notes_dir_str())
+        .unwrap();
+
+    assert_eq!(indexed, 500);
+    for db in [&per_note_db, &bulk_db] {
+        assert_eq!(db.get_all_bookmarks().unwrap().len(), 500);
+        assert_eq!(db.get_notes_by_tag("topic0").unwrap().len(), 50);
+        assert_eq!(db.get_incomplete_todos().unwrap().len(), 500);
+    }
+    assert_eq!(
+        bulk_db.search_notes_fts("\"task 123\"").unwrap(),
+        [bulk_notes[123].0.clone()]
+    );
+    assert!(bulk_db.search_notes_fts("gone").unwrap().is_empty());
+}
+
+#[test]
+fn background_sync_reports_progress_and_releases_the_cache_between_notes() {
+    let ws = TestWorkspace::new("sync-progress");
+    for i in 0..60 {
+        ws.write_note(&format!("Note {i}.md"), &format!("searchable {i}\n"));
+    }
+    let state = crate::commands::AppState::new(
+        ws.create_cache(),
+        crate::git_manager::GitManager::new(ws.notes_dir_str()),
+        ws.notes_dir_str().to_string(),
+    );
+
+    let mut progress = Vec::new();
+    crate::sync_cache_with_progress(&state, |done, total| {
+        // Commands can use whatever has been indexed so far
+        let cache_db = state
+            .cache_db
+            .try_lock()
+            .expect("cache should not be locked between notes");
+        let indexed = cache_db.search_notes_fts("searchable").unwrap().len();
+        progress.push((done, total, indexed));
+    })
+    .unwrap();
+
+    assert_eq!(progress, [(0, 60, 0), (50, 60, 50), (60, 60, 60)]);
+}
+
+#[test]
+fn optimize_reclaims_space_from_deleted_notes() {
+    let ws = TestWorkspace::new("cache-optimize");
+    let db = ws.create_cache();
+    let body = "lorem ipsum dolor sit amet ".repeat(200);
+    for i in 0..200 {
+        db.add_note_content(&format!("/notes/{i}.md"), "Note", &body)
+            .unwrap();
+    }
+    db.add_note_content("/notes/keep.md", "Keep", "still searchable")
+        .unwrap();
+    for i in 0..200 {
+        db.remove_note_content(&format!("/notes/{i}.md")).unwrap();
+    }
+
+    let (before, after) = db.optimize().unwrap();
+    assert!(after < before, "{after} should be below {before}");
+    assert_eq!(
+        db.search_notes_fts("searchable").unwrap(),
+        ["/notes/keep.md"]
+    );
+    assert_eq!(
+        CacheDb::new(":memory:").unwrap().optimize().unwrap(),
+        (0, 0)
+    );
+}
+
+#[test]
+fn open_or_recreate_sets_a_corrupted_database_aside() {
+    let ws = TestWorkspace::new("cache-corrupt");
+    let db_path = ws.db_path.to_str().unwrap();
+    let junk = b"this is not a sqlite database, just junk bytes";
+    fs::write(&ws.db_path, junk).unwrap();
+    assert!(CacheDb::new(db_path).is_err());
+
+    let db = CacheDb::open_or_recreate(db_path).unwrap();
+    db.check_integrity().unwrap();
+    // The bad file is kept next to the new one, not deleted
+    let quarantined: Vec<_> = fs::read_dir(&ws.root)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.to_string_lossy()
+                .starts_with(&format!("{db_path}.corrupt-"))
+        })
+        .collect();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(fs::read(&quarantined[0]).unwrap(), junk);
+    db.add_note_content("/notes/a.md", "A", "fresh start")
+        .unwrap();
+    assert_eq!(db.search_notes_fts("fresh").unwrap(), ["/notes/a.md"]);
+    drop(db);
+
+    // A healthy cache is opened as it is
+    let db = CacheDb::open_or_recreate(db_path).unwrap();
+    assert_eq!(db.search_notes_fts("fresh").unwrap(), ["/notes/a.md"]);
+
+    // A path that can't be opened at all isn't corruption
+    assert!(CacheDb::open_or_recreate(ws.notes_dir_str()).is_err());
+    assert!(ws.notes_dir.is_dir());
+}
+
+#[test]
+fn optimize_is_refused_while_a_sync_or_rebuild_runs() {
+    let ws = TestWorkspace::new("optimize-during-sync");
+    ws.write_note("Note.md", "hello\n");
+    let state = crate::commands::AppState::new(
+        ws.create_cache(),
+        crate::git_manager::GitManager::new(ws.notes_dir_str()),
+        ws.notes_dir_str().to_string(),
+    );
+
+    let mut attempts = Vec::new();
+    crate::sync_cache_with_progress(&state, |_, _| {
+        attempts.push(crate::commands::optimize_cache_db(&state).is_err());
+    })
+    .unwrap();
+    crate::force_rebuild_cache(&state, |_, _| {
+        attempts.push(state.cache_job_running());
+    })
+    .unwrap();
+    assert!(!attempts.is_empty());
+    assert!(attempts.iter().all(|refused| *refused));
+
+    assert!(!state.cache_job_running());
+    crate::commands::optimize_cache_db(&state).unwrap();
+}
+
+#[test]
+fn cache_opens_with_wal_and_busy_timeout() {
+    let ws = TestWorkspace::new("cache-wal");
+    let db = ws.create_cache();
+    let pragma = |name: &str| -> String {
+        db.conn()
+            .query_row(&format!("PRAGMA {name}"), [], |row| {
+                row.get::<_, rusqlite::types::Value>(0)
+            })
+            .map(|value| match value {
+                rusqlite::types::Value::Integer(n) => n.to_string(),
+                rusqlite::types::Value::Text(text) => text,
+                other => format!("{other:?}"),
+            })
+            .unwrap()
+    };
+
+    assert_eq!(pragma("journal_mode"), "wal");
+    assert_eq!(pragma("busy_timeout"), "5000");
+    assert_eq!(pragma("cache_size"), "-16000");
+}
+
+#[test]
+fn upgrading_an_old_cache_reindexes_every_note() {
+    let ws = TestWorkspace::new("cache-upgrade");
+    let note = ws.write_note("Alpha.md", "---\naliases: [Gamma]\n---\n");
+    {
+        let db = ws.create_cache();
+        db.set_cached_mtime(&note, 1, 0).unwrap();
+        db.conn().execute("DROP TABLE aliases", []).unwrap();
+    }
+
+    let db = ws.create_cache();
+    assert_eq!(db.get_cached_mtime(&note).unwrap(), None);
+
+    // Same for todos cached before scheduled dates were
+    db.set_cached_mtime(&note, 1, 0).unwrap();
+    db.conn()
+        .execute("ALTER TABLE todos DROP COLUMN scheduled_date", [])
+        .unwrap();
+    drop(db);
+    let db = ws.create_cache();
+    assert_eq!(db.get_cached_mtime(&note).unwrap(), None);
+
+    // Reopening an up-to-date cache keeps what it knows
+    db.set_cached_mtime(&note, 1, 0).unwrap();
+    drop(db);
+    assert_eq!(
+        ws.create_cache().get_cached_mtime(&note).unwrap(),
+        Some((1, 0))
+    );
+}
+
+#[test]
+fn cancelled_rebuild_keeps_progress_and_next_sync_finishes_it() {
+    use std::sync::atomic::Ordering;
+
+    let ws = TestWorkspace::new("rebuild-cancel");
+    let paths: Vec<String> = (0..60)
+        .map(|i| ws.write_note(&format!("Note {i}.md"), "stale words\n"))
+        .collect();
+    let state = crate::commands::AppState::new(
+        ws.create_cache(),
+        crate::git_manager::GitManager::new(ws.notes_dir_str()),
+        ws.notes_dir_str().to_string(),
+    );
+    crate::sync_cache(&state).unwrap();
+    for path in &paths {
+        fs::write(path, "fresh words\n").unwrap();
+    }
+
+    let mut progress = Vec::new();
+    let result = crate::force_rebuild_cache(&state, |done, total| {
+        progress.push((done, total));
+        if done == 50 {
+            state.rebuild_cancelled.store(true, Ordering::SeqCst);
+        }
+    })
+    .unwrap();
+    assert_eq!(
+        (result.processed, result.total, result.cancelled),
+        (50, 60, true)
+    );
+    assert_eq!(progress, [(0, 60), (50, 60), (50, 60)]);
+
+    let cache_db = lock_mutex!(state.cache_db);
+    assert_eq!(cache_db.search_notes_fts("fresh").unwrap().len(), 50);
+    assert_eq!(cache_db.search_notes_fts("stale").unwrap().len(), 10);
+    let unprocessed = paths
+        .iter()
+        .filter(|path| cache_db.get_cached_mtime(path).unwrap().is_none())
+        .count();
+    assert_eq!(unprocessed, 10);
+    drop(cache_db);
+
+    crate::sync_cache(&state).unwrap();
+    let cache_db = lock_mutex!(state.cache_db);
+    assert_eq!(cache_db.search_notes_fts("fresh").unwrap().len(), 60);
+    assert!(cache_db.search_notes_fts("stale").unwrap().is_empty());
+    drop(cache_db);
+
+    // The flag is reset when the next rebuild starts
+    let result = crate::force_rebuild_cache(&state, |_, _| {}).unwrap();
+    assert_eq!((result.processed, result.cancelled), (60, false));
+}
+
+#[test]
+fn huge_autocommit_minutes_do_not_overflow() {
+    let ws = TestWorkspace::new("autocommit-overflow");
+    let git_manager = crate::git_manager::GitManager::new(ws.notes_dir_str());
+    git_manager.set_autocommit_minutes(Some(u64::MAX));
+    git_manager.set_autocommit_minutes(Some(0));
+}
+
+#[tokio::test]
+async fn debounced_auto_commit_captures_several_changes_in_one_commit() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let ws = TestWorkspace::new("git-debounce");
+    let notes_dir = ws.notes_dir_str().to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+    git_manager.set_commit_delay(Some(Duration::from_millis(300)));
+    let git_manager = Arc::new(Mutex::new(git_manager));
+
+    let commit_count = || {
+        let repo = git2::Repository::open(&notes_dir).unwrap();
+        let mut walk = repo.revwalk().unwrap();
+        if walk.push_head().is_err() {
+            return 0;
+        }
+        walk.count()
+    };
+    let commits_before = commit_count();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let schedule = || {
+        let manager = git_manager.clone();
+        let attempts = attempts.clone();
+        git_manager
+            .lock()
+            .unwrap()
+            .schedule_commit(Duration::from_millis(50), move || {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                manager
+                    .lock()
+                    .unwrap()
+                    .commit_changes(None)
+                    .expect("commit should succeed");
+            });
+    };
+
+    for name in ["A.md", "B.md", "C.md"] {
+        ws.write_note(name, "content\n");
+        schedule();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    for _ in 0..50 {
+        if attempts.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    // Give a stray second commit the chance to show up
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    assert_eq!(commit_count(), commits_before + 1);
+
+    let repo = git2::Repository::open(&notes_dir).unwrap();
+    let tree = repo.head().unwrap().peel_to_tree().unwrap();
+    for name in ["A.md", "B.md", "C.md"] {
+        assert!(tree.get_name(name).is_some(), "{name} should be committed");
+    }
+}
+
+#[test]
+fn git_blame_is_cached_until_head_moves() {
+    let ws = TestWorkspace::new("git-blame-cache");
+    let notes_dir = ws
+        .notes_dir
+        .canonicalize()
+        .expect("notes dir should exist")
+        .to_string_lossy()
+        .to_string();
+
+    let mut git_manager = crate::git_manager::GitManager::new(&notes_dir);
+    git_manager.init_repo().expect("failed to init repo");
+
+    let note_path = format!("{notes_dir}/Note.md");
+    fs::write(&note_path, "one\ntwo\nthree\n").unwrap();
+    git_manager
+        .commit_changes(Some("initial"))
+        .expect("commit should succeed");
+
+    let first = git_manager.get_blame_info(&note_path).unwrap();
+    let second = git_manager.get_blame_info(&note_path).unwrap();
+    assert_eq!(first.len(), 3);
+    assert_eq!(
+        first.iter().map(|b| &b.commit_hash).collect::<Vec<_>>(),
+        second.iter().map(|b| &b.commit_hash).collect::<Vec<_>>()
+    );
+    assert_eq!(git_manager.blame_cache_misses(), 1);
+
+    // Uncommitted edits don't invalidate; only lines on disk are reported
+    fs::write(&note_path, "one\ntwo\n").unwrap();
+    assert_eq!(git_manager.get_blame_info(&note_path).unwrap().len(), 2);
+    assert_eq!(git_manager.blame_cache_misses(), 1);
+
+    fs::write(&note_path, "one\ntwo\nfour\n").unwrap();
+    git_manager
+        .commit_changes(Some("edit"))
+        .expect("commit should succeed");
+    let after = git_manager.get_blame_info(&note_path).unwrap();
+    assert_eq!(git_manager.blame_cache_misses(), 2);
+    assert_eq!(after.len(), 3);
+    assert_eq!(after[0].summary, "initial");
+    assert_eq!(after[2].summary, "edit");
+}
+
+#[test]
+fn fold_state_follows_moved_notes_and_prunes_missing_ones() {
+    let ws = TestWorkspace::new("fold-state");
+    let notes_dir = ws.notes_dir_str();
+    let note = ws.write_note("Folder/Note.md", "# A\n## B\n# C\n");
+    let gone = ws.write_note("Gone.md", "# Gone\n");
+
+    crate::folds::save_fold_state(&note, vec![3, 1, 3], notes_dir).unwrap();
+    crate::folds::save_fold_state(&gone, vec![1], notes_dir).unwrap();
+    assert_eq!(
+        crate::folds::get_fold_state(&note, notes_dir).unwrap(),
+        [1, 3]
+    );
+
+    fs::remove_file(&gone).unwrap();
+    assert!(crate::folds::get_fold_state(&gone, notes_dir)
+        .unwrap()
+        .is_empty());
+    let stored = fs::read_to_string(ws.notes_dir.join(".plainflux/folds.json")).unwrap();
+    assert!(!stored.contains("Gone.md"));
+
+    // Renaming the folder carries the state of the notes inside it
+    fs::rename(ws.notes_dir.join("Folder"), ws.notes_dir.join("Renamed")).unwrap();
+    let old_folder = format!("{notes_dir}/Folder");
+    let new_folder = format!("{notes_dir}/Renamed");
+    crate::folds::move_fold_state(&old_folder, &new_folder, notes_dir).unwrap();
+    let moved = format!("{new_folder}/Note.md");
+    assert_eq!(
+        crate::folds::get_fold_state(&moved, notes_dir).unwrap(),
+        [1, 3]
+    );
+
+    crate::folds::save_fold_state(&moved, Vec::new(), notes_dir).unwrap();
+    assert!(crate::folds::get_fold_state(&moved, notes_dir)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn notes_report_a_created_time() {
+    let ws = TestWorkspace::new("created-time");
+    let path = ws.write_note("Note.md", "hello\n");
+
+    let listed = note_manager::list_notes(ws.notes_dir_str()).unwrap();
+    let note = note_manager::read_note(&path).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(note.created > 0);
+    assert!(note.created <= note.last_modified);
+    assert_eq!(listed[0].created, note.created);
+}
+
+#[test]
+fn created_time_is_the_first_one_recorded_and_follows_moves() {
+    use crate::note_manager::SortBy;
+
+    let ws = TestWorkspace::new("created-time-recorded");
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+    let old = ws.write_note("Old.md", "first\n");
+    let fresh = ws.write_note("Fresh.md", "second\n");
+    let titles = |page: &crate::commands::NotesPage| {
+        page.notes
+            .iter()
+            .map(|note| (note.title.clone(), note.created))
+            .collect::<Vec<_>>()
+    };
+
+    // Pretend the old note was first seen long ago; saving it again must
+    // not move it to the top of the newest-first list
+    assert_eq!(cache_db.record_created_time(&old, 1_000).unwrap(), 1_000);
+    note_manager::write_note(&old, "edited\n").unwrap();
+    let page =
+        crate::commands::notes_page(notes_dir, &cache_db, 0, 10, SortBy::CreatedDesc).unwrap();
+    let listed = titles(&page);
+    assert_eq!(listed[1], ("Old".to_string(), 1_000));
+    assert_eq!(
+        cache_db.record_created_time(&fresh, 0).unwrap(),
+        listed[0].1,
+        "first listing records the filesystem time"
+    );
+
+    // A move carries the recorded time along
+    let renamed = note_manager::rename_note(&old, "Renamed").unwrap();
+    cache_db.move_created_time(&old, &renamed).unwrap();
+    let page =
+        crate::commands::notes_page(notes_dir, &cache_db, 0, 10, SortBy::CreatedDesc).unwrap();
+    assert_eq!(titles(&page)[1], ("Renamed".to_string(), 1_000));
+
+    // Deleted notes are forgotten, so a new note at the same path starts over
+    fs::remove_file(&renamed).unwrap();
+    crate::commands::notes_page(notes_dir, &cache_db, 0, 10, SortBy::CreatedDesc).unwrap();
+    assert_eq!(cache_db.record_created_time(&renamed, 5).unwrap(), 5);
+}
+
+#[test]
+fn notes_list_sorts_by_requested_order() {
+    use crate::note_manager::{sort_notes, SortBy};
+
+    let ws = TestWorkspace::new("notes-sort");
+    ws.write_note("b/Apple.md", "z\n");
+    ws.write_note("a/beta.md", "b\n");
+    ws.write_note("Zeta.md", "a\n");
+    let mut notes = note_manager::list_notes(ws.notes_dir_str()).unwrap();
+    for (i, note) in notes.iter_mut().enumerate() {
+        note.last_modified = 100 + i as i64;
+        note.created = 200 - i as i64;
+    }
+    let titles = |notes: &[note_manager::NoteMetadata]| {
+        notes.iter().map(|n| n.title.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(titles(&notes), ["Zeta", "beta", "Apple"]);
+
+    sort_notes(&mut notes, SortBy::ModifiedDesc);
+    assert_eq!(titles(&notes), ["Apple", "beta", "Zeta"]);
+    sort_notes(&mut notes, SortBy::CreatedDesc);
+    assert_eq!(titles(&notes), ["Zeta", "beta", "Apple"]);
+    sort_notes(&mut notes, SortBy::TitleAsc);
+    assert_eq!(titles(&notes), ["Apple", "beta", "Zeta"]);
+    sort_notes(&mut notes, SortBy::FolderThenTitle);
+    assert_eq!(titles(&notes), ["Zeta", "beta", "Apple"]);
+}
+
+#[test]
+fn notes_list_pages_slice_the_sorted_list() {
+    use crate::note_manager::SortBy;
+
+    let ws = TestWorkspace::new("notes-page");
+    for i in 0..5 {
+        ws.write_note(&format!("Note {i}.md"), "x\n");
+    }
+    let cache_db = ws.create_cache();
+    let notes_dir = ws.notes_dir_str();
+
+    let page = crate::commands::notes_page(notes_dir, &cache_db, 1, 2, SortBy::TitleAsc).unwrap();
+    assert_eq!(page.total, 5);
+    let titles: Vec<_> = page.notes.iter().map(|n| n.title.as_str()).collect();
+    assert_eq!(titles, ["Note 1", "Note 2"]);
+
+    let last = crate::commands::notes_page(notes_dir, &cache_db, 4, 10, SortBy::TitleAsc).unwrap();
+    assert_eq!(last.notes.len(), 1);
+    assert_eq!(last.notes[0].title, "Note 4");
+
+    let past_end =
+        crate::commands::notes_page(notes_dir, &cache_db, 9, 10, SortBy::TitleAsc).unwrap();
+    assert!(past_end.notes.is_empty());
+    assert_eq!(past_end.total, 5);
+}
@@ -1,29 +1,62 @@
 #[macro_use]
 mod macros;
+mod attachments;
 mod cache;
 mod commands;
 mod error;
+mod export;
+mod favicons;
+mod folds;
 mod git_manager;
+mod import;
 #[cfg(test)]
 mod integration_tests;
+mod links;
 mod note_manager;
+mod trash;
 mod utils;
+mod watcher;
+mod web;
 
 use cache::CacheDb;
 use commands::AppState;
 use error::Result;
 use git_manager::GitManager;
 use note_manager::read_file_with_encoding;
-use std::collections::{HashSet, VecDeque};
-use std::sync::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
 use std::time::UNIX_EPOCH;
 
+/// How often the startup sync reports progress, in notes
+const SYNC_PROGRESS_INTERVAL: usize = 50;
+
+/// Payload of the `cache-rebuild-progress` event
+#[derive(Clone, serde::Serialize)]
+pub(crate) struct CacheRebuildProgress {
+    pub(crate) done: usize,
+    pub(crate) total: usize,
+}
+
 /// Sync the cache incrementally - only update files that have changed since last cache
-fn sync_cache(state: &AppState) -> Result<()> {
-    let notes = note_manager::list_notes(&state.notes_dir)?;
+pub(crate) fn sync_cache(state: &AppState) -> Result<()> {
+    sync_cache_with_progress(state, |_, _| {})
+}
+
+/// `sync_cache`, calling `on_progress(done, total)` as notes are checked.
+/// The cache lock is taken per note rather than for the whole sync, so
+/// commands can run against what is indexed so far. Stops early if the
+/// vault is switched meanwhile.
+pub(crate) fn sync_cache_with_progress(
+    state: &AppState,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<()> {
+    let _job = state.begin_cache_job();
+    let notes_dir = state.notes_dir();
+    let notes = note_manager::list_notes(&notes_dir)?;
+    let total = notes.len();
 
     // Handle mutex with proper poisoning recovery
-    let cache_db = match state.cache_db.lock() {
+    let lock_cache = || match state.cache_db.lock() {
         Ok(guard) => guard,
         Err(poisoned) => {
             eprintln!("Error: Cache database mutex was poisoned. Attempting recovery...");
@@ -32,7 +65,7 @@ fn sync_cache(state: &AppState) -> Result<()> {
     };
 
     // Get all currently cached paths to detect deletions
-    let cached_paths: HashSet<String> = cache_db
+    let cached_paths: HashSet<String> = lock_cache()
         .get_all_cached_paths()
         .unwrap_or_default()
         .into_iter()
@@ -40,7 +73,10 @@ fn sync_cache(state: &AppState) -> Result<()> {
 
     let mut current_paths: HashSet<String> = HashSet::new();
 
-    for note in notes {
+    for (index, note) in notes.into_iter().enumerate() {
+        if index % SYNC_PROGRESS_INTERVAL == 0 {
+            on_progress(index, total);
+        }
         current_paths.insert(note.path.clone());
 
         // Get file modification time
@@ -58,6 +94,12 @@ fn sync_cache(state: &AppState) -> Result<()> {
             Err(_) => continue, // Can't access file, skip
         };
 
+        let cache_db = lock_cache();
+        if state.notes_dir() != notes_dir {
+            // The vault was switched and its own sync has taken over
+            return Ok(());
+        }
+
         // Check if file needs updating
         let needs_update = match cache_db.get_cached_mtime(&note.path) {
             Ok(Some((cached_secs, cached_nanos))) => {
@@ -75,7 +117,7 @@ fn sync_cache(state: &AppState) -> Result<()> {
                     &note.path,
                     &note.title,
                     &content,
-                    &state.notes_dir,
+                    &notes_dir,
                 ) {
                     let path = &note.path;
                     eprintln!("Warning: Failed to update cache for '{path}': {e}");
@@ -96,16 +138,45 @@ fn sync_cache(state: &AppState) -> Result<()> {
     let deleted_paths: Vec<String> = cached_paths.difference(&current_paths).cloned().collect();
 
     if !deleted_paths.is_empty() {
+        let cache_db = lock_cache();
+        if state.notes_dir() != notes_dir {
+            return Ok(());
+        }
         if let Err(e) = cache_db.remove_stale_entries(&deleted_paths) {
             eprintln!("Warning: Failed to remove stale cache entries: {e}");
         }
     }
 
+    on_progress(total, total);
     Ok(())
 }
 
-/// Force a full cache rebuild (clears all metadata and rebuilds from scratch)
-pub fn force_rebuild_cache(state: &AppState) -> Result<()> {
+/// Force a full cache rebuild (reindexes every note from scratch in one
+/// transaction), calling `on_progress(done, total)` along the way. Stops
+/// between notes once `rebuild_cancelled` is set, keeping the notes done so
+/// far; the others lose their cached mtime so the next sync reindexes them.
+pub fn force_rebuild_cache(
+    state: &AppState,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<commands::CacheRebuildResult> {
+    let _job = state.begin_cache_job();
+    state.rebuild_cancelled.store(false, Ordering::SeqCst);
+
+    let notes_dir = state.notes_dir();
+    let mut entries = Vec::new();
+    let mut mtimes = Vec::new();
+    for note in note_manager::list_notes(&notes_dir)? {
+        let content = match read_file_with_encoding(&note.path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Failed to read '{}' for rebuild: {e}", note.path);
+                continue;
+            }
+        };
+        mtimes.push(commands::get_file_mtime(&note.path).ok());
+        entries.push((note.path, note.title, content));
+    }
+
     let cache_db = match state.cache_db.lock() {
         Ok(guard) => guard,
         Err(poisoned) => {
@@ -114,14 +185,29 @@ pub fn force_rebuild_cache(state: &AppState) -> Result<()> {
         }
     };
 
-    // Clear all metadata to force full rebuild
-    if let Err(e) = cache_db.clear_all_metadata() {
-        eprintln!("Warning: Failed to clear metadata: {e}");
-    }
+    // Cleared first so a failed rebuild is redone in full by the next sync
+    cache_db.clear_all_metadata()?;
+    let total = entries.len();
+    let processed = cache_db.rebuild_all_with_progress(&entries, &notes_dir, |done, total| {
+        if done % SYNC_PROGRESS_INTERVAL == 0 {
+            on_progress(done, total);
+        }
+        !state.rebuild_cancelled.load(Ordering::SeqCst)
+    })?;
 
-    drop(cache_db); // Release lock before calling sync_cache
+    let processed_mtimes: Vec<(String, i64, u32)> = entries[..processed]
+        .iter()
+        .zip(&mtimes)
+        .filter_map(|((path, _, _), mtime)| mtime.map(|(secs, nanos)| (path.clone(), secs, nanos)))
+        .collect();
+    cache_db.set_cached_mtimes(&processed_mtimes)?;
+    on_progress(processed, total);
 
-    sync_cache(state)
+    Ok(commands::CacheRebuildResult {
+        processed,
+        total,
+        cancelled: processed < total,
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -142,67 +228,158 @@ pub fn run() {
                     .expect("Failed to create app data directory");
             }
 
-            let cache_db_path = app_data_dir.join("notes_cache.db");
-            let cache_db = CacheDb::new(&cache_db_path.to_string_lossy())
-                .expect("Failed to initialize cache database");
-
+            // Use the vault chosen last time, else ~/Notes
             let home_dir = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-            let default_notes_dir = home_dir.join("Notes");
+            let mut config = commands::load_app_config(&app_data_dir);
+            let notes_dir = config
+                .notes_dir
+                .clone()
+                .map(std::path::PathBuf::from)
+                .filter(|dir| dir.is_dir())
+                .unwrap_or_else(|| home_dir.join("Notes"));
 
-            if !default_notes_dir.exists() {
-                std::fs::create_dir_all(&default_notes_dir)
+            if !notes_dir.exists() {
+                std::fs::create_dir_all(&notes_dir)
                     .expect("Failed to create default notes directory");
             }
+            let notes_dir = notes_dir.to_string_lossy().to_string();
+
+            // Every vault has its own cache database
+            let known_vaults = config.vaults.len();
+            let vault = config.vault_for_path(&notes_dir);
+            if config.vaults.len() != known_vaults {
+                if let Err(e) = commands::save_app_config(&app_data_dir, &config) {
+                    eprintln!("Warning: Failed to save app config: {e}");
+                }
+            }
+            let cache_db_path = app_data_dir.join(&vault.cache_db);
+            let cache_db = CacheDb::open_or_recreate(&cache_db_path.to_string_lossy())
+                .expect("Failed to initialize cache database");
+            commands::configure_search_tokenizer(&cache_db, &notes_dir);
 
-            let git_manager = GitManager::new(&default_notes_dir.to_string_lossy());
+            let git_manager = GitManager::new(&notes_dir);
+            commands::configure_git_manager(&git_manager, &notes_dir);
 
-            let app_state = AppState {
-                cache_db: Mutex::new(cache_db),
-                git_manager: Mutex::new(git_manager),
-                notes_dir: default_notes_dir.to_string_lossy().to_string(),
-                recent_notes: Mutex::new(VecDeque::new()),
-            };
+            let app_state = AppState::new(cache_db, git_manager, notes_dir.clone());
+            app.manage(app_state);
+
+            // Sync cache in the background so the window opens straight away;
+            // only changed files are reindexed
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                use tauri::Emitter;
+
+                let state = app_handle.state::<AppState>();
+                let result = sync_cache_with_progress(&state, |done, total| {
+                    let progress = CacheRebuildProgress { done, total };
+                    if let Err(e) = app_handle.emit("cache-rebuild-progress", progress) {
+                        eprintln!("Warning: Failed to emit cache-rebuild-progress event: {e}");
+                    }
+                });
+                if let Err(e) = result {
+                    eprintln!("Warning: Failed to sync cache on startup: {e}");
+                }
+                if let Err(e) = app_handle.emit("cache-rebuild-complete", ()) {
+                    eprintln!("Warning: Failed to emit cache-rebuild-complete event: {e}");
+                }
+            });
 
-            // Sync cache on startup - only updates changed files
-            if let Err(e) = sync_cache(&app_state) {
-                eprintln!("Warning: Failed to sync cache on startup: {e}");
+            // Keep the cache in sync with edits made outside the app
+            match watcher::watch_notes_dir(app.handle().clone(), &notes_dir) {
+                Ok(notes_watcher) => {
+                    let state = app.state::<AppState>();
+                    let mut watcher_slot = lock_mutex!(state.notes_watcher);
+                    *watcher_slot = Some(notes_watcher);
+                }
+                Err(e) => eprintln!("Warning: Failed to start file watcher: {e}"),
             }
 
-            app.manage(app_state);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_notes_list,
+            commands::get_notes_list_page,
             commands::read_note,
             commands::save_note,
             commands::create_note,
+            commands::create_note_from_template,
+            commands::list_templates,
             commands::delete_note,
+            commands::restore_from_trash,
+            commands::list_trash,
+            commands::empty_trash,
+            commands::get_fold_state,
+            commands::save_fold_state,
             commands::search_notes,
             commands::search_notes_enhanced,
+            commands::search_notes_fast,
+            commands::save_search,
+            commands::list_saved_searches,
+            commands::delete_saved_search,
+            commands::run_saved_search,
             commands::get_daily_note,
+            commands::get_daily_note_for_date,
+            commands::list_daily_notes,
+            commands::rollover_incomplete_todos,
+            commands::create_periodic_note,
             commands::get_block_reference,
             commands::get_blocks_for_note,
+            commands::get_note_toc,
             commands::resolve_transclusion,
             commands::get_backlinks,
+            commands::get_backlinks_with_context,
             commands::get_outgoing_links,
+            commands::get_broken_links,
+            commands::get_unlinked_mentions,
+            commands::get_link_suggestions,
             commands::get_all_tags,
+            commands::get_all_tags_with_counts,
+            commands::get_tag_suggestions,
             commands::get_notes_by_tag,
+            commands::rename_tag,
+            commands::replace_in_notes,
+            commands::convert_links,
             commands::set_notes_directory,
+            commands::get_vaults,
+            commands::add_vault,
+            commands::switch_vault,
             commands::find_note_by_name,
+            commands::get_duplicate_titles,
             commands::move_note,
+            commands::move_notes,
             commands::get_folder_contents,
             commands::delete_folder,
             commands::create_folder,
             commands::get_all_folders,
+            commands::get_folder_tree,
             commands::get_global_graph,
+            commands::export_graph,
+            commands::get_orphan_notes,
+            commands::get_dead_end_notes,
+            commands::get_hub_notes,
+            commands::get_note_link_stats,
             commands::get_local_graph,
             commands::get_filtered_graph,
+            commands::get_connection_path,
             commands::save_image,
             commands::save_attachment,
+            commands::get_unreferenced_assets,
+            commands::delete_unreferenced_assets,
             commands::open_file_external,
+            commands::reveal_in_file_manager,
+            commands::import_markdown_dir,
+            commands::export_note_html,
+            commands::export_vault_html,
+            commands::export_bookmarks_html,
             commands::get_incomplete_todos,
             commands::get_all_todos,
+            commands::get_todos_scheduled_today,
+            commands::get_overdue_todos,
+            commands::get_todos_by_priority,
+            commands::get_incomplete_todos_sorted,
+            commands::get_todos_agenda,
             commands::toggle_todo,
+            commands::update_todo,
             commands::get_daily_note_template,
             commands::save_daily_note_template,
             commands::rename_note,
@@ -210,9 +387,15 @@ pub fn run() {
             commands::init_git_repo,
             commands::is_git_repo,
             commands::get_git_blame,
+            commands::get_note_diff,
+            commands::restore_note_version,
+            commands::get_git_status,
             commands::git_commit,
+            commands::get_app_info,
             commands::get_app_settings,
             commands::save_app_settings,
+            commands::export_settings,
+            commands::import_settings,
             commands::get_recent_notes,
             commands::save_window_state,
             commands::apply_window_state,
@@ -220,11 +403,17 @@ pub fn run() {
             commands::search_bookmarks,
             commands::get_bookmarks_by_domain,
             commands::add_bookmark_manual,
+            commands::fetch_bookmark_metadata,
+            commands::get_bookmark_favicon,
+            commands::toggle_bookmark_favorite,
+            commands::get_frequent_bookmarks,
             commands::update_bookmark,
             commands::delete_bookmark,
             commands::get_all_bookmark_domains,
             commands::open_url_external,
             commands::force_rebuild_cache,
+            commands::cancel_rebuild,
+            commands::optimize_cache,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,182 @@
+use crate::attachments::{normalize_path, percent_decode, percent_encode_path};
+use crate::cache::heading_slug;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+/// How links between notes are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// `[[Note]]` and `[[Note|Text]]`
+    #[default]
+    Wikilink,
+    /// `[Note](Note.md)`, readable on GitHub and other markdown renderers
+    Markdown,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LinkChange {
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Rewrite the links in a note to `to_style`. `resolve` maps a wikilink
+/// target to the note path it points at; links that don't resolve, embeds,
+/// external URLs and anything in code are left alone. Aliases and
+/// `#heading`/`#^block` parts are carried over in both directions. Returns
+/// the new content and each link changed.
+pub fn convert_links(
+    content: &str,
+    note_path: &str,
+    notes_dir: &str,
+    to_style: LinkStyle,
+    resolve: impl Fn(&str) -> Option<String>,
+) -> (String, Vec<LinkChange>) {
+    let notes_root = Path::new(notes_dir);
+    let note_relative = Path::new(note_path)
+        .strip_prefix(notes_root)
+        .unwrap_or(Path::new(note_path));
+
+    let link_regex = match to_style {
+        LinkStyle::Markdown => Regex::new(r"(!?)\[\[([^\]|#]*)(#[^\]|]*)?(?:\|([^\]]*))?\]\]"),
+        LinkStyle::Wikilink => {
+            Regex::new(r"(!?)\[([^\]]*)\]\(\s*<?([^)>]+?\.md)(#[^)>\s]*)?>?\s*\)")
+        }
+    }
+    .expect("link regex is valid");
+
+    let mut changes = Vec::new();
+    let mut result = String::with_capacity(content.len());
+    let mut in_code_block = false;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            result.push_str(line);
+            continue;
+        }
+
+        let updated = link_regex.replace_all(line, |caps: &Captures| {
+            let original = &caps[0];
+            let start = caps.get(0).map_or(0, |m| m.start());
+            let in_inline_code = line[..start].matches('`').count() % 2 == 1;
+            if !caps[1].is_empty() || in_inline_code {
+                return original.to_string();
+            }
+
+            let converted = match to_style {
+                LinkStyle::Markdown => {
+                    wikilink_to_markdown(caps, note_relative, notes_root, &resolve)
+                }
+                LinkStyle::Wikilink => markdown_to_wikilink(caps, note_path, notes_root),
+            };
+            match converted {
+                Some(converted) => {
+                    changes.push(LinkChange {
+                        line_number: index + 1,
+                        before: original.to_string(),
+                        after: converted.clone(),
+                    });
+                    converted
+                }
+                None => original.to_string(),
+            }
+        });
+        result.push_str(&updated);
+    }
+
+    (result, changes)
+}
+
+/// `[[Note#Heading|Text]]` to `[Text](Note.md#heading)`. Without an alias
+/// the link text is the wikilink as written, so converting back restores it.
+fn wikilink_to_markdown(
+    caps: &Captures,
+    note_relative: &Path,
+    notes_root: &Path,
+    resolve: &impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let target = caps[2].trim();
+    if target.is_empty() {
+        return None;
+    }
+    let anchor = caps.get(3).map_or("", |m| m.as_str());
+    let target_path = resolve(target)?;
+    let target_relative = Path::new(&target_path).strip_prefix(notes_root).ok()?;
+
+    let href = percent_encode_path(&relative_link_path(note_relative, target_relative));
+    let fragment = match anchor.strip_prefix('#') {
+        Some(block) if block.starts_with('^') => format!("#{block}"),
+        Some(heading) if !heading.trim().is_empty() => format!("#{}", heading_slug(heading)),
+        _ => String::new(),
+    };
+    let text = match caps.get(4) {
+        Some(alias) => alias.as_str().to_string(),
+        None => format!("{target}{anchor}"),
+    };
+
+    Some(format!("[{text}]({href}{fragment})"))
+}
+
+/// `[Text](Note.md#heading)` to `[[Note#heading|Text]]`, dropping the alias
+/// when the text already names the same note and section
+fn markdown_to_wikilink(caps: &Captures, note_path: &str, notes_root: &Path) -> Option<String> {
+    let destination = percent_decode(caps[3].trim());
+    if destination.contains("://") {
+        return None;
+    }
+
+    let note_dir = Path::new(note_path).parent().unwrap_or(notes_root);
+    let target_path = normalize_path(&note_dir.join(&destination));
+    if !target_path.starts_with(notes_root) || !target_path.is_file() {
+        return None;
+    }
+    let stem = target_path.file_stem()?.to_str()?;
+
+    let anchor = caps
+        .get(4)
+        .map(|m| percent_decode(m.as_str()))
+        .unwrap_or_default();
+    let fragment = anchor.trim_start_matches('#');
+    let text = &caps[2];
+
+    let (text_name, text_fragment) = text.split_once('#').unwrap_or((text, ""));
+    let text_matches = text_name.trim().eq_ignore_ascii_case(stem)
+        && (text_fragment == fragment
+            || (!fragment.starts_with('^') && heading_slug(text_fragment) == fragment));
+    if text_matches {
+        Some(format!("[[{text}]]"))
+    } else if fragment.is_empty() {
+        Some(format!("[[{stem}|{text}]]"))
+    } else {
+        Some(format!("[[{stem}#{fragment}|{text}]]"))
+    }
+}
+
+/// Relative path from the note at `from` to `to`, both relative to the
+/// notes directory, with `/` separators
+fn relative_link_path(from: &Path, to: &Path) -> String {
+    let from_dir: Vec<Component> = from
+        .parent()
+        .map(|parent| parent.components().collect())
+        .unwrap_or_default();
+    let to_components: Vec<Component> = to.components().collect();
+    let shared = from_dir
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts = vec!["..".to_string(); from_dir.len() - shared];
+    parts.extend(
+        to_components[shared..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
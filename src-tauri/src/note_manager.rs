@@ -1,4 +1,4 @@
-use crate::utils::safe_write_file;
+use crate::utils::{safe_write_file, validate_path_security};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Component, Path, PathBuf};
@@ -10,6 +10,48 @@ pub struct Note {
     pub title: String,
     pub content: String,
     pub last_modified: i64,
+    /// Sub-second part of the modification time, for conflict checks
+    pub last_modified_nanos: u32,
+    pub created: i64,
+    /// Styling requested by the note's frontmatter, if any
+    pub style: Option<NoteStyle>,
+}
+
+/// Per-note styling from the `cssclass`/`cssclasses` and `theme`
+/// frontmatter keys
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct NoteStyle {
+    pub css_classes: Vec<String>,
+    /// One of the app themes; other values are ignored
+    pub theme: Option<String>,
+}
+
+const NOTE_THEMES: [&str; 3] = ["dark", "light", "custom"];
+
+/// Read the style keys from a note's frontmatter, dropping class names that
+/// aren't plain identifiers and unknown themes
+pub fn extract_note_style(content: &str) -> Option<NoteStyle> {
+    let is_class_name = |class: &str| {
+        class
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && !class.starts_with(|c: char| c.is_ascii_digit())
+    };
+    let mut css_classes: Vec<String> = Vec::new();
+    for value in crate::cache::frontmatter_values(content, &["cssclass", "cssclasses"]) {
+        for class in value.split_whitespace() {
+            if is_class_name(class) && !css_classes.iter().any(|c| c == class) {
+                css_classes.push(class.to_string());
+            }
+        }
+    }
+
+    let theme = crate::cache::frontmatter_values(content, &["theme"])
+        .into_iter()
+        .map(|theme| theme.to_lowercase())
+        .find(|theme| NOTE_THEMES.contains(&theme.as_str()));
+
+    (!css_classes.is_empty() || theme.is_some()).then_some(NoteStyle { css_classes, theme })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,15 +59,75 @@ pub struct NoteMetadata {
     pub path: String,
     pub title: String,
     pub last_modified: i64,
+    pub created: i64,
     pub relative_path: String,
     pub folder: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FolderNode {
+    pub name: String,
+    /// Relative to the notes directory; empty for the root
+    pub path: String,
+    /// Notes directly in this folder
+    pub note_count: usize,
+    /// Notes in this folder and all of its subfolders
+    pub total_count: usize,
+    pub children: Vec<FolderNode>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
     pub note: Note,
     pub match_count: usize,
     pub snippets: Vec<SearchSnippet>,
+    pub score: f64, // FTS5 relevance (bm25, negated so higher is better)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnlinkedMention {
+    pub note_path: String,
+    pub line_number: usize,
+    pub snippet: String,
+}
+
+/// A line matching a regex search. Offsets are in chars within `text`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegexMatch {
+    pub line_number: usize,
+    pub text: String,
+    pub match_start: usize,
+    pub match_length: usize,
+    /// One entry per capture group; `None` when the group didn't take part
+    pub captures: Vec<Option<CaptureSpan>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CaptureSpan {
+    pub start: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegexSearchResult {
+    pub note: Note,
+    pub matches: Vec<RegexMatch>,
+}
+
+/// Regex search can't use the FTS index, so every note is read;
+/// `files_scanned` reports how many that was.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegexSearchResults {
+    pub results: Vec<RegexSearchResult>,
+    pub files_scanned: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TocEntry {
+    pub level: i32,
+    pub text: String,
+    pub slug: String,
+    pub line_number: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,12 +143,13 @@ pub fn read_note(path: &str) -> Result<Note, String> {
 
     let metadata = fs::metadata(path).map_err(|e| format!("Failed to get metadata: {e}"))?;
 
-    let last_modified = metadata
+    let modified = metadata
         .modified()
         .map_err(|e| format!("Failed to get modified time: {e}"))?
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("Failed to convert time: {e}"))?
-        .as_secs() as i64;
+        .map_err(|e| format!("Failed to convert time: {e}"))?;
+    let last_modified = modified.as_secs() as i64;
+    let created = created_time(&metadata, last_modified);
 
     let title = Path::new(path)
         .file_stem()
@@ -57,17 +160,66 @@ pub fn read_note(path: &str) -> Result<Note, String> {
     Ok(Note {
         path: path.to_string(),
         title,
+        style: extract_note_style(&content),
         content,
         last_modified,
+        last_modified_nanos: modified.subsec_nanos(),
+        created,
     })
 }
 
+/// Creation time in seconds since the epoch, or `last_modified` where the
+/// platform or filesystem doesn't record one
+fn created_time(metadata: &fs::Metadata, last_modified: i64) -> i64 {
+    metadata
+        .created()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(last_modified, |d| d.as_secs() as i64)
+}
+
 pub fn write_note(path: &str, content: &str) -> Result<(), String> {
     // Use the safe write utility which handles parent directory creation
     // and atomic writes
     safe_write_file(path, content).map_err(|e| format!("Failed to write note: {e}"))
 }
 
+/// The line ending most of `content` uses: `"\r\n"` or `"\n"`
+pub fn line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > lf {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// `content` with every line break converted to `ending`
+pub fn with_line_ending(content: &str, ending: &str) -> String {
+    let normalized = content.replace("\r\n", "\n");
+    if ending == "\n" {
+        normalized
+    } else {
+        normalized.replace('\n', ending)
+    }
+}
+
+/// The headings of a note in order. Slugs are the heading block ids, so
+/// an entry can be jumped to like any `[[Note#heading]]` link.
+pub fn extract_toc(content: &str) -> Vec<TocEntry> {
+    crate::cache::extract_blocks(content)
+        .into_iter()
+        .filter(|block| block.block_type == crate::cache::BLOCK_TYPE_HEADING)
+        .map(|block| TocEntry {
+            level: block.level,
+            text: block.content,
+            slug: block.block_id,
+            line_number: block.line_number,
+        })
+        .collect()
+}
+
 pub fn list_notes(base_path: &str) -> Result<Vec<NoteMetadata>, String> {
     let mut notes = Vec::new();
     let base_path_buf = Path::new(base_path);
@@ -86,6 +238,7 @@ pub fn list_notes(base_path: &str) -> Result<Vec<NoteMetadata>, String> {
                     .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
                     .map(|d| d.as_secs() as i64)
                     .unwrap_or(0);
+                let created = created_time(&metadata, last_modified);
 
                 let title = path
                     .file_stem()
@@ -106,23 +259,12 @@ pub fn list_notes(base_path: &str) -> Result<Vec<NoteMetadata>, String> {
                     .unwrap_or_else(String::new);
 
                 // Skip notes in hidden internal folders (.plainflux, images)
-                let relative_path_obj = Path::new(&relative_path);
-                let skip_note = relative_path_obj.components().any(|component| {
-                    if let std::path::Component::Normal(name) = component {
-                        if let Some(name_str) = name.to_str() {
-                            return name_str == ".plainflux"
-                                || name_str == "images"
-                                || name_str == ".git";
-                        }
-                    }
-                    false
-                });
-
-                if !skip_note {
+                if !is_in_hidden_folder(Path::new(&relative_path)) {
                     notes.push(NoteMetadata {
                         path: path.to_string_lossy().to_string(),
                         title,
                         last_modified,
+                        created,
                         relative_path,
                         folder,
                     });
@@ -131,16 +273,62 @@ pub fn list_notes(base_path: &str) -> Result<Vec<NoteMetadata>, String> {
         }
     }
 
-    // Sort notes alphabetically by folder and then by title
-    notes.sort_by(|a, b| match a.folder.cmp(&b.folder) {
-        std::cmp::Ordering::Equal => a.title.cmp(&b.title),
-        other => other,
-    });
+    sort_notes(&mut notes, SortBy::FolderThenTitle);
 
     Ok(notes)
 }
 
-pub fn get_all_folders(base_path: &str) -> Result<Vec<String>, String> {
+/// Orderings for the notes list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortBy {
+    TitleAsc,
+    ModifiedDesc,
+    CreatedDesc,
+    /// Alphabetically by folder and then by title
+    #[default]
+    FolderThenTitle,
+}
+
+/// Sort `notes` in place using the timestamps already on each entry
+pub fn sort_notes(notes: &mut [NoteMetadata], sort_by: SortBy) {
+    match sort_by {
+        SortBy::TitleAsc => notes.sort_by(|a, b| {
+            a.title
+                .to_lowercase()
+                .cmp(&b.title.to_lowercase())
+                .then_with(|| a.path.cmp(&b.path))
+        }),
+        SortBy::ModifiedDesc => notes.sort_by(|a, b| {
+            b.last_modified
+                .cmp(&a.last_modified)
+                .then_with(|| a.path.cmp(&b.path))
+        }),
+        SortBy::CreatedDesc => {
+            notes.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| a.path.cmp(&b.path)))
+        }
+        SortBy::FolderThenTitle => notes.sort_by(|a, b| match a.folder.cmp(&b.folder) {
+            std::cmp::Ordering::Equal => a.title.cmp(&b.title),
+            other => other,
+        }),
+    }
+}
+
+/// Whether a path relative to the notes directory lies inside one of the
+/// hidden internal folders (.plainflux, images, .git)
+pub fn is_in_hidden_folder(relative_path: &Path) -> bool {
+    relative_path.components().any(|component| {
+        if let Component::Normal(name) = component {
+            if let Some(name_str) = name.to_str() {
+                return name_str == ".plainflux" || name_str == "images" || name_str == ".git";
+            }
+        }
+        false
+    })
+}
+
+/// Folders of the vault, leaving out internal folders and the daily notes
+/// folder
+pub fn get_all_folders(base_path: &str, daily_folder: &str) -> Result<Vec<String>, String> {
     let mut folders = Vec::new();
     let base_path_buf = Path::new(base_path);
 
@@ -157,32 +345,11 @@ pub fn get_all_folders(base_path: &str) -> Result<Vec<String>, String> {
                 .unwrap_or_else(|_| path.to_string_lossy().to_string());
 
             if !relative_path.is_empty() {
-                // Skip hidden internal folders
-                let folder_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                if folder_name == ".plainflux"
-                    || folder_name == "images"
-                    || folder_name == "Daily Notes"
-                    || folder_name == ".git"
-                {
-                    continue;
-                }
-
-                // Also skip if any parent folder is .plainflux, images, or Daily Notes
+                // Skip hidden internal folders and everything under the daily notes folder
                 let relative_path_obj = Path::new(&relative_path);
-                let contains_hidden = relative_path_obj.components().any(|component| {
-                    if let std::path::Component::Normal(name) = component {
-                        if let Some(name_str) = name.to_str() {
-                            return name_str == ".plainflux"
-                                || name_str == "images"
-                                || name_str == "Daily Notes"
-                                || name_str == ".git";
-                        }
-                    }
-                    false
-                });
-
-                if !contains_hidden {
+                if !is_in_hidden_folder(relative_path_obj)
+                    && !relative_path_obj.starts_with(daily_folder)
+                {
                     folders.push(relative_path);
                 }
             }
@@ -193,20 +360,119 @@ pub fn get_all_folders(base_path: &str) -> Result<Vec<String>, String> {
     Ok(folders)
 }
 
-pub fn create_daily_note(base_path: &str, template: Option<&str>) -> Result<String, String> {
+/// Folder hierarchy of the vault with note counts at each level. Folders
+/// without notes are kept so no branch is missing; internal folders are
+/// left out exactly as `list_notes` leaves out their notes. Like
+/// `get_all_folders`, the daily notes folder is left out too.
+pub fn get_folder_tree(base_path: &str, daily_folder: &str) -> Result<FolderNode, String> {
+    let base = Path::new(base_path);
+    let mut direct_counts: std::collections::BTreeMap<PathBuf, usize> =
+        std::collections::BTreeMap::new();
+    direct_counts.insert(PathBuf::new(), 0);
+
+    for entry in WalkDir::new(base)
+        .follow_links(true)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+    {
+        if let Ok(relative) = entry.path().strip_prefix(base) {
+            if !is_in_hidden_folder(relative) && !relative.starts_with(daily_folder) {
+                direct_counts.entry(relative.to_path_buf()).or_insert(0);
+            }
+        }
+    }
+    for note in list_notes(base_path)? {
+        if Path::new(&note.folder).starts_with(daily_folder) {
+            continue;
+        }
+        *direct_counts.entry(PathBuf::from(note.folder)).or_insert(0) += 1;
+    }
+
+    fn build(
+        path: &Path,
+        direct_counts: &std::collections::BTreeMap<PathBuf, usize>,
+    ) -> FolderNode {
+        let children: Vec<FolderNode> = direct_counts
+            .keys()
+            .filter(|candidate| {
+                candidate.parent() == Some(path) && !candidate.as_os_str().is_empty()
+            })
+            .map(|child| build(child, direct_counts))
+            .collect();
+        let note_count = direct_counts.get(path).copied().unwrap_or(0);
+
+        FolderNode {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            note_count,
+            total_count: note_count + children.iter().map(|c| c.total_count).sum::<usize>(),
+            children,
+        }
+    }
+
+    Ok(build(Path::new(""), &direct_counts))
+}
+
+/// Folder daily notes live in unless the settings name another one
+pub const DEFAULT_DAILY_NOTES_FOLDER: &str = "Daily Notes";
+
+pub fn create_daily_note(
+    base_path: &str,
+    daily_folder: &str,
+    template: Option<&str>,
+) -> Result<String, String> {
+    create_daily_note_at(
+        base_path,
+        daily_folder,
+        template,
+        chrono::Local::now().naive_local(),
+    )
+}
+
+/// Parse a `YYYY-MM-DD` date as used for daily note file names
+pub fn parse_daily_note_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .filter(|parsed| parsed.format("%Y-%m-%d").to_string() == date)
+        .ok_or_else(|| format!("Invalid date '{date}': expected YYYY-MM-DD"))
+}
+
+/// Create (if missing) the daily note for `date`. Template date variables
+/// resolve to that day, while time variables use the current time.
+pub fn create_daily_note_for_date(
+    base_path: &str,
+    daily_folder: &str,
+    date: &str,
+    template: Option<&str>,
+) -> Result<String, String> {
+    let date = parse_daily_note_date(date)?;
+    let now = date.and_time(chrono::Local::now().time());
+    create_daily_note_at(base_path, daily_folder, template, now)
+}
+
+fn create_daily_note_at(
+    base_path: &str,
+    daily_folder: &str,
+    template: Option<&str>,
+    now: chrono::NaiveDateTime,
+) -> Result<String, String> {
     use crate::utils::ensure_dir_exists;
-    use chrono::Local;
 
-    let daily_notes_dir = Path::new(base_path).join("Daily Notes");
+    let daily_notes_dir = Path::new(base_path).join(daily_folder);
     ensure_dir_exists(&daily_notes_dir)
-        .map_err(|e| format!("Failed to create Daily Notes directory: {e}"))?;
+        .map_err(|e| format!("Failed to create daily notes directory: {e}"))?;
 
-    let today = Local::now().format("%Y-%m-%d").to_string();
+    let today = now.format("%Y-%m-%d").to_string();
     let note_path = daily_notes_dir.join(format!("{today}.md"));
 
     if !note_path.exists() {
         let content = if let Some(template_content) = template {
-            apply_template_variables(template_content)
+            render_template_variables(template_content, &today, now)
         } else {
             format!("# {today}\n\n")
         };
@@ -218,13 +484,251 @@ pub fn create_daily_note(base_path: &str, template: Option<&str>) -> Result<Stri
     Ok(note_path.to_string_lossy().to_string())
 }
 
-fn apply_template_variables(template: &str) -> String {
-    use chrono::Local;
+/// Take the incomplete todos out of `content`, returning what's left, the
+/// removed lines in order and how many incomplete todos they contain. Each
+/// todo moves together with everything nested under it, and todos under a
+/// completed parent stay put so the hierarchy isn't broken; moved lines
+/// keep their markers and are dedented to the shallowest one.
+pub fn take_incomplete_todos(content: &str) -> (String, Vec<String>, usize) {
+    let todo_regex = regex::Regex::new(r"^(\s*)[-*+]\s*\[([ xX])\]").unwrap();
+    // Measured in chars so dedenting never splits a multi-byte space
+    let indent_of = |line: &str| line.chars().take_while(|c| c.is_whitespace()).count();
+    let is_incomplete = |line: &str| {
+        todo_regex
+            .captures(line)
+            .is_some_and(|caps| &caps[2] == " ")
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut kept = Vec::new();
+    let mut moved = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        // Lines nested under this one, up to the next blank or shallower line
+        let indent = indent_of(line);
+        let subtree_end = lines[i + 1..]
+            .iter()
+            .position(|next| next.trim().is_empty() || indent_of(next) <= indent)
+            .map_or(lines.len(), |offset| i + 1 + offset);
+
+        match todo_regex.captures(line) {
+            Some(caps) => {
+                let target = if &caps[2] == " " {
+                    &mut moved
+                } else {
+                    &mut kept
+                };
+                target.extend_from_slice(&lines[i..subtree_end]);
+                i = subtree_end;
+            }
+            None => {
+                kept.push(line);
+                i += 1;
+            }
+        }
+    }
+
+    let todo_count = moved.iter().filter(|line| is_incomplete(line)).count();
+    let min_indent = moved.iter().map(|line| indent_of(line)).min().unwrap_or(0);
+    let moved = moved
+        .iter()
+        .map(|line| line.chars().skip(min_indent).collect())
+        .collect();
+
+    let ending = line_ending(content);
+    let mut remaining = kept.join(ending);
+    if content.ends_with('\n') {
+        remaining.push_str(ending);
+    }
+    (remaining, moved, todo_count)
+}
+
+/// Add `lines` at the end of the note's `## Tasks` section, creating the
+/// section at the end of the note if there isn't one
+pub fn append_to_tasks_section(content: &str, lines: &[String]) -> String {
+    let ending = line_ending(content);
+    let mut all: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let heading = all
+        .iter()
+        .position(|line| line.trim().eq_ignore_ascii_case("## tasks"));
+    match heading {
+        Some(heading) => {
+            let section_end = all[heading + 1..]
+                .iter()
+                .position(|line| line.starts_with('#'))
+                .map_or(all.len(), |offset| heading + 1 + offset);
+            // After the last non-blank line of the section
+            let insert_at = (heading + 1..section_end)
+                .rev()
+                .find(|&i| !all[i].trim().is_empty())
+                .map_or(heading + 1, |i| i + 1);
+            all.splice(insert_at..insert_at, lines.iter().cloned());
+        }
+        None => {
+            if all.last().is_some_and(|line| !line.trim().is_empty()) {
+                all.push(String::new());
+            }
+            all.push("## Tasks".to_string());
+            all.extend(lines.iter().cloned());
+        }
+    }
+
+    let mut updated = all.join(ending);
+    updated.push_str(ending);
+    updated
+}
+
+/// Dates (`YYYY-MM-DD`, ascending) that have a file in the daily notes folder
+pub fn list_daily_notes(base_path: &str, daily_folder: &str) -> Result<Vec<String>, String> {
+    let daily_notes_dir = Path::new(base_path).join(daily_folder);
+    if !daily_notes_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&daily_notes_dir)
+        .map_err(|e| format!("Failed to read daily notes directory: {e}"))?;
+
+    let mut dates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md"))
+        .filter_map(|path| {
+            path.file_stem()
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+        })
+        .filter(|stem| parse_daily_note_date(stem).is_ok())
+        .collect();
+
+    dates.sort();
+    Ok(dates)
+}
+
+/// Values `create_periodic_note` accepts for `period`
+pub const NOTE_PERIODS: [&str; 2] = ["weekly", "monthly"];
+
+/// Create (if missing) the weekly or monthly note for the current period.
+/// `period` is `"weekly"` or `"monthly"`.
+pub fn create_periodic_note(
+    base_path: &str,
+    daily_folder: &str,
+    period: &str,
+    template: Option<&str>,
+) -> Result<String, String> {
+    create_periodic_note_at(
+        base_path,
+        daily_folder,
+        period,
+        template,
+        chrono::Local::now().naive_local(),
+    )
+}
+
+pub(crate) fn create_periodic_note_at(
+    base_path: &str,
+    daily_folder: &str,
+    period: &str,
+    template: Option<&str>,
+    now: chrono::NaiveDateTime,
+) -> Result<String, String> {
+    use crate::utils::ensure_dir_exists;
+
+    // Both folders live under the daily notes folder so they stay out of the folder list
+    let (folder, title) = match period {
+        "weekly" => ("Weekly", now.format("%G-W%V").to_string()),
+        "monthly" => ("Monthly", now.format("%Y-%m").to_string()),
+        _ => return Err(format!("Unknown note period: {period}")),
+    };
+
+    let notes_dir = Path::new(base_path).join(daily_folder).join(folder);
+    ensure_dir_exists(&notes_dir)
+        .map_err(|e| format!("Failed to create {folder} notes directory: {e}"))?;
+
+    let note_path = notes_dir.join(format!("{title}.md"));
+
+    if !note_path.exists() {
+        let content = if let Some(template_content) = template {
+            render_template_variables(template_content, &title, now)
+        } else {
+            format!("# {title}\n\n")
+        };
+
+        safe_write_file(&note_path, &content)
+            .map_err(|e| format!("Failed to create {period} note: {e}"))?;
+    }
+
+    Ok(note_path.to_string_lossy().to_string())
+}
+
+/// Names (file stems) of the note templates in `.plainflux/templates`
+pub fn list_templates(base_path: &str) -> Result<Vec<String>, String> {
+    let templates_dir = Path::new(base_path).join(".plainflux").join("templates");
+    if !templates_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&templates_dir).map_err(|e| format!("Failed to read templates: {e}"))?;
+
+    let mut templates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md"))
+        .filter_map(|path| {
+            path.file_stem()
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+        })
+        .collect();
+
+    templates.sort();
+    Ok(templates)
+}
+
+/// Render the template `template_name` for a new note called `title`
+pub fn render_template(
+    base_path: &str,
+    template_name: &str,
+    title: &str,
+) -> Result<String, String> {
+    let name_is_plain = !template_name.is_empty()
+        && !template_name.contains(['/', '\\'])
+        && template_name != "."
+        && template_name != "..";
+    if !name_is_plain {
+        return Err(format!("Invalid template name: {template_name}"));
+    }
+
+    let template_path = Path::new(base_path)
+        .join(".plainflux")
+        .join("templates")
+        .join(format!("{template_name}.md"));
+    let template = read_file_with_encoding(&template_path.to_string_lossy())
+        .map_err(|_| format!("Template not found: {template_name}"))?;
+
+    Ok(apply_template_variables(&template, title))
+}
+
+fn apply_template_variables(template: &str, title: &str) -> String {
+    render_template_variables(template, title, chrono::Local::now().naive_local())
+}
+
+/// Substitute template variables as of `now`. `{{date:<format>}}` takes any
+/// chrono format string; tokens with an invalid format are left as written.
+pub(crate) fn render_template_variables(
+    template: &str,
+    title: &str,
+    now: chrono::NaiveDateTime,
+) -> String {
+    use chrono::format::{Item, StrftimeItems};
+    use chrono::Duration;
 
-    let now = Local::now();
     let mut result = template.to_string();
 
     // Replace template variables
+    result = result.replace("{{title}}", title);
     result = result.replace("{{date}}", &now.format("%Y-%m-%d").to_string());
     result = result.replace("{{date_long}}", &now.format("%A, %B %d, %Y").to_string());
     result = result.replace("{{time}}", &now.format("%H:%M").to_string());
@@ -233,8 +737,27 @@ fn apply_template_variables(template: &str) -> String {
     result = result.replace("{{month}}", &now.format("%m").to_string());
     result = result.replace("{{day}}", &now.format("%d").to_string());
     result = result.replace("{{weekday}}", &now.format("%A").to_string());
-
-    result
+    result = result.replace("{{week}}", &now.format("%V").to_string());
+    result = result.replace(
+        "{{yesterday}}",
+        &(now - Duration::days(1)).format("%Y-%m-%d").to_string(),
+    );
+    result = result.replace(
+        "{{tomorrow}}",
+        &(now + Duration::days(1)).format("%Y-%m-%d").to_string(),
+    );
+
+    let custom_date = regex::Regex::new(r"\{\{date:([^}]+)\}\}").unwrap();
+    custom_date
+        .replace_all(&result, |caps: &regex::Captures| {
+            let format = &caps[1];
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                caps[0].to_string()
+            } else {
+                now.format(format).to_string()
+            }
+        })
+        .into_owned()
 }
 
 /// Helper function to read file contents, preferring UTF-8 with fallback for legacy files
@@ -245,31 +768,59 @@ pub fn read_file_with_encoding(path: &str) -> Result<String, String> {
     #[cfg(not(target_os = "windows"))]
     let path = path.to_string();
 
-    // First try reading as UTF-8 (the standard encoding)
-    match fs::read_to_string(&path) {
-        Ok(content) => Ok(content),
-        Err(e) => {
-            // If UTF-8 fails, try reading as bytes and convert lossily
-            // This handles legacy files that may have been created with other encodings
-            if e.kind() == std::io::ErrorKind::InvalidData {
-                match fs::read(&path) {
-                    Ok(bytes) => {
-                        eprintln!("[READ] Warning: File {path} contains invalid UTF-8, using lossy conversion");
-                        Ok(String::from_utf8_lossy(&bytes).into_owned())
-                    }
-                    Err(read_err) => {
-                        let err_msg = format!("Failed to read file {path}: {read_err}");
-                        eprintln!("[READ] ERROR: {err_msg}");
-                        Err(err_msg)
-                    }
-                }
-            } else {
-                let err_msg = format!("Failed to read file {path}: {e}");
-                eprintln!("[READ] ERROR: {err_msg}");
-                Err(err_msg)
-            }
-        }
+    let bytes = fs::read(&path).map_err(|e| {
+        let err_msg = format!("Failed to read file {path}: {e}");
+        eprintln!("[READ] ERROR: {err_msg}");
+        err_msg
+    })?;
+
+    Ok(decode_note_bytes(&bytes).unwrap_or_else(|| {
+        // Not valid UTF-8: most likely a legacy Windows file
+        eprintln!("[READ] Warning: File {path} is not valid UTF-8, decoding as Windows-1252");
+        decode_windows_1252(&bytes)
+    }))
+}
+
+/// Decode note bytes as UTF-8 (UTF-16 when a UTF-16 BOM is present),
+/// dropping any byte order mark. None if the bytes are not valid UTF-8.
+fn decode_note_bytes(bytes: &[u8]) -> Option<String> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return String::from_utf8(rest.to_vec()).ok();
     }
+
+    let utf16 = |rest: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    };
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return utf16(rest, u16::from_be_bytes);
+    }
+
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+/// Windows-1252 is Latin-1 except for 0x80-0x9F, which hold typographic
+/// characters such as curly quotes and the euro sign
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    const HIGH_CONTROL: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž',
+        '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}',
+        'ž', 'Ÿ',
+    ];
+
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => HIGH_CONTROL[(byte - 0x80) as usize],
+            _ => byte as char,
+        })
+        .collect()
 }
 
 pub fn search_notes(base_path: &str, query: &str) -> Result<Vec<Note>, String> {
@@ -341,32 +892,311 @@ pub fn search_notes(base_path: &str, query: &str) -> Result<Vec<Note>, String> {
     Ok(results)
 }
 
+/// A user search query translated for the FTS5 index
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedQuery {
+    /// FTS5 MATCH expression, or None when the query only filters by tag
+    pub fts_expression: Option<String>,
+    /// Tags from `tag:` operators; results must carry all of them
+    pub tags: Vec<String>,
+    /// Words and phrases to highlight in snippets
+    pub terms: Vec<String>,
+}
+
+enum QueryToken {
+    Word(String),
+    Phrase(String),
+}
+
+/// Split a query into bare words and quoted phrases.
+/// Returns None when the quotes are unbalanced.
+fn tokenize_search_query(query: &str) -> Option<Vec<QueryToken>> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut chars = query.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            if !word.is_empty() {
+                tokens.push(QueryToken::Word(std::mem::take(&mut word)));
+            }
+            let mut phrase = String::new();
+            let mut closed = false;
+            for c in chars.by_ref() {
+                if c == '"' {
+                    closed = true;
+                    break;
+                }
+                phrase.push(c);
+            }
+            if !closed {
+                return None;
+            }
+            if !phrase.trim().is_empty() {
+                tokens.push(QueryToken::Phrase(phrase.trim().to_string()));
+            }
+        } else if c.is_whitespace() {
+            if !word.is_empty() {
+                tokens.push(QueryToken::Word(std::mem::take(&mut word)));
+            }
+        } else {
+            word.push(c);
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(QueryToken::Word(word));
+    }
+
+    Some(tokens)
+}
+
+/// Quote a term as an FTS5 string so punctuation and keywords are literal
+fn fts_quote(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Translate a user query into an FTS5 MATCH expression.
+///
+/// Bare words are ANDed, `"quoted text"` becomes a phrase, `OR` between two
+/// terms makes them alternatives and `tag:name` restricts results to notes
+/// carrying that tag. A query with unbalanced quotes is searched literally.
+pub fn parse_search_query(query: &str) -> ParsedQuery {
+    let (tokens, literal) = match tokenize_search_query(query) {
+        Some(tokens) => (tokens, false),
+        None => (
+            query
+                .split(|c: char| c.is_whitespace() || c == '"')
+                .filter(|word| !word.is_empty())
+                .map(|word| QueryToken::Word(word.to_string()))
+                .collect(),
+            true,
+        ),
+    };
+
+    let mut parsed = ParsedQuery::default();
+    // Each group is a set of OR alternatives; groups are ANDed together
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut pending_or = false;
+
+    for token in tokens {
+        let term = match token {
+            QueryToken::Word(word) if !literal && word == "AND" => continue,
+            QueryToken::Word(word) if !literal && word == "OR" => {
+                pending_or = !groups.is_empty();
+                continue;
+            }
+            QueryToken::Word(word) if !literal && word.to_lowercase().starts_with("tag:") => {
+                let tag = word[4..].trim_start_matches('#');
+                if !tag.is_empty() {
+                    parsed.tags.push(tag.to_string());
+                }
+                continue;
+            }
+            QueryToken::Word(term) | QueryToken::Phrase(term) => term,
+        };
+
+        // Terms without any word characters tokenize to nothing in FTS5
+        if !term.chars().any(char::is_alphanumeric) {
+            continue;
+        }
+
+        let quoted = fts_quote(&term);
+        match groups.last_mut() {
+            Some(group) if pending_or => group.push(quoted),
+            _ => groups.push(vec![quoted]),
+        }
+        pending_or = false;
+        parsed.terms.push(term);
+    }
+
+    if !groups.is_empty() {
+        let clauses: Vec<String> = groups
+            .into_iter()
+            .map(|group| {
+                if group.len() > 1 {
+                    format!("({})", group.join(" OR "))
+                } else {
+                    group.join("")
+                }
+            })
+            .collect();
+        parsed.fts_expression = Some(clauses.join(" AND "));
+    }
+
+    parsed
+}
+
+/// Match `pattern` against every line of every note. The pattern is used
+/// as written, so `(?i)` makes it case-insensitive.
+pub fn search_notes_regex(base_path: &str, pattern: &str) -> Result<RegexSearchResults, String> {
+    let regex = regex::Regex::new(pattern).map_err(|e| format!("Invalid regex: {e}"))?;
+
+    let notes = list_notes(base_path)?;
+    let files_scanned = notes.len();
+    let mut results = Vec::new();
+
+    for metadata in notes {
+        let note = match read_note(&metadata.path) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!("[SEARCH] ERROR reading file content {}: {e}", metadata.path);
+                continue;
+            }
+        };
+
+        let mut matches = Vec::new();
+        for (line_number, line) in note.content.lines().enumerate() {
+            let char_offset = |byte: usize| line[..byte].chars().count();
+            for caps in regex.captures_iter(line) {
+                let whole = caps.get(0).expect("group 0 is always present");
+                if whole.as_str().is_empty() {
+                    continue;
+                }
+                let match_start = char_offset(whole.start());
+
+                matches.push(RegexMatch {
+                    line_number: line_number + 1,
+                    text: line.to_string(),
+                    match_start,
+                    match_length: whole.as_str().chars().count(),
+                    captures: caps
+                        .iter()
+                        .skip(1)
+                        .map(|group| {
+                            group.map(|m| CaptureSpan {
+                                start: char_offset(m.start()),
+                                length: m.as_str().chars().count(),
+                            })
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        if !matches.is_empty() {
+            results.push(RegexSearchResult { note, matches });
+        }
+    }
+
+    Ok(RegexSearchResults {
+        results,
+        files_scanned,
+    })
+}
+
+/// Replace every literal occurrence of `query` in `content`, optionally
+/// leaving fenced code blocks alone. Returns the new content and the
+/// number of replacements.
+pub fn replace_literal(
+    content: &str,
+    query: &str,
+    replacement: &str,
+    skip_code_blocks: bool,
+) -> (String, usize) {
+    let mut result = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut in_code_block = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            if skip_code_blocks {
+                result.push_str(line);
+                continue;
+            }
+        }
+        if skip_code_blocks && in_code_block {
+            result.push_str(line);
+            continue;
+        }
+
+        count += line.matches(query).count();
+        result.push_str(&line.replace(query, replacement));
+    }
+
+    (result, count)
+}
+
+/// Search using only the FTS index: results carry a highlighted excerpt
+/// from SQLite instead of positions found by reading every matching file.
+/// Supports the same query syntax as `search_notes_enhanced`.
+pub fn search_notes_fast(
+    query: &str,
+    cache_db: &crate::cache::CacheDb,
+) -> Result<Vec<crate::cache::FtsSnippet>, String> {
+    let parsed = parse_search_query(query);
+    if parsed.fts_expression.is_none() && parsed.tags.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    cache_db.search_notes_fts_snippets(parsed.fts_expression.as_deref(), &parsed.tags)
+}
+
+/// Limits a search to one folder (relative to the notes directory,
+/// including subfolders) or to the notes carrying one tag
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SearchScope {
+    Folder(String),
+    Tag(String),
+}
+
 pub fn search_notes_enhanced(
-    _base_path: &str,
+    base_path: &str,
     query: &str,
+    scope: Option<&SearchScope>,
     cache_db: &crate::cache::CacheDb,
 ) -> Result<Vec<SearchResult>, String> {
-    // Use FTS5 to get matching note paths
-    let note_paths = cache_db.search_notes_fts(query)?;
+    let parsed = parse_search_query(query);
+    if parsed.fts_expression.is_none() && parsed.tags.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Use FTS5 to get matching note paths, ranked by relevance
+    let mut ranked_paths =
+        cache_db.search_notes_fts_filtered(parsed.fts_expression.as_deref(), &parsed.tags)?;
+
+    // Narrow to the scope before reading any notes for snippets
+    match scope {
+        Some(SearchScope::Folder(folder)) => {
+            validate_relative_folder_path(folder, true)?;
+            let folder_path = Path::new(base_path).join(folder.trim());
+            ranked_paths.retain(|(path, _)| Path::new(path).starts_with(&folder_path));
+        }
+        Some(SearchScope::Tag(tag)) => {
+            let tagged: std::collections::HashSet<String> = cache_db
+                .get_notes_by_tag(tag.trim().trim_start_matches('#'))?
+                .into_iter()
+                .collect();
+            ranked_paths.retain(|(path, _)| tagged.contains(path));
+        }
+        None => {}
+    }
 
     let mut results = Vec::new();
-    let query_lower = query.to_lowercase();
+    let terms_lower: Vec<String> = parsed.terms.iter().map(|t| t.to_lowercase()).collect();
 
-    for note_path in note_paths {
+    for (note_path, score) in ranked_paths {
         // Read the note
         match read_note(&note_path) {
             Ok(note) => {
-                // Extract snippets from the content
-                let snippets = extract_search_snippets(&note.content, &query_lower);
+                // Extract snippets for every searched term. FTS stems terms, so
+                // a note can match without containing the raw text; keep it
+                // even when no snippet is found.
+                let mut snippets: Vec<SearchSnippet> = terms_lower
+                    .iter()
+                    .flat_map(|term| extract_search_snippets(&note.content, term))
+                    .collect();
+                snippets.sort_by_key(|snippet| (snippet.line_number, snippet.match_start));
                 let match_count = snippets.len();
 
-                if match_count > 0 {
-                    results.push(SearchResult {
-                        note,
-                        match_count,
-                        snippets,
-                    });
-                }
+                results.push(SearchResult {
+                    note,
+                    match_count,
+                    snippets,
+                    score,
+                });
             }
             Err(e) => {
                 eprintln!("[SEARCH_ENHANCED] ERROR reading note {note_path}: {e}");
@@ -374,91 +1204,205 @@ pub fn search_notes_enhanced(
         }
     }
 
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
     Ok(results)
 }
 
+/// Characters of context kept on each side of a match in a snippet
+const SNIPPET_CONTEXT_CHARS: usize = 50;
+
+/// Cut a snippet out of `line` around the match between the given char
+/// offsets, with ellipses where the line was shortened
+fn snippet_around(
+    line: &str,
+    line_number: usize,
+    match_start_char: usize,
+    match_end_char: usize,
+) -> SearchSnippet {
+    // Byte offset of every char in the line, plus the end
+    let char_starts: Vec<usize> = line
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(line.len()))
+        .collect();
+    let total_chars = char_starts.len() - 1;
+
+    // Snippet boundaries are chosen in chars, then converted to byte
+    // offsets that always fall on a codepoint boundary
+    let snippet_start_char = match_start_char.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let snippet_end_char = (match_end_char + SNIPPET_CONTEXT_CHARS).min(total_chars);
+
+    let snippet_start = char_starts[snippet_start_char];
+    let snippet_end = char_starts[snippet_end_char];
+
+    // Extract the snippet text
+    let mut snippet_text = line[snippet_start..snippet_end].to_string();
+
+    // Add ellipsis if we're not at the start/end
+    let leading_ellipsis = snippet_start_char > 0;
+    if leading_ellipsis {
+        snippet_text = format!("...{snippet_text}");
+    }
+    if snippet_end_char < total_chars {
+        snippet_text = format!("{snippet_text}...");
+    }
+
+    // Match position within the snippet, in chars
+    let match_start_in_snippet =
+        match_start_char - snippet_start_char + if leading_ellipsis { 3 } else { 0 };
+
+    SearchSnippet {
+        line_number,
+        text: snippet_text,
+        match_start: match_start_in_snippet,
+        match_length: match_end_char - match_start_char,
+    }
+}
+
+/// Snippets around every `[[link]]` in `content` that points at one of
+/// `names` (a note's title and aliases), compared case-insensitively
+pub fn find_link_snippets(content: &str, names: &[String]) -> Vec<SearchSnippet> {
+    let link_regex = regex::Regex::new(r"!?\[\[([^\]|#]+)[^\]]*\]\]").unwrap();
+    let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+
+    let mut snippets = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        for caps in link_regex.captures_iter(line) {
+            let target = caps[1].trim().trim_end_matches(".md").to_lowercase();
+            if !names.contains(&target) {
+                continue;
+            }
+            let whole = caps.get(0).unwrap();
+            snippets.push(snippet_around(
+                line,
+                line_number + 1,
+                line[..whole.start()].chars().count(),
+                line[..whole.end()].chars().count(),
+            ));
+        }
+    }
+    snippets
+}
+
 fn extract_search_snippets(content: &str, query_lower: &str) -> Vec<SearchSnippet> {
     let mut snippets = Vec::new();
-    const CONTEXT_CHARS: usize = 50; // Characters of context on each side
+
+    if query_lower.is_empty() {
+        return snippets;
+    }
 
     for (line_number, line) in content.lines().enumerate() {
-        let line_lower = line.to_lowercase();
-        let query_len_lower = query_lower.len();
+        // Lowercase char by char, remembering which original char each
+        // lowercased char came from. Case-folding can change both byte and
+        // char lengths (e.g. Turkish İ -> i̇), so offsets into the lowercased
+        // line can't be reused on the original directly.
+        let mut line_lower = String::with_capacity(line.len());
+        let mut lower_to_original: Vec<usize> = Vec::with_capacity(line.len());
+        for (char_index, c) in line.chars().enumerate() {
+            for lower_c in c.to_lowercase() {
+                line_lower.push(lower_c);
+                lower_to_original.push(char_index);
+            }
+        }
 
-        // Find all matches in the lowercased line, then map byte offsets
-        // back to the original string via char counts to avoid panics when
-        // case-folding changes byte lengths (e.g. Turkish İ -> i̇).
         let mut search_start = 0;
         while let Some(match_pos_lower) = line_lower[search_start..].find(query_lower) {
-            let actual_pos_lower = search_start + match_pos_lower;
-
-            // Map byte offset in lowercased string to the original string
-            // by counting chars up to the match position, then finding the
-            // corresponding byte offset in the original.
-            let char_offset = line_lower[..actual_pos_lower].chars().count();
-            let actual_pos = line
-                .char_indices()
-                .nth(char_offset)
-                .map(|(i, _)| i)
-                .unwrap_or(line.len());
-
-            // Map the end of the match similarly
-            let match_end_char_offset = line_lower[..actual_pos_lower + query_len_lower]
-                .chars()
-                .count();
-            let match_end = line
-                .char_indices()
-                .nth(match_end_char_offset)
-                .map(|(i, _)| i)
-                .unwrap_or(line.len());
-
-            // Calculate snippet boundaries using char-aware offsets
-            let snippet_start_char = char_offset.saturating_sub(CONTEXT_CHARS);
-            let snippet_start = line
-                .char_indices()
-                .nth(snippet_start_char)
-                .map(|(i, _)| i)
-                .unwrap_or(0);
-
-            let total_chars = line.chars().count();
-            let snippet_end_char = (match_end_char_offset + CONTEXT_CHARS).min(total_chars);
-            let snippet_end = line
-                .char_indices()
-                .nth(snippet_end_char)
-                .map(|(i, _)| i)
-                .unwrap_or(line.len());
-
-            // Extract the snippet text
-            let mut snippet_text = line[snippet_start..snippet_end].to_string();
-
-            // Add ellipsis if we're not at the start/end
-            if snippet_start > 0 {
-                snippet_text = format!("...{snippet_text}");
-            }
-            if snippet_end < line.len() {
-                snippet_text = format!("{snippet_text}...");
-            }
+            let match_start_lower = search_start + match_pos_lower;
+            let match_end_lower = match_start_lower + query_lower.len();
+
+            // Map the match back to char offsets in the original line
+            let first_lower_char = line_lower[..match_start_lower].chars().count();
+            let last_lower_char = line_lower[..match_end_lower].chars().count() - 1;
+            let match_start_char = lower_to_original[first_lower_char];
+            let match_end_char = lower_to_original[last_lower_char] + 1;
+
+            snippets.push(snippet_around(
+                line,
+                line_number + 1, // 1-based line numbers
+                match_start_char,
+                match_end_char,
+            ));
+
+            // Move past this match in the lowercased string
+            search_start = match_end_lower;
+        }
+    }
 
-            // Calculate match position within the snippet
-            let match_start_in_snippet =
-                actual_pos - snippet_start + (if snippet_start > 0 { 3 } else { 0 });
+    snippets
+}
 
-            snippets.push(SearchSnippet {
-                line_number: line_number + 1, // 1-based line numbers
-                text: snippet_text,
-                match_start: match_start_in_snippet,
-                match_length: match_end - actual_pos,
+/// Find lines in other notes that mention any of `names` (a note's title and
+/// aliases) as a whole word without linking to it via `[[...]]`
+pub fn find_unlinked_mentions(
+    base_path: &str,
+    note_path: &str,
+    names: &[String],
+) -> Result<Vec<UnlinkedMention>, String> {
+    let mut names: Vec<&str> = names
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Prefer the longest name when one is a prefix of another
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+
+    let pattern = names
+        .iter()
+        .map(|name| regex::escape(name))
+        .collect::<Vec<_>>()
+        .join("|");
+    let mention_regex = regex::Regex::new(&format!("(?i){pattern}"))
+        .map_err(|e| format!("Failed to build mention pattern: {e}"))?;
+    let wikilink_regex = regex::Regex::new(r"\[\[[^\]]*\]\]").unwrap();
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut mentions = Vec::new();
+    for note in list_notes(base_path)? {
+        if note.path == note_path {
+            continue;
+        }
+        let content = match read_file_with_encoding(&note.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        for (line_index, line) in content.lines().enumerate() {
+            let links: Vec<_> = wikilink_regex.find_iter(line).map(|m| m.range()).collect();
+
+            let has_mention = mention_regex.find_iter(line).any(|m| {
+                let starts_word = !line[..m.start()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(is_word_char);
+                let ends_word = !line[m.end()..].chars().next().is_some_and(is_word_char);
+                let in_link = links
+                    .iter()
+                    .any(|link| m.start() < link.end && link.start < m.end());
+                starts_word && ends_word && !in_link
             });
 
-            // Move past this match in the lowercased string
-            search_start = actual_pos_lower + query_len_lower;
+            if has_mention {
+                mentions.push(UnlinkedMention {
+                    note_path: note.path.clone(),
+                    line_number: line_index + 1,
+                    snippet: line.trim().to_string(),
+                });
+            }
         }
     }
 
-    snippets
+    Ok(mentions)
 }
 
-fn validate_relative_folder_path(folder_path: &str, allow_root: bool) -> Result<(), String> {
+pub(crate) fn validate_relative_folder_path(
+    folder_path: &str,
+    allow_root: bool,
+) -> Result<(), String> {
     let trimmed = folder_path.trim();
 
     if trimmed.is_empty() {
@@ -543,6 +1487,8 @@ pub fn delete_folder(folder_path: &str, base_path: &str) -> Result<Vec<String>,
 
     let base = Path::new(base_path);
     let full_path = base.join(folder_path);
+    // A symlinked folder could still point outside the notes directory
+    validate_path_security(&full_path, base_path).map_err(|e| e.to_string())?;
 
     if !full_path.exists() {
         return Err("Folder does not exist".to_string());
@@ -566,15 +1512,25 @@ pub fn delete_folder(folder_path: &str, base_path: &str) -> Result<Vec<String>,
     Ok(relative_files)
 }
 
-pub fn delete_folder_confirmed(folder_path: &str, base_path: &str) -> Result<(), String> {
+/// Delete a folder and everything in it. Returns the paths of the notes
+/// that were deleted so their cache entries can be cleared.
+pub fn delete_folder_confirmed(folder_path: &str, base_path: &str) -> Result<Vec<String>, String> {
     validate_relative_folder_path(folder_path, false)?;
 
     let base = Path::new(base_path);
     let full_path = base.join(folder_path);
+    // A symlinked folder could still point outside the notes directory
+    validate_path_security(&full_path, base_path).map_err(|e| e.to_string())?;
+
+    let mut deleted_notes = Vec::new();
+    collect_files_recursive(&full_path, &mut deleted_notes)?;
 
     fs::remove_dir_all(&full_path).map_err(|e| format!("Failed to delete folder: {e}"))?;
 
-    Ok(())
+    Ok(deleted_notes
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
 }
 
 pub fn create_folder(folder_path: &str, base_path: &str) -> Result<(), String> {
@@ -615,9 +1571,12 @@ pub fn rename_note(old_path: &str, new_name: &str) -> Result<String, String> {
     // Create the new path
     let new_path = parent.join(&new_filename);
 
-    // Check if a file with the new name already exists
-    if new_path.exists() {
-        return Err("A note with this name already exists".to_string());
+    // Check if a file with the new name already exists, ignoring case so
+    // only a case change of this same note is allowed
+    if let Some(existing) = find_case_insensitive_match(&new_path) {
+        if existing != old_path_buf {
+            return Err("A note with this name already exists".to_string());
+        }
     }
 
     // Rename the file
@@ -626,6 +1585,31 @@ pub fn rename_note(old_path: &str, new_name: &str) -> Result<String, String> {
     Ok(new_path.to_string_lossy().to_string())
 }
 
+/// An existing file in the same folder whose name matches `path` ignoring
+/// case. Wikilinks resolve case-insensitively, so `Note.md` and `note.md`
+/// must not both exist.
+pub fn find_case_insensitive_match(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let matches: Vec<PathBuf> = fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            candidate.is_file()
+                && candidate
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().to_lowercase() == name)
+        })
+        .collect();
+
+    // Prefer the exact name if differently-cased copies already exist
+    matches
+        .iter()
+        .find(|candidate| candidate.as_path() == path)
+        .or(matches.first())
+        .cloned()
+}
+
 pub fn rename_folder(old_path: &str, new_name: &str, base_path: &str) -> Result<String, String> {
     validate_relative_folder_path(old_path, false)?;
     validate_folder_name(new_name)?;
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Deleted notes are kept here, mirroring their place in the vault
+const TRASH_DIR: &str = ".plainflux/trash";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub trash_path: String,
+    pub original_path: String,
+    pub title: String,
+}
+
+fn trash_root(notes_dir: &str) -> PathBuf {
+    Path::new(notes_dir).join(TRASH_DIR)
+}
+
+/// Move a note into the trash, keeping its path relative to the vault.
+/// A note already trashed under the same name gets a deletion timestamp.
+/// Returns the path inside the trash.
+pub fn move_to_trash(path: &str, notes_dir: &str) -> Result<String, String> {
+    let relative = Path::new(path)
+        .strip_prefix(notes_dir)
+        .map_err(|_| format!("'{path}' is outside the notes directory"))?;
+    if relative.starts_with(TRASH_DIR) {
+        return Err("Note is already in the trash".to_string());
+    }
+
+    let mut trash_path = trash_root(notes_dir).join(relative);
+    if trash_path.exists() {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let stem = trash_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled")
+            .to_string();
+        let extension = trash_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| format!(".{e}"))
+            .unwrap_or_default();
+
+        let mut counter = 1;
+        loop {
+            let suffix = if counter == 1 {
+                stamp.clone()
+            } else {
+                format!("{stamp}-{counter}")
+            };
+            trash_path.set_file_name(format!("{stem} (deleted {suffix}){extension}"));
+            if !trash_path.exists() {
+                break;
+            }
+            counter += 1;
+        }
+    }
+
+    if let Some(parent) = trash_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create trash folder: {e}"))?;
+    }
+    fs::rename(path, &trash_path).map_err(|e| format!("Failed to move note to trash: {e}"))?;
+
+    Ok(trash_path.to_string_lossy().to_string())
+}
+
+/// Move a trashed note back to where it was deleted from. Refuses to
+/// overwrite a note that has since been created at that path.
+pub fn restore_from_trash(trash_path: &str, notes_dir: &str) -> Result<String, String> {
+    let relative = trash_relative_path(trash_path, notes_dir)?;
+    if !Path::new(trash_path).is_file() {
+        return Err(format!("'{trash_path}' is not in the trash"));
+    }
+
+    let original_path = original_path(&relative, notes_dir);
+    if original_path.exists() {
+        return Err(format!(
+            "A note already exists at '{}'",
+            original_path.display()
+        ));
+    }
+
+    if let Some(parent) = original_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {e}"))?;
+    }
+    fs::rename(trash_path, &original_path)
+        .map_err(|e| format!("Failed to restore note from trash: {e}"))?;
+    remove_empty_trash_folders(Path::new(trash_path), notes_dir);
+
+    Ok(original_path.to_string_lossy().to_string())
+}
+
+/// Everything currently in the trash, sorted by original path
+pub fn list_trash(notes_dir: &str) -> Result<Vec<TrashEntry>, String> {
+    let root = trash_root(notes_dir);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(&root).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = match entry.path().strip_prefix(&root) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let original_path = original_path(relative, notes_dir);
+        let title = original_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled")
+            .to_string();
+
+        entries.push(TrashEntry {
+            trash_path: entry.path().to_string_lossy().to_string(),
+            original_path: original_path.to_string_lossy().to_string(),
+            title,
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        a.original_path
+            .cmp(&b.original_path)
+            .then_with(|| a.trash_path.cmp(&b.trash_path))
+    });
+    Ok(entries)
+}
+
+/// Permanently delete everything in the trash. Returns the number of
+/// files removed.
+pub fn empty_trash(notes_dir: &str) -> Result<usize, String> {
+    let root = trash_root(notes_dir);
+    if !root.exists() {
+        return Ok(0);
+    }
+
+    let count = WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .count();
+    fs::remove_dir_all(&root).map_err(|e| format!("Failed to empty trash: {e}"))?;
+
+    Ok(count)
+}
+
+/// Path of `trash_path` relative to the trash folder, rejecting anything
+/// outside it
+fn trash_relative_path(trash_path: &str, notes_dir: &str) -> Result<PathBuf, String> {
+    let path = Path::new(trash_path);
+    if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        return Err(format!("'{trash_path}' is not in the trash"));
+    }
+    path.strip_prefix(trash_root(notes_dir))
+        .map(Path::to_path_buf)
+        .map_err(|_| format!("'{trash_path}' is not in the trash"))
+}
+
+/// Where a trashed file came from, dropping any collision timestamp
+fn original_path(relative: &Path, notes_dir: &str) -> PathBuf {
+    let mut original = Path::new(notes_dir).join(relative);
+    let file_name = original
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let collision_suffix =
+        regex::Regex::new(r"^(.*) \(deleted \d{8}-\d{6}(?:-\d+)?\)(\.[^.]*)?$").unwrap();
+    if let Some(caps) = collision_suffix.captures(&file_name) {
+        let extension = caps.get(2).map_or("", |m| m.as_str());
+        original.set_file_name(format!("{}{extension}", &caps[1]));
+    }
+
+    original
+}
+
+/// Remove folders left empty inside the trash after a restore
+fn remove_empty_trash_folders(trash_path: &Path, notes_dir: &str) {
+    let root = trash_root(notes_dir);
+    let mut dir = trash_path.parent();
+    while let Some(current) = dir {
+        if !current.starts_with(&root) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
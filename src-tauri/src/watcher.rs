@@ -0,0 +1,141 @@
+use crate::commands::{get_file_mtime, AppState};
+use crate::note_manager::{self, read_file_with_encoding};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Quiet period after the last filesystem event before a burst is processed
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// Watch `notes_dir` recursively and keep the cache in sync with changes made
+/// outside the app (other editors, sync clients). Emits `notes-changed` with
+/// the affected paths after each debounced burst. Watching stops when the
+/// returned watcher is dropped.
+pub fn watch_notes_dir(app: AppHandle, notes_dir: &str) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = channel::<PathBuf>();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+        Err(e) => eprintln!("Warning: File watcher error: {e}"),
+    })
+    .map_err(|e| format!("Failed to create file watcher: {e}"))?;
+
+    watcher
+        .watch(Path::new(notes_dir), RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch notes directory: {e}"))?;
+
+    let notes_dir = notes_dir.to_string();
+    std::thread::spawn(move || {
+        // recv() fails once the watcher (and with it the sender) is dropped
+        while let Ok(first_path) = rx.recv() {
+            let mut pending = HashSet::new();
+            pending.insert(first_path);
+
+            // Collect the rest of the burst until things go quiet
+            loop {
+                match rx.recv_timeout(DEBOUNCE_DELAY) {
+                    Ok(path) => {
+                        pending.insert(path);
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let changed = apply_changes(&app, &notes_dir, pending);
+            if !changed.is_empty() {
+                if let Err(e) = app.emit("notes-changed", &changed) {
+                    eprintln!("Warning: Failed to emit notes-changed event: {e}");
+                }
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Update or clear cache entries for the given paths, returning the note
+/// paths whose cached state actually changed
+fn apply_changes(app: &AppHandle, notes_dir: &str, paths: HashSet<PathBuf>) -> Vec<String> {
+    let state = app.state::<AppState>();
+    let cache_db = lock_mutex!(
+        state.cache_db,
+        "Cache database mutex was poisoned during file watcher sync"
+    );
+    if state.notes_dir() != notes_dir {
+        // The vault was switched while this batch was debouncing
+        return Vec::new();
+    }
+
+    let mut changed = Vec::new();
+
+    for path in paths {
+        if path.extension().and_then(|s| s.to_str()) != Some("md") {
+            continue;
+        }
+
+        // Skip notes in hidden internal folders, like list_notes does
+        match path.strip_prefix(notes_dir) {
+            Ok(relative_path) if !note_manager::is_in_hidden_folder(relative_path) => {}
+            _ => continue,
+        }
+
+        let path_str = path.to_string_lossy().to_string();
+
+        if path.is_file() {
+            let file_mtime = match get_file_mtime(&path_str) {
+                Ok(mtime) => mtime,
+                Err(_) => continue,
+            };
+
+            // Writes made by the app itself are already indexed
+            if let Ok(Some(cached_mtime)) = cache_db.get_cached_mtime(&path_str) {
+                if cached_mtime == file_mtime {
+                    continue;
+                }
+            }
+
+            let content = match read_file_with_encoding(&path_str) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let title = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .unwrap_or("Untitled");
+
+            if let Err(e) =
+                cache_db.update_note_cache_with_fts(&path_str, title, &content, notes_dir)
+            {
+                eprintln!("Warning: Failed to update cache for '{path_str}': {e}");
+                continue;
+            }
+            if let Err(e) = cache_db.set_cached_mtime(&path_str, file_mtime.0, file_mtime.1) {
+                eprintln!("Warning: Failed to store mtime for '{path_str}': {e}");
+            }
+
+            changed.push(path_str);
+        } else if !path.exists() {
+            // Only report deletions of notes we actually had cached
+            if let Ok(Some(_)) = cache_db.get_cached_mtime(&path_str) {
+                if let Err(e) = cache_db.remove_stale_entries(std::slice::from_ref(&path_str)) {
+                    eprintln!("Warning: Failed to remove stale cache entry '{path_str}': {e}");
+                    continue;
+                }
+                changed.push(path_str);
+            }
+        }
+    }
+
+    changed
+}
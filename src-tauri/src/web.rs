@@ -0,0 +1,165 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Give up on a page after this long
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Stop reading a page after this many bytes; the head is all we need
+const MAX_PAGE_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PageMetadata {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Only plain web links are fetched
+pub fn validate_web_url(url: &str) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(parsed),
+        _ => Err(format!("Only http and https URLs can be fetched: {url}")),
+    }
+}
+
+/// GET `url` with the fetch timeout, reading at most `max_bytes` of the
+/// body. Returns the content type and the bytes read.
+pub async fn fetch_limited(
+    url: &url::Url,
+    max_bytes: usize,
+) -> Result<(Option<String>, Vec<u8>), String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(concat!("Plainflux/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read {url}: {e}"))?
+    {
+        let remaining = max_bytes - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+        if body.len() >= max_bytes {
+            break;
+        }
+    }
+
+    Ok((content_type, body))
+}
+
+/// Fetch a page's title and description. Anything that isn't HTML comes
+/// back with just the URL.
+pub async fn fetch_page_metadata(url: &str) -> Result<PageMetadata, String> {
+    let parsed = validate_web_url(url)?;
+    let (content_type, body) = fetch_limited(&parsed, MAX_PAGE_BYTES).await?;
+
+    let is_html = content_type.as_deref().is_none_or(|content_type| {
+        let content_type = content_type.to_ascii_lowercase();
+        content_type.starts_with("text/html") || content_type.starts_with("application/xhtml")
+    });
+    if !is_html {
+        return Ok(PageMetadata {
+            url: url.to_string(),
+            ..Default::default()
+        });
+    }
+
+    Ok(parse_page_metadata(url, &String::from_utf8_lossy(&body)))
+}
+
+/// Pull the `<title>` and description out of an HTML page, falling back to
+/// the Open Graph tags
+pub fn parse_page_metadata(url: &str, html: &str) -> PageMetadata {
+    static TITLE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+    static META_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap());
+    static ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"(?is)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+    });
+
+    let mut meta = std::collections::HashMap::new();
+    for tag in META_REGEX.find_iter(html) {
+        let mut key = None;
+        let mut content = None;
+        for caps in ATTRIBUTE_REGEX.captures_iter(tag.as_str()) {
+            let value = caps
+                .get(2)
+                .or(caps.get(3))
+                .or(caps.get(4))
+                .map_or("", |m| m.as_str());
+            match caps[1].to_ascii_lowercase().as_str() {
+                "name" | "property" => key = Some(value.to_ascii_lowercase()),
+                "content" => content = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        if let (Some(key), Some(content)) = (key, content) {
+            meta.entry(key).or_insert(content);
+        }
+    }
+
+    let clean = |text: &str| {
+        let text = decode_html_entities(text);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    };
+    let title = TITLE_REGEX
+        .captures(html)
+        .and_then(|caps| clean(&caps[1]))
+        .or_else(|| meta.get("og:title").and_then(|title| clean(title)));
+    let description = meta
+        .get("description")
+        .or_else(|| meta.get("og:description"))
+        .and_then(|description| clean(description));
+
+    PageMetadata {
+        url: url.to_string(),
+        title,
+        description,
+    }
+}
+
+/// Decode the entities that commonly show up in titles: the named XML ones
+/// and numeric references
+fn decode_html_entities(text: &str) -> String {
+    static ENTITY_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+    ENTITY_REGEX
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .unwrap_or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
@@ -110,7 +110,7 @@ export const tauriApi = {
     });
   },
 
-  async saveAttachment(fileData: Uint8Array, filename: string, notePath: string): Promise<string> {
+  async saveAttachment(fileData: Uint8Array, filename: string, notePath: string): Promise<SavedAttachment> {
     return invoke('save_attachment', {
       fileData: Array.from(fileData),
       filename,
@@ -231,6 +231,11 @@ export const tauriApi = {
   },
 };
 
+export interface SavedAttachment {
+  path: string; // relative to the note's folder
+  content_type: string;
+}
+
 export interface GraphNode {
   id: string;
   label: string;
@@ -67,7 +67,7 @@ export function createPasteHandler(notePath: string | undefined) {
                 markdownSyntax = `![${file.name}](${filePath})`;
               } else {
                 // Use new attachment handler for other files
-                filePath = await tauriApi.saveAttachment(fileData, file.name, notePath);
+                filePath = (await tauriApi.saveAttachment(fileData, file.name, notePath)).path;
                 markdownSyntax = `[${fileType.icon} ${file.name}](${filePath})`;
               }
 
@@ -163,7 +163,7 @@ export function createPasteHandler(notePath: string | undefined) {
                 markdownSyntax = `![${file.name}](${filePath})`;
               } else {
                 // Use new attachment handler for other files
-                filePath = await tauriApi.saveAttachment(fileData, file.name, notePath);
+                filePath = (await tauriApi.saveAttachment(fileData, file.name, notePath)).path;
                 markdownSyntax = `[${fileType.icon} ${file.name}](${filePath})`;
               }
 